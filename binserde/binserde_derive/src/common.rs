@@ -4,7 +4,7 @@ use darling::ast::{Fields, Style};
 use darling::{FromDeriveInput, FromField, FromVariant};
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{ConstParam, GenericParam, Generics, Ident, LifetimeDef, Type, TypeParam};
+use syn::{ConstParam, GenericParam, Generics, Ident, LifetimeDef, TypeParam};
 
 #[derive(FromDeriveInput, Debug)]
 #[darling(attributes(binserde), supports(struct_any, enum_any))]
@@ -12,6 +12,99 @@ pub struct BinSerdeOpts {
     pub ident: Ident,
     pub generics: Generics,
     pub data: darling::ast::Data<BinSerdeVariant, BinSerdeField>,
+    #[darling(default)]
+    pub repr: Option<Ident>,
+}
+
+impl BinSerdeOpts {
+    /// Parses the options from the derive input, panicking on invalid
+    /// attributes.
+    pub fn parse(ast: &syn::DeriveInput) -> Self {
+        let mut opts: BinSerdeOpts = FromDeriveInput::from_derive_input(ast).unwrap();
+
+        // darling doesn't give us the variant discriminants, take them from
+        // the syn input instead
+        if let (darling::ast::Data::Enum(variants), syn::Data::Enum(e)) =
+            (&mut opts.data, &ast.data)
+        {
+            for (variant, v) in variants.iter_mut().zip(e.variants.iter()) {
+                variant.discriminant = v.discriminant.as_ref().map(|(_, expr)| expr.clone());
+            }
+        }
+
+        opts.validate();
+        opts
+    }
+
+    fn validate(&self) {
+        match &self.data {
+            darling::ast::Data::Enum(variants) => {
+                for field in variants.iter().flat_map(|v| v.fields.iter()) {
+                    field.validate();
+                }
+            }
+            darling::ast::Data::Struct(fields) => {
+                if self.repr.is_some() {
+                    panic!("#[binserde(repr)] is only valid on enums");
+                }
+
+                for field in fields.iter() {
+                    field.validate();
+                }
+            }
+        }
+    }
+
+    /// Returns the expression for the tag written for each variant of an
+    /// enum. Without `repr`, this is the variant's index as a `usize`;
+    /// otherwise the variant's discriminant converted to the `repr` type,
+    /// following the same rules as Rust for variants without an explicit
+    /// discriminant.
+    pub fn variant_tags(&self, variants: &[BinSerdeVariant]) -> Vec<TokenStream> {
+        match &self.repr {
+            None => variants
+                .iter()
+                .enumerate()
+                .map(|(idx, _)| quote!(#idx))
+                .collect(),
+            Some(repr) => {
+                let mut base: Option<&syn::Expr> = None;
+                let mut offset = 0usize;
+
+                variants
+                    .iter()
+                    .map(|v| {
+                        if let Some(d) = &v.discriminant {
+                            base = Some(d);
+                            offset = 0;
+                        }
+
+                        let offset_lit = syn::LitInt::new(&offset.to_string(), Span::call_site());
+                        let value = match base {
+                            None => quote!(#offset_lit),
+                            Some(base) if offset == 0 => quote!(#base),
+                            Some(base) => quote!((#base) + #offset_lit),
+                        };
+
+                        offset += 1;
+
+                        quote!({
+                            const TAG: #repr = #value;
+                            TAG
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// The type the enum tag is written as.
+    pub fn tag_type(&self) -> TokenStream {
+        match &self.repr {
+            None => quote!(usize),
+            Some(repr) => quote!(#repr),
+        }
+    }
 }
 
 #[derive(FromVariant, Debug)]
@@ -19,19 +112,200 @@ pub struct BinSerdeOpts {
 pub struct BinSerdeVariant {
     pub ident: Ident,
     pub fields: Fields<BinSerdeField>,
+    #[darling(skip)]
+    pub discriminant: Option<syn::Expr>,
 }
 
 #[derive(FromField, Debug)]
 #[darling(attributes(binserde))]
 pub struct BinSerdeField {
     pub ident: Option<syn::Ident>,
-    pub ty: Type,
     #[darling(default)]
     pub no_dedup: bool,
     #[darling(default)]
     pub skip: bool,
     #[darling(default)]
+    pub default: Option<syn::Path>,
+    /// Not implemented yet, using it is an error. See [`serialize_order`].
+    #[darling(default)]
     pub index: Option<usize>,
+    #[darling(default)]
+    pub with_serde: bool,
+    #[darling(default)]
+    pub with: Option<syn::Path>,
+    #[darling(default)]
+    pub serialize_with: Option<syn::Path>,
+    #[darling(default)]
+    pub deserialize_with: Option<syn::Path>,
+    #[darling(default)]
+    pub endian: Option<String>,
+    #[darling(default)]
+    pub usize_len: Option<String>,
+    #[darling(default)]
+    pub varint: Option<bool>,
+}
+
+impl BinSerdeField {
+    fn validate(&self) {
+        let name = self
+            .ident
+            .as_ref()
+            .map_or_else(|| "tuple field".to_string(), |id| format!("field `{}`", id));
+        let codecs = [
+            self.with_serde,
+            self.with.is_some(),
+            self.serialize_with.is_some() || self.deserialize_with.is_some(),
+        ];
+
+        if codecs.iter().filter(|v| **v).count() > 1 {
+            panic!(
+                "{}: only one of with_serde, with and serialize_with/deserialize_with can be used",
+                name
+            );
+        }
+
+        if self.default.is_some() && !self.skip {
+            panic!("{}: default requires skip", name);
+        }
+
+        if self.index.is_some() {
+            panic!("{}: index is not implemented yet", name);
+        }
+
+        if let Err(e) = self.mode_changes() {
+            panic!("{}: {}", name, e);
+        }
+    }
+
+    /// The statements changing the mode this field is (de)serialized with,
+    /// applied to a `mode` variable.
+    fn mode_changes(&self) -> Result<Vec<TokenStream>, String> {
+        let mut changes = Vec::new();
+
+        if self.no_dedup {
+            changes.push(quote!(mode.use_dedup = false;));
+        }
+
+        if let Some(endian) = &self.endian {
+            let variant = match &**endian {
+                "little" => quote!(Little),
+                "big" => quote!(Big),
+                _ => {
+                    return Err(format!(
+                        "invalid endian `{}`, expected little or big",
+                        endian
+                    ))
+                }
+            };
+            changes.push(quote!(mode.endianness = ::binserde::Endianness::#variant;));
+        }
+
+        if let Some(len) = &self.usize_len {
+            let variant = match &**len {
+                "u8" => quote!(U8),
+                "u16" => quote!(U16),
+                "u32" => quote!(U32),
+                "u64" => quote!(U64),
+                "varint" => quote!(Variable),
+                _ => {
+                    return Err(format!(
+                        "invalid usize_len `{}`, expected u8, u16, u32, u64 or varint",
+                        len
+                    ))
+                }
+            };
+            changes.push(quote!(mode.usize_len = ::binserde::UsizeLen::#variant;));
+        }
+
+        if let Some(varint) = self.varint {
+            changes.push(quote!(mode.fixed_size_use_varint = #varint;));
+        }
+
+        Ok(changes)
+    }
+
+    /// The serializer expression this field is written with.
+    pub fn serializer(&self, serializer: TokenStream) -> TokenStream {
+        let changes = self.mode_changes().unwrap();
+
+        if changes.is_empty() {
+            serializer
+        } else {
+            quote!(::binserde::BinSerializer::change_mode(#serializer, |mode| { #( #changes )* }))
+        }
+    }
+
+    /// The deserializer expression this field is read with.
+    pub fn deserializer(&self, deserializer: TokenStream) -> TokenStream {
+        let changes = self.mode_changes().unwrap();
+
+        if changes.is_empty() {
+            deserializer
+        } else {
+            quote!(::binserde::BinDeserializer::change_mode(#deserializer, |mode| { #( #changes )* }))
+        }
+    }
+
+    /// Whether this field is deserialized by something other than its
+    /// `BinDeserialize` implementation.
+    fn custom_deserialize(&self) -> bool {
+        self.with_serde || self.with.is_some() || self.deserialize_with.is_some()
+    }
+
+    /// Generates the statement serializing `value` (a reference to this
+    /// field's value) into `serializer`.
+    pub fn gen_serialize(&self, value: TokenStream, serializer: TokenStream) -> TokenStream {
+        let serializer = self.serializer(serializer);
+
+        if let Some(path) = &self.serialize_with {
+            quote!(#path(#value, #serializer)?;)
+        } else if let Some(path) = &self.with {
+            quote!(#path::serialize(#value, #serializer)?;)
+        } else if self.with_serde {
+            quote!(::binserde::serde_bridge::serialize(#value, #serializer)?;)
+        } else {
+            quote!(::binserde::BinSerialize::serialize(#value, #serializer)?;)
+        }
+    }
+
+    /// Generates the expression deserializing this field from `deserializer`.
+    pub fn gen_deserialize(&self, deserializer: TokenStream) -> TokenStream {
+        let deserializer = self.deserializer(deserializer);
+
+        if let Some(path) = &self.deserialize_with {
+            quote!(#path(#deserializer)?)
+        } else if let Some(path) = &self.with {
+            quote!(#path::deserialize(#deserializer)?)
+        } else if self.with_serde {
+            quote!(::binserde::serde_bridge::deserialize(#deserializer)?)
+        } else {
+            quote!(::binserde::BinDeserialize::deserialize(#deserializer)?)
+        }
+    }
+
+    /// Generates the statement deserializing this field from `deserializer`
+    /// into `place` (a mutable reference to the field).
+    pub fn gen_deserialize_in_place(
+        &self,
+        place: TokenStream,
+        deserializer: TokenStream,
+    ) -> TokenStream {
+        if self.custom_deserialize() {
+            let expr = self.gen_deserialize(deserializer);
+            quote!(*#place = #expr;)
+        } else {
+            let deserializer = self.deserializer(deserializer);
+            quote!(::binserde::BinDeserialize::deserialize_in_place(#place, #deserializer)?;)
+        }
+    }
+
+    /// Generates the expression producing the value of a skipped field.
+    pub fn gen_default(&self) -> TokenStream {
+        match &self.default {
+            None => quote!(::std::default::Default::default()),
+            Some(path) => quote!(#path()),
+        }
+    }
 }
 
 pub enum StructField<'a> {
@@ -48,35 +322,27 @@ impl ToTokens for StructField<'_> {
     }
 }
 
-pub fn to_struct_fields(fields: &Fields<BinSerdeField>, skip: bool) -> Vec<StructField> {
-    let iter = fields
-        .iter()
-        .enumerate()
-        .filter(|(_, el)| !skip || !el.skip);
-
+pub fn to_struct_fields(fields: &Fields<BinSerdeField>) -> Vec<StructField<'_>> {
     match fields.style {
-        Style::Tuple => iter
-            .map(|(idx, _el)| StructField::Tuple(syn::Index::from(idx)))
+        Style::Tuple => (0..fields.len())
+            .map(|idx| StructField::Tuple(syn::Index::from(idx)))
             .collect(),
-        Style::Struct => iter
-            .map(|(_, el)| StructField::Struct(el.ident.as_ref().unwrap()))
+        Style::Struct => fields
+            .iter()
+            .map(|el| StructField::Struct(el.ident.as_ref().unwrap()))
             .collect(),
         Style::Unit => vec![],
     }
 }
 
-pub fn to_idents(fields: &Fields<BinSerdeField>, skip: bool) -> Vec<Cow<Ident>> {
-    let iter = fields
-        .iter()
-        .enumerate()
-        .filter(|(_, el)| !skip || !el.skip);
-
+pub fn to_idents(fields: &Fields<BinSerdeField>) -> Vec<Cow<'_, Ident>> {
     match fields.style {
-        Style::Tuple => iter
-            .map(|(idx, _el)| Cow::Owned(Ident::new(&format!("v{}", idx), Span::call_site())))
+        Style::Tuple => (0..fields.len())
+            .map(|idx| Cow::Owned(Ident::new(&format!("v{}", idx), Span::call_site())))
             .collect(),
-        Style::Struct => iter
-            .map(|(_, el)| Cow::Borrowed(el.ident.as_ref().unwrap()))
+        Style::Struct => fields
+            .iter()
+            .map(|el| Cow::Borrowed(el.ident.as_ref().unwrap()))
             .collect(),
         Style::Unit => Vec::new(),
     }
@@ -119,51 +385,21 @@ pub fn add_trait_bounds(opts: &BinSerdeOpts, bound: &TokenStream) -> TokenStream
     quote!(#prefix #( #v ),*)
 }
 
-fn move_sort<T, F>(slice: &mut [T], op: F)
-where
-    F: Fn(&T) -> Option<usize>,
-{
-    let more = slice.len();
-    let mut idx = 0;
-
-    while more > 0 {
-        let d = &slice[idx];
-        let new_idx = op(d);
-
-        if let Some(new_idx) = new_idx {
-            if new_idx < idx {}
-            idx = new_idx;
-        }
-
-        idx += 1;
-    }
+/// Returns the indices of the fields that get serialized, in the order they
+/// are serialized in.
+// TODO: apply #[binserde(index = n)], which changes the order fields are
+// serialized in. swap_at is meant for moving the fields.
+pub fn serialize_order(fields: &Fields<BinSerdeField>) -> Vec<usize> {
+    (0..fields.fields.len())
+        .filter(|&idx| !fields.fields[idx].skip)
+        .collect()
 }
 
+/// Swaps the parts of the slice before and after `idx`, keeping the order of
+/// the elements inside each part.
+#[cfg(test)]
 fn swap_at<T>(slice: &mut [T], idx: usize) {
-    if idx == 0 || idx == slice.len() {
-        return;
-    }
-
-    let (left, right) = slice.split_at_mut(idx);
-
-    if left.len() == right.len() {
-        left.swap_with_slice(right);
-    } else if left.len() < right.len() {
-        swap_outer(slice, idx);
-        let len = slice.len() - idx;
-        swap_at(&mut slice[..len], idx);
-    } else if right.len() < left.len() {
-        let count = slice.len() - idx;
-        swap_outer(slice, count);
-        swap_at(&mut slice[idx..], count);
-    }
-}
-
-fn swap_outer<T>(slice: &mut [T], count: usize) {
-    let (left, right) = slice.split_at_mut(count);
-    let i = right.len() - count;
-    let right = &mut right[i..];
-    left.swap_with_slice(right);
+    slice.rotate_left(idx);
 }
 
 #[test]
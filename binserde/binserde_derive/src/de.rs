@@ -1,7 +1,6 @@
 use darling::ast::{Data, Fields, Style};
 use quote::quote;
 use proc_macro2::TokenStream;
-use syn::Index;
 
 use crate::common::*;
 
@@ -15,7 +14,9 @@ pub fn impl_bin_deserialize(opts: &BinSerdeOpts) -> TokenStream {
             let body = gen_deserialize_in_place_method_body(fields);
             quote! {
                 fn deserialize_in_place<D: ::binserde::BinDeserializer<'de>>(&mut self, mut deserializer: D) -> ::binserde::Result<()> {
-                    #body
+                    ::binserde::BinDeserializer::nested(&mut deserializer, |deserializer| {
+                        #body
+                    })
                 }
             }
         }
@@ -28,7 +29,9 @@ pub fn impl_bin_deserialize(opts: &BinSerdeOpts) -> TokenStream {
     let gen = quote! {
         impl #generic_defs ::binserde::BinDeserialize<'de> for #name #generic_params #where_clause {
             fn deserialize<D: ::binserde::BinDeserializer<'de>>(mut deserializer: D) -> ::binserde::Result<Self> {
-                #deserialize_body
+                ::binserde::BinDeserializer::nested(&mut deserializer, |deserializer| {
+                    #deserialize_body
+                })
             }
 
             #deserialize_in_place_m
@@ -40,7 +43,7 @@ pub fn impl_bin_deserialize(opts: &BinSerdeOpts) -> TokenStream {
 
 fn gen_deserialize_method_body(opts: &BinSerdeOpts) -> TokenStream {
     fn gen_struct_like(struct_like: TokenStream, fields: &Fields<BinSerdeField>) -> TokenStream {
-        let idents = to_idents(fields, false);
+        let idents = to_idents(fields);
 
         let fields_list = quote! { #( #idents ),* };
         let struct_value = match fields.style {
@@ -49,32 +52,32 @@ fn gen_deserialize_method_body(opts: &BinSerdeOpts) -> TokenStream {
             Style::Unit => quote! { #struct_like },
         };
 
-        let exprs = fields.iter().map(|el| {
-            if el.skip {
-                quote!(Default::default())
-            } else {
-                let mut expr = quote!(&mut deserializer);
-
-                if el.no_dedup {
-                    expr = quote!(::binserde::BinDeserializer::disable_dedup(#expr));
-                }
-
-                quote!( ::binserde::BinDeserialize::deserialize( #expr )? )
-            }
+        let defaults = fields
+            .iter()
+            .zip(idents.iter())
+            .filter(|(el, _)| el.skip)
+            .map(|(el, ident)| {
+                let expr = el.gen_default();
+                quote!(let #ident = #expr;)
+            });
+        let reads = serialize_order(fields).into_iter().map(|idx| {
+            let ident = &idents[idx];
+            let expr = fields.fields[idx].gen_deserialize(quote!(&mut *deserializer));
+            quote!(let #ident = #expr;)
         });
 
         quote! {
-            #( let #idents = #exprs; )*
+            #( #reads )*
+            #( #defaults )*
             Ok( #struct_value )
         }
     }
 
-    fn gen_variant_impl(index: usize, variant: &BinSerdeVariant) -> TokenStream {
+    fn gen_variant_impl(tag: &TokenStream, variant: &BinSerdeVariant) -> TokenStream {
         let name = &variant.ident;
-        let index = Index::from(index);
         let g = gen_struct_like(quote!(Self::#name), &variant.fields);
         quote! {
-            #index => { #g }
+            x if x == #tag => { #g }
         }
     }
 
@@ -86,12 +89,14 @@ fn gen_deserialize_method_body(opts: &BinSerdeOpts) -> TokenStream {
             }
         }
         Data::Enum(variants) => {
+            let tags = opts.variant_tags(variants);
+            let tag_type = opts.tag_type();
             let variants = variants
                 .iter()
-                .enumerate()
-                .map(|(idx, el)| gen_variant_impl(idx, el));
+                .zip(tags.iter())
+                .map(|(el, tag)| gen_variant_impl(tag, el));
             quote! {
-                match <usize as ::binserde::BinDeserialize>::deserialize(&mut deserializer)? {
+                match <#tag_type as ::binserde::BinDeserialize>::deserialize(&mut *deserializer)? {
                     #( #variants )*
                     x @ _ => Err(::binserde::Error::custom(&format!("invalid variant {}", x))),
                 }
@@ -102,24 +107,25 @@ fn gen_deserialize_method_body(opts: &BinSerdeOpts) -> TokenStream {
 }
 
 fn gen_deserialize_in_place_method_body(fields: &Fields<BinSerdeField>) -> TokenStream {
-    let idents = to_struct_fields(fields, false);
-
-    let exprs = fields.iter().zip(idents.iter()).map(|(el, field)| {
-        if el.skip {
-            quote!(self.#field = Default::default();)
-        } else {
-            let mut expr = quote!(&mut deserializer);
-
-            if el.no_dedup {
-                expr = quote!(::binserde::BinDeserializer::disable_dedup(#expr));
-            }
-
-            quote!( ::binserde::BinDeserialize::deserialize_in_place( &mut self.#field, #expr )?; )
-        }
+    let idents = to_struct_fields(fields);
+
+    let defaults = fields
+        .iter()
+        .zip(idents.iter())
+        .filter(|(el, _)| el.skip)
+        .map(|(el, field)| {
+            let expr = el.gen_default();
+            quote!(self.#field = #expr;)
+        });
+    let reads = serialize_order(fields).into_iter().map(|idx| {
+        let field = &idents[idx];
+        fields.fields[idx]
+            .gen_deserialize_in_place(quote!(&mut self.#field), quote!(&mut *deserializer))
     });
 
     quote! {
-        #( #exprs )*
+        #( #reads )*
+        #( #defaults )*
         Ok(())
     }
 }
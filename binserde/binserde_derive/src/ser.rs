@@ -1,5 +1,3 @@
-use std::borrow::Cow;
-
 use darling::ast::{Data, Fields, Style};
 use quote::quote;
 
@@ -9,7 +7,7 @@ use proc_macro2::TokenStream;
 pub fn impl_bin_serialize(opts: &BinSerdeOpts) -> TokenStream {
     let name = &opts.ident;
     let body = match &opts.data {
-        Data::Enum(variants) => gen_variants(&variants),
+        Data::Enum(variants) => gen_variants(opts, variants),
         Data::Struct(s) => gen_serialize_fields(s),
     };
 
@@ -29,30 +27,26 @@ pub fn impl_bin_serialize(opts: &BinSerdeOpts) -> TokenStream {
 }
 
 fn gen_serialize_fields(fields: &Fields<BinSerdeField>) -> TokenStream {
-    let idents = to_struct_fields(fields, true);
-
-    let serializers = fields.iter().filter(|el| !el.skip).map(|el| {
-        let mut expr = quote!(&mut serializer);
-
-        if el.no_dedup {
-            expr = quote!(::binserde::BinSerializer::disable_dedup(#expr));
-        }
+    let idents = to_struct_fields(fields);
 
-        expr
+    let stmts = serialize_order(fields).into_iter().map(|idx| {
+        let ident = &idents[idx];
+        fields.fields[idx].gen_serialize(quote!(&self.#ident), quote!(&mut serializer))
     });
 
     quote! {
-        #( ::binserde::BinSerialize::serialize(&self.#idents, #serializers )?; )*
+        #( #stmts )*
         Ok(())
     }
 }
 
-fn gen_variants(variants: &[BinSerdeVariant]) -> TokenStream {
+fn gen_variants(opts: &BinSerdeOpts, variants: &[BinSerdeVariant]) -> TokenStream {
     if !variants.is_empty() {
+        let tags = opts.variant_tags(variants);
         let variants = variants
             .iter()
-            .enumerate()
-            .map(|(idx, el)| gen_variant_impl(idx, el));
+            .zip(tags.iter())
+            .map(|(el, tag)| gen_variant_impl(tag, el));
         quote! {
             match self {
                 #( #variants )*
@@ -65,28 +59,37 @@ fn gen_variants(variants: &[BinSerdeVariant]) -> TokenStream {
     }
 }
 
-fn gen_variant_impl(idx: usize, variant: &BinSerdeVariant) -> TokenStream {
+fn gen_variant_impl(tag: &TokenStream, variant: &BinSerdeVariant) -> TokenStream {
     let name = &variant.ident;
     let fs = &variant.fields;
-    let (args, fields) = match variant.fields.style {
-        Style::Tuple => {
-            let idents = to_idents(fs, true);
-            (quote! { ( #( #idents ),* ) }, idents)
+    let idents = to_idents(fs);
+    let bindings = fs.iter().zip(idents.iter()).map(|(el, ident)| {
+        if el.skip {
+            quote!(_)
+        } else {
+            quote!(#ident)
         }
+    });
+    let args = match fs.style {
+        Style::Tuple => quote! { ( #( #bindings ),* ) },
         Style::Struct => {
-            let idents: Vec<_> = fs
+            let idents = fs
                 .iter()
-                .filter(|el| !el.skip)
-                .map(|el| Cow::Borrowed(el.ident.as_ref().unwrap()))
-                .collect();
-            (quote! { { #( #idents ),* } }, idents)
+                .zip(idents.iter())
+                .filter(|(el, _)| !el.skip)
+                .map(|(_, ident)| ident);
+            quote! { { #( #idents, )* .. } }
         }
-        Style::Unit => (quote!(), vec![]),
+        Style::Unit => quote!(),
     };
+    let stmts = serialize_order(fs).into_iter().map(|idx| {
+        let ident = &idents[idx];
+        fs.fields[idx].gen_serialize(quote!(#ident), quote!(&mut serializer))
+    });
     quote! {
         Self::#name #args => {
-            ::binserde::BinSerialize::serialize(&#idx, &mut serializer)?;
-            #( ::binserde::BinSerialize::serialize(&#fields, &mut serializer)?; )*
+            ::binserde::BinSerialize::serialize(&#tag, &mut serializer)?;
+            #( #stmts )*
             Ok(())
         }
     }
@@ -1,8 +1,10 @@
 use std::io::Read;
 
 use crate::dedup::DedupContext;
+use crate::lazy::LazySource;
 use crate::serde::Mode;
-use crate::Result;
+use crate::share::ReadShareContext;
+use crate::{Error, Result};
 
 pub trait BinDeserialize<'de>: Sized {
     fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self>;
@@ -23,8 +25,43 @@ pub trait BinDeserializer<'de>: Sized {
 
     fn dedup(&self) -> &'de DedupContext;
 
+    /// Returns the context for reading shared pointers back. Deserializers
+    /// that don't track them return `None`, and then fail on pointers that
+    /// refer to a pointee read before.
+    fn share(&mut self) -> Option<&mut ReadShareContext> {
+        None
+    }
+
     fn mode(&self) -> Mode;
 
+    /// Returns the current nesting depth, tracked by [`nested`](Self::nested).
+    /// Deserializers that don't track it return `None`, which makes them
+    /// ignore [`Limits::max_depth`](crate::Limits::max_depth).
+    fn depth_mut(&mut self) -> Option<&mut usize> {
+        None
+    }
+
+    /// Returns the source that [`Lazy`](crate::lazy::Lazy) values can be
+    /// decoded from later, if the input can be seeked in.
+    fn lazy_source(&self) -> Option<&LazySource> {
+        None
+    }
+
+    /// Runs `op` one nesting level deeper, failing with [`Error::TooDeep`] if
+    /// that would exceed [`Limits::max_depth`](crate::Limits::max_depth).
+    /// Implementations of types that can contain other values should
+    /// deserialize their contents through this.
+    fn nested<T, F>(&mut self, op: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        let max_depth = self.mode().limits.max_depth;
+        enter(self.depth_mut(), max_depth)?;
+        let result = op(self);
+        leave(self.depth_mut());
+        result
+    }
+
     fn with_mode(self, mode: Mode) -> WithMode<Self> {
         WithMode {
             deserializer: self,
@@ -46,6 +83,28 @@ pub trait BinDeserializer<'de>: Sized {
     }
 }
 
+/// Increments `depth`, failing with [`Error::TooDeep`] if it is already at
+/// `max_depth`.
+pub(crate) fn enter(depth: Option<&mut usize>, max_depth: Option<usize>) -> Result<()> {
+    if let Some(depth) = depth {
+        if let Some(max_depth) = max_depth {
+            if *depth >= max_depth {
+                return Err(Error::TooDeep(max_depth));
+            }
+        }
+
+        *depth += 1;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn leave(depth: Option<&mut usize>) {
+    if let Some(depth) = depth {
+        *depth -= 1;
+    }
+}
+
 impl<'de, T> BinDeserializer<'de> for &mut T
 where
     T: BinDeserializer<'de>,
@@ -60,19 +119,50 @@ where
         (**self).dedup()
     }
 
+    fn share(&mut self) -> Option<&mut ReadShareContext> {
+        (**self).share()
+    }
+
     fn mode(&self) -> Mode {
         (**self).mode()
     }
+
+    fn depth_mut(&mut self) -> Option<&mut usize> {
+        (**self).depth_mut()
+    }
+
+    fn lazy_source(&self) -> Option<&LazySource> {
+        (**self).lazy_source()
+    }
 }
 
 pub struct BinDeserializerBase<'de, R> {
     pipe: R,
     dedup: &'de DedupContext,
+    share: ReadShareContext,
+    depth: usize,
+    lazy: Option<LazySource>,
 }
 
 impl<'de, R> BinDeserializerBase<'de, R> {
     pub fn new(pipe: R, dedup: &'de DedupContext) -> Self {
-        BinDeserializerBase { pipe, dedup }
+        BinDeserializerBase {
+            pipe,
+            dedup,
+            share: ReadShareContext::new(),
+            depth: 0,
+            lazy: None,
+        }
+    }
+
+    pub(crate) fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub(crate) fn with_lazy_source(mut self, source: LazySource) -> Self {
+        self.lazy = Some(source);
+        self
     }
 }
 
@@ -90,9 +180,21 @@ where
         self.dedup
     }
 
+    fn share(&mut self) -> Option<&mut ReadShareContext> {
+        Some(&mut self.share)
+    }
+
     fn mode(&self) -> Mode {
         Mode::default()
     }
+
+    fn depth_mut(&mut self) -> Option<&mut usize> {
+        Some(&mut self.depth)
+    }
+
+    fn lazy_source(&self) -> Option<&LazySource> {
+        self.lazy.as_ref()
+    }
 }
 
 pub struct WithMode<D> {
@@ -114,7 +216,19 @@ where
         self.deserializer.dedup()
     }
 
+    fn share(&mut self) -> Option<&mut ReadShareContext> {
+        self.deserializer.share()
+    }
+
     fn mode(&self) -> Mode {
         self.mode
     }
+
+    fn depth_mut(&mut self) -> Option<&mut usize> {
+        self.deserializer.depth_mut()
+    }
+
+    fn lazy_source(&self) -> Option<&LazySource> {
+        self.deserializer.lazy_source()
+    }
 }
@@ -1,7 +1,7 @@
 use std::io::{Read, Write};
 
 use crate::de::{BinDeserializer, BinDeserializerBase};
-use crate::serde::UsizeLen;
+use crate::serde::{Endianness, Limits, UsizeLen};
 use crate::Result;
 use crate::{BinDeserialize, BinSerializer, BinSerializerBase, Mode};
 use crate::util::serialize_iter;
@@ -10,7 +10,9 @@ const DEDUP_MODE: Mode = Mode {
     usize_len: UsizeLen::Variable,
     dedup_idx: UsizeLen::Variable,
     fixed_size_use_varint: false,
+    endianness: Endianness::Little,
     use_dedup: false,
+    limits: Limits::none(),
 };
 
 pub struct DedupContext {
@@ -18,6 +20,12 @@ pub struct DedupContext {
     by_index: Vec<usize>,
 }
 
+impl Default for DedupContext {
+    fn default() -> Self {
+        DedupContext::new()
+    }
+}
+
 impl DedupContext {
     pub fn new() -> Self {
         DedupContext {
@@ -52,8 +60,15 @@ impl DedupContext {
     }
 
     pub fn read_from<R: Read>(pipe: R) -> Result<Self> {
+        DedupContext::read_from_limited(pipe, Limits::none())
+    }
+
+    pub fn read_from_limited<R: Read>(pipe: R, limits: Limits) -> Result<Self> {
         let empty = DedupContext::new();
-        let de = BinDeserializerBase::new(pipe, &empty).with_mode(DEDUP_MODE);
+        let de = BinDeserializerBase::new(pipe, &empty).with_mode(Mode {
+            limits,
+            ..DEDUP_MODE
+        });
 
         let by_index: Vec<String> = Vec::deserialize(de)?;
         let mut strings: Vec<_> = by_index
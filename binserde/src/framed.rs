@@ -0,0 +1,338 @@
+//! Length-delimited message framing, for sending multiple binserde messages
+//! over one stream.
+//!
+//! Each frame consists of the length of the payload as a variable length
+//! integer, followed by the payload, which is the value serialized with
+//! [`serialize_with`](crate::serialize_with) (including the string table if
+//! deduplication is enabled). Since every frame is self-contained, frames can
+//! be read independently of each other.
+//!
+//! The functions in this module work with blocking [`Read`]/[`Write`] pipes.
+//! With the `tokio` or `futures` feature, the [`tokio`](self::tokio) and
+//! [`futures`](self::futures) modules provide the same functions for the
+//! respective `AsyncRead`/`AsyncWrite` traits. Values are always serialized
+//! into a buffer first, so the serializer itself never blocks.
+//!
+//! When reading, the frame length is checked against
+//! [`Limits::max_total_bytes`](crate::Limits::max_total_bytes) of the mode
+//! before the payload is read, and the buffer for the payload only grows as
+//! the data arrives, so a corrupt or malicious length prefix can't cause a
+//! huge allocation, even without limits.
+
+use std::convert::TryInto;
+use std::io;
+use std::io::{Cursor, Read, Write};
+
+use crate::de::BinDeserializeOwned;
+use crate::write_ext::{ReadExt, WriteExt};
+use crate::{BinSerialize, Error, Mode, Result};
+
+/// Writes `value` to `pipe` as a single frame.
+pub fn write_frame<W, T>(mut pipe: W, value: &T, mode: Mode) -> Result<()>
+where
+    W: Write,
+    T: BinSerialize + ?Sized,
+{
+    let buf = encode_frame(value, mode)?;
+    pipe.write_all(&buf)?;
+    Ok(())
+}
+
+/// Reads a single frame from `pipe`. Returns `None` if the end of the stream
+/// was reached before the start of the frame.
+pub fn read_frame<R, T>(mut pipe: R, mode: Mode) -> Result<Option<T>>
+where
+    R: Read,
+    T: BinDeserializeOwned,
+{
+    let mut first = [0];
+
+    if read_or_eof(&mut pipe, &mut first)? {
+        return Ok(None);
+    }
+
+    let len = (&first[..]).chain(&mut pipe).read_varuint()?;
+    let len = check_len(len, mode)?;
+    let mut buf = Vec::new();
+    pipe.take(len as u64).read_to_end(&mut buf)?;
+    check_eof(&buf, len)?;
+
+    decode_frame(&buf, mode).map(Some)
+}
+
+fn read_or_eof<R: Read>(mut pipe: R, buf: &mut [u8; 1]) -> Result<bool> {
+    loop {
+        match pipe.read(buf) {
+            Ok(0) => return Ok(true),
+            Ok(_) => return Ok(false),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Serializes `value` into a buffer containing the length prefix followed by
+/// the payload.
+fn encode_frame<T>(value: &T, mode: Mode) -> Result<Vec<u8>>
+where
+    T: BinSerialize + ?Sized,
+{
+    let payload = crate::serialize_with(value, mode)?;
+    let mut buf = Vec::with_capacity(payload.len() + 10);
+    buf.write_varusize(payload.len())?;
+    buf.extend_from_slice(&payload);
+    Ok(buf)
+}
+
+fn check_len(len: u64, mode: Mode) -> Result<usize> {
+    if let Some(max) = mode.limits.max_total_bytes {
+        if len > max {
+            return Err(Error::InputTooLong(max));
+        }
+    }
+
+    Ok(len.try_into()?)
+}
+
+/// Fails if fewer than `len` bytes of the payload could be read.
+fn check_eof(buf: &[u8], len: usize) -> Result<()> {
+    if buf.len() != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+
+    Ok(())
+}
+
+fn decode_frame<T>(buf: &[u8], mode: Mode) -> Result<T>
+where
+    T: BinDeserializeOwned,
+{
+    let mut cursor = Cursor::new(buf);
+    let value = crate::deserialize_with_from(&mut cursor, mode)?;
+
+    if cursor.position() != buf.len() as u64 {
+        return Err(Error::custom(format!(
+            "{} trailing bytes in frame",
+            buf.len() as u64 - cursor.position()
+        )));
+    }
+
+    Ok(value)
+}
+
+/// Collects the bytes of a varint length prefix, one at a time.
+#[cfg(any(feature = "tokio", feature = "futures"))]
+#[derive(Default)]
+struct LenPrefix {
+    buf: [u8; 10],
+    len: usize,
+}
+
+#[cfg(any(feature = "tokio", feature = "futures"))]
+impl LenPrefix {
+    /// Adds a byte of the prefix. Returns the decoded length once the last
+    /// byte has been pushed.
+    fn push(&mut self, b: u8) -> Result<Option<u64>> {
+        if self.len == self.buf.len() {
+            return Err(Error::InvalidVarInt);
+        }
+
+        self.buf[self.len] = b;
+        self.len += 1;
+
+        if b & 0x80 == 0 {
+            Ok(Some((&self.buf[..self.len]).read_varuint()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "futures"))]
+macro_rules! impl_async_framed {
+    ($read:path, $read_ext:path, $write:path, $write_ext:path) => {
+        use $read_ext as _;
+        use $write_ext as _;
+
+        use crate::de::BinDeserializeOwned;
+        use crate::framed::{check_eof, check_len, decode_frame, encode_frame, LenPrefix};
+        use crate::{BinSerialize, Mode, Result};
+
+        /// Writes `value` to `pipe` as a single frame.
+        pub async fn write_frame<W, T>(pipe: &mut W, value: &T, mode: Mode) -> Result<()>
+        where
+            W: $write + Unpin + ?Sized,
+            T: BinSerialize + ?Sized,
+        {
+            let buf = encode_frame(value, mode)?;
+            pipe.write_all(&buf).await?;
+            Ok(())
+        }
+
+        /// Reads a single frame from `pipe`. Returns `None` if the end of the
+        /// stream was reached before the start of the frame.
+        pub async fn read_frame<R, T>(pipe: &mut R, mode: Mode) -> Result<Option<T>>
+        where
+            R: $read + Unpin + ?Sized,
+            T: BinDeserializeOwned,
+        {
+            let mut prefix = LenPrefix::default();
+            let mut b = [0];
+
+            if pipe.read(&mut b).await? == 0 {
+                return Ok(None);
+            }
+
+            let len = loop {
+                if let Some(len) = prefix.push(b[0])? {
+                    break len;
+                }
+
+                pipe.read_exact(&mut b).await?;
+            };
+
+            let len = check_len(len, mode)?;
+            let mut buf = Vec::new();
+            (&mut *pipe).take(len as u64).read_to_end(&mut buf).await?;
+            check_eof(&buf, len)?;
+
+            decode_frame(&buf, mode).map(Some)
+        }
+    };
+}
+
+/// Framing over tokio's `AsyncRead`/`AsyncWrite`.
+#[cfg(feature = "tokio")]
+pub mod tokio {
+    impl_async_framed!(
+        ::tokio::io::AsyncRead,
+        ::tokio::io::AsyncReadExt,
+        ::tokio::io::AsyncWrite,
+        ::tokio::io::AsyncWriteExt
+    );
+}
+
+/// Framing over the `futures` crate's `AsyncRead`/`AsyncWrite`.
+#[cfg(feature = "futures")]
+pub mod futures {
+    impl_async_framed!(
+        ::futures_util::io::AsyncRead,
+        ::futures_util::io::AsyncReadExt,
+        ::futures_util::io::AsyncWrite,
+        ::futures_util::io::AsyncWriteExt
+    );
+}
+
+#[test]
+fn framed_sync() {
+    use crate::Limits;
+
+    let mode = Mode::dedup();
+    let mut buf = Vec::new();
+    write_frame(&mut buf, &vec!["a".to_string(), "a".to_string()], mode).unwrap();
+    write_frame(&mut buf, "b", mode).unwrap();
+    let start = buf.len();
+    write_frame(&mut buf, &vec![0u8; 300], mode).unwrap();
+    let last = &buf[start..];
+
+    let mut pipe = &buf[..];
+    let a: Vec<String> = read_frame(&mut pipe, mode).unwrap().unwrap();
+    let b: String = read_frame(&mut pipe, mode).unwrap().unwrap();
+    let c: Vec<u8> = read_frame(&mut pipe, mode).unwrap().unwrap();
+    assert_eq!(vec!["a", "a"], a);
+    assert_eq!("b", b);
+    assert_eq!(vec![0u8; 300], c);
+    assert!(read_frame::<_, u8>(&mut pipe, mode).unwrap().is_none());
+
+    // truncated payload
+    assert!(read_frame::<_, Vec<u8>>(&last[..last.len() - 1], mode).is_err());
+
+    let limited = mode.with_limits(Limits::none().with_max_total_bytes(16));
+    assert!(matches!(
+        read_frame::<_, Vec<u8>>(last, limited),
+        Err(Error::InputTooLong(16))
+    ));
+
+    // payload longer than the value
+    let mut buf = Vec::new();
+    write_frame(&mut buf, &[1u8, 2], Mode::default()).unwrap();
+    assert!(read_frame::<_, u8>(&buf[..], Mode::default()).is_err());
+
+    let huge = huge_prefix();
+    assert!(read_frame::<_, Vec<u8>>(&huge[..], Mode::default()).is_err());
+}
+
+/// A frame that claims to be as long as possible, but ends right after the
+/// length prefix.
+#[cfg(test)]
+fn huge_prefix() -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.write_varusize(usize::MAX).unwrap();
+    buf
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn framed_tokio() {
+    futures_executor::block_on(async {
+        let mode = Mode::default();
+        let mut buf = Vec::new();
+        self::tokio::write_frame(&mut buf, &(1u8, "x"), mode)
+            .await
+            .unwrap();
+
+        let mut pipe = &buf[..];
+        let v: (u8, String) = self::tokio::read_frame(&mut pipe, mode)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((1, "x".to_string()), v);
+        assert!(self::tokio::read_frame::<_, u8>(&mut pipe, mode)
+            .await
+            .unwrap()
+            .is_none());
+
+        let huge = huge_prefix();
+        assert!(self::tokio::read_frame::<_, Vec<u8>>(&mut &huge[..], mode)
+            .await
+            .is_err());
+    });
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn framed_futures() {
+    use futures_util::io::Cursor;
+
+    futures_executor::block_on(async {
+        let mode = Mode::dedup();
+        let mut pipe = Cursor::new(Vec::new());
+        self::futures::write_frame(&mut pipe, "hello", mode)
+            .await
+            .unwrap();
+        self::futures::write_frame(&mut pipe, &vec![5u32; 200], mode)
+            .await
+            .unwrap();
+
+        pipe.set_position(0);
+        let a: String = self::futures::read_frame(&mut pipe, mode)
+            .await
+            .unwrap()
+            .unwrap();
+        let b: Vec<u32> = self::futures::read_frame(&mut pipe, mode)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!("hello", a);
+        assert_eq!(vec![5u32; 200], b);
+        assert!(self::futures::read_frame::<_, u8>(&mut pipe, mode)
+            .await
+            .unwrap()
+            .is_none());
+
+        let mut huge = Cursor::new(huge_prefix());
+        assert!(self::futures::read_frame::<_, Vec<u8>>(&mut huge, mode)
+            .await
+            .is_err());
+    });
+}
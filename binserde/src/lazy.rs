@@ -0,0 +1,502 @@
+//! Values that are only decoded when they are first accessed.
+//!
+//! A [`Lazy<T>`] is written as the length of its serialized contents followed
+//! by the contents themselves, so that a reader can skip over it without
+//! decoding it. When reading with [`deserialize_from`] from a pipe that
+//! implements [`Seek`], only the position of the contents is recorded, and
+//! they are decoded from the pipe when [`Lazy::get`] is first called. This
+//! makes it possible to open a large file and only decode the parts of it that
+//! are actually needed. With any other deserialization function, the contents
+//! are decoded right away.
+//!
+//! Shared pointers ([`Rc`], [`Arc`](std::sync::Arc)) are not shared across the
+//! boundary of a `Lazy`, since the value they'd refer back to might never be
+//! loaded. Strings are still deduplicated together with the rest of the data.
+
+use std::cell::{Cell, OnceCell, RefCell};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::rc::Rc;
+
+use crate::de::{BinDeserializeOwned, BinDeserializerBase, WithMode};
+use crate::dedup::DedupContext;
+use crate::limit::{InputLimitExceeded, LimitedRead};
+use crate::share::WriteShareContext;
+use crate::{BinDeserialize, BinDeserializer, BinSerialize, BinSerializer, Error, Mode, Result};
+
+/// A value that is decoded on first access. See the [module
+/// documentation](self) for details.
+pub struct Lazy<T> {
+    value: OnceCell<T>,
+    location: Option<Location>,
+}
+
+impl<T> Lazy<T> {
+    pub fn new(value: T) -> Self {
+        Lazy {
+            value: OnceCell::from(value),
+            location: None,
+        }
+    }
+
+    /// Returns whether the value has been decoded yet.
+    pub fn is_loaded(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl<T> Lazy<T>
+where
+    T: BinDeserializeOwned,
+{
+    /// Returns the value, decoding it first if it hasn't been yet.
+    pub fn get(&self) -> Result<&T> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+
+        let value = self.location().load()?;
+        Ok(self.value.get_or_init(|| value))
+    }
+
+    /// Returns the value mutably, decoding it first if it hasn't been yet.
+    pub fn get_mut(&mut self) -> Result<&mut T> {
+        if !self.is_loaded() {
+            self.value = OnceCell::from(self.location().load::<T>()?);
+        }
+
+        Ok(self.value.get_mut().unwrap())
+    }
+
+    pub fn into_inner(mut self) -> Result<T> {
+        match self.value.take() {
+            Some(value) => Ok(value),
+            None => self.location().load(),
+        }
+    }
+
+    fn location(&self) -> &Location {
+        // a Lazy is only ever created without a value when it was read from
+        // a seekable source
+        self.location
+            .as_ref()
+            .expect("unloaded Lazy without a location")
+    }
+}
+
+impl<T> From<T> for Lazy<T> {
+    fn from(value: T) -> Self {
+        Lazy::new(value)
+    }
+}
+
+impl<T> Default for Lazy<T>
+where
+    T: Default,
+{
+    fn default() -> Self {
+        Lazy::new(T::default())
+    }
+}
+
+impl<T> Debug for Lazy<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.value.get() {
+            Some(value) => f.debug_tuple("Lazy").field(value).finish(),
+            None => f.write_str("Lazy(<not loaded>)"),
+        }
+    }
+}
+
+impl<T> BinSerialize for Lazy<T>
+where
+    T: BinSerialize + BinDeserializeOwned,
+{
+    fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
+        let value = self.get()?;
+        let mode = serializer.mode();
+
+        let mut sub = SubSerializer {
+            pipe: Vec::new(),
+            dedup: serializer.dedup(),
+            share: WriteShareContext::new(),
+            mode,
+        };
+        value.serialize(&mut sub)?;
+        let buf = sub.pipe;
+
+        buf.len().serialize(&mut serializer)?;
+        serializer.pipe().write_all(&buf)?;
+        Ok(())
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for Lazy<T>
+where
+    T: BinDeserializeOwned,
+{
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        let len = u64::try_from(usize::deserialize(&mut deserializer)?)?;
+        let mode = deserializer.mode();
+
+        let depth = deserializer.depth_mut().map_or(0, |d| *d);
+
+        if let Some(source) = deserializer.lazy_source() {
+            let source = source.clone();
+            let offset = source.position();
+            source.seek(offset + len)?;
+
+            return Ok(Lazy {
+                value: OnceCell::new(),
+                location: Some(Location {
+                    source,
+                    offset,
+                    len,
+                    depth,
+                    mode,
+                }),
+            });
+        }
+
+        let dedup = deserializer.dedup();
+        let mut pipe = deserializer.pipe().take(len);
+        let sub = BinDeserializerBase::new(&mut pipe, dedup)
+            .with_depth(depth)
+            .with_mode(mode);
+        let value = T::deserialize(sub)?;
+        check_trailing(pipe.limit())?;
+
+        Ok(Lazy::new(value))
+    }
+}
+
+/// Reads a value from `pipe`, leaving any [`Lazy`] values in it to be decoded
+/// from `pipe` on first access.
+pub fn deserialize_from<R, T>(pipe: R, mode: Mode) -> Result<T>
+where
+    R: Read + Seek + 'static,
+    T: BinDeserializeOwned,
+{
+    read_with(pipe, mode, |deserializer| T::deserialize(deserializer))
+}
+
+/// Like [`deserialize_from`], but reads into an existing value with
+/// [`BinDeserialize::deserialize_in_place`].
+pub fn deserialize_in_place_from<R, T>(target: &mut T, pipe: R, mode: Mode) -> Result<()>
+where
+    R: Read + Seek + 'static,
+    T: BinDeserializeOwned,
+{
+    read_with(pipe, mode, |deserializer| {
+        target.deserialize_in_place(deserializer)
+    })
+}
+
+type SourceDeserializer<'a> = WithMode<BinDeserializerBase<'a, SharedPipe>>;
+
+fn read_with<R, T, F>(pipe: R, mode: Mode, op: F) -> Result<T>
+where
+    R: Read + Seek + 'static,
+    F: for<'a> FnOnce(SourceDeserializer<'a>) -> Result<T>,
+{
+    let mut pipe = TrackedPipe::new(Box::new(pipe))?;
+    let start = pipe.pos;
+    let max = mode.limits.max_total_bytes;
+
+    let dedup = if mode.use_dedup {
+        DedupContext::read_from_limited(LimitedRead::new(&mut pipe, max), mode.limits)?
+    } else {
+        DedupContext::new()
+    };
+
+    let remaining = max.map_or(u64::MAX, |max| max.saturating_sub(pipe.pos - start));
+    let source = LazySource(Rc::new(Source {
+        pipe: RefCell::new(pipe),
+        dedup,
+        limit: max.unwrap_or(u64::MAX),
+        remaining: Cell::new(remaining),
+    }));
+
+    let pipe = SharedPipe {
+        source: source.clone(),
+        end: None,
+    };
+    let deserializer = BinDeserializerBase::new(pipe, &source.0.dedup)
+        .with_lazy_source(source.clone())
+        .with_mode(mode);
+    op(deserializer)
+}
+
+/// The seekable pipe and string table that unloaded [`Lazy`] values are
+/// decoded from, shared by all of them that came from the same input.
+///
+/// The input's [`Limits::max_total_bytes`](crate::Limits::max_total_bytes)
+/// is shared as well: every byte decoded from the source counts against it,
+/// whether it is read right away or when a `Lazy` is first accessed.
+#[derive(Clone)]
+pub struct LazySource(Rc<Source>);
+
+struct Source {
+    pipe: RefCell<TrackedPipe>,
+    dedup: DedupContext,
+    limit: u64,
+    remaining: Cell<u64>,
+}
+
+impl LazySource {
+    fn position(&self) -> u64 {
+        self.0.pipe.borrow().pos
+    }
+
+    fn seek(&self, pos: u64) -> io::Result<()> {
+        self.0.pipe.borrow_mut().seek(pos)
+    }
+}
+
+struct Location {
+    source: LazySource,
+    offset: u64,
+    len: u64,
+    /// The nesting depth the `Lazy` was read at, which decoding its contents
+    /// continues from.
+    depth: usize,
+    mode: Mode,
+}
+
+impl Location {
+    fn load<T>(&self) -> Result<T>
+    where
+        T: BinDeserializeOwned,
+    {
+        // Normally nothing else is reading from the source at this point,
+        // but a value might be accessed while the data containing it is
+        // still being read, so put the position back afterwards.
+        let saved = self.source.position();
+        self.source.seek(self.offset)?;
+        let result = self.load_here();
+        self.source.seek(saved)?;
+        result
+    }
+
+    fn load_here<T>(&self) -> Result<T>
+    where
+        T: BinDeserializeOwned,
+    {
+        let end = self.offset + self.len;
+        let pipe = SharedPipe {
+            source: self.source.clone(),
+            end: Some(end),
+        };
+        let deserializer = BinDeserializerBase::new(pipe, &self.source.0.dedup)
+            .with_lazy_source(self.source.clone())
+            .with_depth(self.depth)
+            .with_mode(self.mode);
+        let value = T::deserialize(deserializer)?;
+        check_trailing(end - self.source.position())?;
+        Ok(value)
+    }
+}
+
+fn check_trailing(remaining: u64) -> Result<()> {
+    if remaining != 0 {
+        return Err(Error::custom(format!(
+            "{} trailing bytes in lazy value",
+            remaining
+        )));
+    }
+
+    Ok(())
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T> ReadSeek for T where T: Read + Seek {}
+
+/// A pipe that keeps track of its position, so that it doesn't have to be
+/// queried from the underlying pipe every time a [`Lazy`] is read.
+struct TrackedPipe {
+    inner: Box<dyn ReadSeek>,
+    pos: u64,
+}
+
+impl TrackedPipe {
+    fn new(mut inner: Box<dyn ReadSeek>) -> io::Result<Self> {
+        let pos = inner.stream_position()?;
+        Ok(TrackedPipe { inner, pos })
+    }
+
+    fn seek(&mut self, pos: u64) -> io::Result<()> {
+        if pos != self.pos {
+            self.pos = self.inner.seek(SeekFrom::Start(pos))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Read for TrackedPipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+/// Reads from the pipe of a [`LazySource`], up to `end` if set, counting the
+/// bytes read against the source's size limit.
+struct SharedPipe {
+    source: LazySource,
+    end: Option<u64>,
+}
+
+impl Read for SharedPipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let source = &self.source.0;
+        let mut pipe = source.pipe.borrow_mut();
+
+        let max = match self.end {
+            None => buf.len(),
+            Some(end) => {
+                let remaining = end.saturating_sub(pipe.pos);
+                buf.len().min(remaining.min(usize::MAX as u64) as usize)
+            }
+        };
+
+        if max == 0 {
+            return Ok(0);
+        }
+
+        let remaining = source.remaining.get();
+
+        if remaining == 0 {
+            return Err(io::Error::other(InputLimitExceeded(source.limit)));
+        }
+
+        let max = max.min(remaining.min(usize::MAX as u64) as usize);
+        let len = pipe.read(&mut buf[..max])?;
+        source.remaining.set(remaining - len as u64);
+        Ok(len)
+    }
+}
+
+/// Serializes the contents of a [`Lazy`] into a buffer, sharing the string
+/// table with the serializer the `Lazy` itself is written to.
+struct SubSerializer<'a> {
+    pipe: Vec<u8>,
+    dedup: &'a mut DedupContext,
+    share: WriteShareContext,
+    mode: Mode,
+}
+
+impl BinSerializer for SubSerializer<'_> {
+    type Pipe = Vec<u8>;
+
+    fn pipe(&mut self) -> &mut Self::Pipe {
+        &mut self.pipe
+    }
+
+    fn dedup(&mut self) -> &mut DedupContext {
+        self.dedup
+    }
+
+    fn share(&mut self) -> Option<&mut WriteShareContext> {
+        Some(&mut self.share)
+    }
+
+    fn mode(&self) -> Mode {
+        self.mode
+    }
+}
+
+#[test]
+fn lazy_round_trip() {
+    use std::io::Cursor;
+
+    type Data = (String, Lazy<Vec<String>>, Lazy<(u32, Lazy<String>)>, u8);
+
+    let data: Data = (
+        "a".to_string(),
+        Lazy::new(vec!["a".to_string(), "b".to_string()]),
+        Lazy::new((5, Lazy::new("b".to_string()))),
+        7,
+    );
+
+    let mode = Mode::dedup();
+    let buf = crate::serialize_with(&data, mode).unwrap();
+
+    let read: Data = deserialize_from(Cursor::new(buf.clone()), mode).unwrap();
+    assert_eq!("a", read.0);
+    assert_eq!(7, read.3);
+    assert!(!read.1.is_loaded());
+    assert!(!read.2.is_loaded());
+
+    let (n, inner) = read.2.get().unwrap();
+    assert_eq!(5, *n);
+    assert!(!inner.is_loaded());
+    assert_eq!("b", inner.get().unwrap());
+    assert_eq!(vec!["a", "b"], *read.1.get().unwrap());
+
+    let mut read = Data::default();
+    deserialize_in_place_from(&mut read, Cursor::new(buf.clone()), mode).unwrap();
+    assert!(!read.1.is_loaded());
+    assert_eq!(vec!["a", "b"], *read.1.get().unwrap());
+
+    // writing it back out loads everything that isn't yet
+    let read: Data = deserialize_from(Cursor::new(buf.clone()), mode).unwrap();
+    assert_eq!(buf, crate::serialize_with(&read, mode).unwrap());
+
+    // without a seekable source, everything is read right away
+    let read: Data = crate::deserialize_with(&buf, mode).unwrap();
+    assert!(read.1.is_loaded());
+    assert!(read.2.is_loaded());
+    assert_eq!("b", read.2.into_inner().unwrap().1.into_inner().unwrap());
+}
+
+#[test]
+fn lazy_skips_contents() {
+    use std::io::Cursor;
+
+    let mut buf = crate::serialize(&(Lazy::new("ab".to_string()), 7u8)).unwrap();
+    let idx = buf.len() - 2;
+    buf[idx] = 0xFF;
+
+    let (s, n): (Lazy<String>, u8) =
+        deserialize_from(Cursor::new(buf.clone()), Mode::default()).unwrap();
+    assert_eq!(7, n);
+    assert!(matches!(s.get(), Err(Error::InvalidUtf8(_))));
+
+    assert!(crate::deserialize::<(Lazy<String>, u8)>(&buf).is_err());
+}
+
+#[test]
+fn lazy_limits() {
+    use std::io::Cursor;
+
+    use crate::Limits;
+
+    // the contents of a Lazy are nested as deeply as where it was read
+    let buf = crate::serialize(&vec![Lazy::new(vec![1u8])]).unwrap();
+    let mode = Mode::default().with_limits(Limits::none().with_max_depth(1));
+    let v: Vec<Lazy<Vec<u8>>> = deserialize_from(Cursor::new(buf.clone()), mode).unwrap();
+    assert!(matches!(v[0].get(), Err(Error::TooDeep(1))));
+    let mode = Mode::default().with_limits(Limits::none().with_max_depth(2));
+    let v: Vec<Lazy<Vec<u8>>> = deserialize_from(Cursor::new(buf), mode).unwrap();
+    assert_eq!(&vec![1u8], v[0].get().unwrap());
+
+    // skipped contents count against the size limit once they're decoded
+    let buf = crate::serialize(&(Lazy::new("abc".to_string()), 7u8)).unwrap();
+    let mode =
+        Mode::default().with_limits(Limits::none().with_max_total_bytes(buf.len() as u64 - 1));
+    let (s, n): (Lazy<String>, u8) = deserialize_from(Cursor::new(buf.clone()), mode).unwrap();
+    assert_eq!(7, n);
+    assert!(matches!(s.get(), Err(Error::InputTooLong(_))));
+    let mode = Mode::default().with_limits(Limits::none().with_max_total_bytes(buf.len() as u64));
+    let (s, _): (Lazy<String>, u8) = deserialize_from(Cursor::new(buf), mode).unwrap();
+    assert_eq!("abc", s.get().unwrap());
+}
@@ -1,6 +1,3 @@
-#![allow(incomplete_features)]
-#![feature(const_generics)]
-
 //! # binserde
 //!
 //! A crate similar to serde, but specialized for serializing into a compact
@@ -16,10 +13,9 @@
 //! ## Usage
 //!
 //! ```
-//! use std::fs::File;
-//! use std::io::BufReader;
+//! use binserde::{BinDeserialize, BinSerialize};
 //!
-//! #[derive(BinSerialize, BinDeserialize, Eq, PartialEq)]
+//! #[derive(BinSerialize, BinDeserialize, Debug, Eq, PartialEq)]
 //! struct MyData {
 //!     v1: String,
 //!     v2: Option<usize>,
@@ -56,6 +52,83 @@
 //! Turns off deduplication for this field. See [Deduplication] for more
 //! information about how it works.
 //!
+//! ### `#[binserde(with_serde)]`
+//!
+//! Valid for: fields
+//!
+//! Serializes the field using its [serde](https://serde.rs) implementation
+//! instead of [`BinSerialize`]/[`BinDeserialize`]. Requires the `serde`
+//! feature. See the `serde_bridge` module for more information.
+//!
+//! ### `#[binserde(default = "path")]`
+//!
+//! Valid for: fields with `#[binserde(skip)]`
+//!
+//! Calls the function at `path` instead of [`Default::default()`] to fill the
+//! skipped field when deserializing. Useful for fields whose type doesn't
+//! implement [`Default`].
+//!
+//! ### `#[binserde(with = "module")]`
+//!
+//! Valid for: fields
+//!
+//! Serializes the field using `module::serialize` and deserializes it using
+//! `module::deserialize` instead of its [`BinSerialize`]/[`BinDeserialize`]
+//! implementation. The functions have the same signatures as the trait methods,
+//! with the value passed by reference instead of as `self`:
+//!
+//! ```
+//! # use binserde::{BinDeserializer, BinSerializer, Result};
+//! # struct T;
+//! fn serialize<S: BinSerializer>(value: &T, serializer: S) -> Result<()>
+//! # { Ok(()) }
+//! fn deserialize<'de, D: BinDeserializer<'de>>(deserializer: D) -> Result<T>
+//! # { Ok(T) }
+//! ```
+//!
+//! ### `#[binserde(serialize_with = "path")]`, `#[binserde(deserialize_with = "path")]`
+//!
+//! Valid for: fields
+//!
+//! Like `with`, but only replaces one direction, using the function at `path`.
+//!
+//! ### `#[binserde(endian = "...")]`, `#[binserde(usize_len = "...")]`, `#[binserde(varint)]`
+//!
+//! Valid for: fields
+//!
+//! Override the `Mode` for this field and everything contained in it.
+//! `endian` sets the byte order of fixed size numbers (`"little"` or `"big"`),
+//! `usize_len` sets how lengths and other `usize` values are written (`"u8"`,
+//! `"u16"`, `"u32"`, `"u64"` or `"varint"`), and `varint` (or `varint = false`)
+//! turns writing fixed size integers as variable length integers on or off. This
+//! makes it possible to describe existing fixed binary formats, for example a
+//! header with a big-endian magic number:
+//!
+//! ```
+//! use binserde::BinSerialize;
+//!
+//! #[derive(BinSerialize)]
+//! struct Header {
+//!     #[binserde(endian = "big")]
+//!     magic: u16,
+//!     #[binserde(usize_len = "u16")]
+//!     name: String,
+//! }
+//!
+//! let vec = binserde::serialize(&Header { magic: 0xCAFE, name: "a".to_string() }).unwrap();
+//!
+//! assert_eq!(&[0xCA, 0xFE, 1, 0, b'a'], &*vec);
+//! ```
+//!
+//! ### `#[binserde(repr = "type")]`
+//!
+//! Valid for: enums
+//!
+//! Writes the variant tag as the given integer type instead of a `usize`
+//! holding the variant's index. The tag is the variant's discriminant, so
+//! explicit discriminants (`A = 5`) are honored; variants without one continue
+//! counting from the previous variant, like in Rust.
+//!
 //! ### `#[binserde(index = n)]`
 //!
 //! Valid for: fields
@@ -68,7 +141,9 @@
 //!
 //! #### Example:
 //!
-//! ```
+//! ```ignore
+//! use binserde::BinSerialize;
+//!
 //! #[derive(BinSerialize)]
 //! struct S {
 //!     w: u8,
@@ -78,9 +153,9 @@
 //!     z: u8,
 //! }
 //!
-//! let vec = binserde::serialize(&S { w: 0, x: 1, y: 2, z: 3 });
+//! let vec = binserde::serialize(&S { w: 0, x: 1, y: 2, z: 3 }).unwrap();
 //!
-//! assert_eq!(&[2, 3, 0, 1], &vec);
+//! assert_eq!(&[2, 3, 0, 1], &*vec);
 //! ```
 //!
 //! The attribute moved `y` and `z` into position 0, pushing `w` and `x` back to
@@ -91,7 +166,9 @@
 //! struct serializes in the order z, x, y, w and not x, y, z, w or any other
 //! order:
 //!
-//! ```
+//! ```ignore
+//! use binserde::BinSerialize;
+//!
 //! #[derive(BinSerialize)]
 //! struct S {
 //!     w: u8,
@@ -101,6 +178,10 @@
 //!     #[binserde(index = 0)]
 //!     z: u8,
 //! }
+//!
+//! let vec = binserde::serialize(&S { w: 0, x: 1, y: 2, z: 3 }).unwrap();
+//!
+//! assert_eq!(&[3, 1, 2, 0], &*vec);
 //! ```
 //!
 //! # Deduplication
@@ -138,6 +219,35 @@
 //! serialized data structure when multiple occurrences of the same string
 //! appear.
 //!
+//! # Limits
+//!
+//! By default, length prefixes in the input are trusted, which is fine for
+//! data you wrote yourself. When reading data from an untrusted source, set
+//! [`Limits`] on the [`Mode`] used to deserialize it, for example
+//! `Mode::dedup().with_limits(Limits::untrusted())`. Input exceeding any of
+//! the limits fails with an error instead of exhausting memory or the stack.
+//!
+//! # Streams
+//!
+//! To send several values over one stream, such as a socket, use the functions
+//! in the [`framed`] module. They write each value as a length-prefixed frame, so
+//! the reader knows where one message ends and the next begins. With the `tokio`
+//! or `futures` feature, `framed::tokio` and `framed::futures` provide the same
+//! functions for `AsyncRead`/`AsyncWrite`. The frame length is checked against
+//! [`Limits::max_total_bytes`] before anything is allocated.
+//!
+//! # Lazy Loading
+//!
+//! Wrapping a field in [`lazy::Lazy`] writes it with its length in front, so it
+//! can be skipped when reading. Reading with [`lazy::deserialize_from`] from a
+//! pipe that implements [`Seek`](std::io::Seek), such as a file, only records
+//! where each `Lazy` value is and decodes it the first time
+//! [`Lazy::get`](lazy::Lazy::get) is called. This way, only the parts of a large
+//! file that are actually used are decoded. Everywhere else, `Lazy` values are
+//! decoded right away like any other value.
+//!
+
+#![forbid(unstable_features)]
 
 extern crate self as binserde;
 
@@ -154,16 +264,23 @@ use de::BinDeserializeOwned;
 pub use de::{BinDeserialize, BinDeserializer};
 use dedup::DedupContext;
 pub use ser::{BinSerialize, BinSerializer};
-pub use serde::Mode;
+pub use serde::{Endianness, Limits, Mode, UsizeLen};
 
 use crate::de::BinDeserializerBase;
+use crate::limit::{InputLimitExceeded, LimitedRead};
 use crate::ser::{BinSerializerBase, PrescanSerializer};
 
 pub mod de;
 pub mod dedup;
+pub mod framed;
+pub mod lazy;
+mod limit;
 pub mod ser;
 pub mod serde;
+#[cfg(feature = "serde")]
+pub mod serde_bridge;
 mod serdeimpl;
+pub mod share;
 pub mod try_iter;
 mod varint;
 mod write_ext;
@@ -230,13 +347,14 @@ where
     deserialize_with_from(pipe, Mode::default())
 }
 
-pub fn deserialize_with_from<R, T>(mut pipe: R, mode: Mode) -> Result<T>
+pub fn deserialize_with_from<R, T>(pipe: R, mode: Mode) -> Result<T>
 where
     R: Read,
     T: BinDeserializeOwned,
 {
+    let mut pipe = LimitedRead::new(pipe, mode.limits.max_total_bytes);
     let context = if mode.use_dedup {
-        DedupContext::read_from(&mut pipe)?
+        DedupContext::read_from_limited(&mut pipe, mode.limits)?
     } else {
         DedupContext::new()
     };
@@ -244,13 +362,14 @@ where
     T::deserialize(deserializer)
 }
 
-pub fn deserialize_in_place<R, T>(target: &mut T, mut pipe: R, mode: Mode) -> Result<()>
+pub fn deserialize_in_place<R, T>(target: &mut T, pipe: R, mode: Mode) -> Result<()>
 where
     R: Read,
     T: BinDeserializeOwned,
 {
+    let mut pipe = LimitedRead::new(pipe, mode.limits.max_total_bytes);
     let context = if mode.use_dedup {
-        DedupContext::read_from(&mut pipe)?
+        DedupContext::read_from_limited(&mut pipe, mode.limits)?
     } else {
         DedupContext::new()
     };
@@ -263,17 +382,36 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("I/O error: {0}")]
-    Io(#[from] io::Error),
+    Io(#[source] io::Error),
     #[error("string too long")]
     TryFromInt(#[from] TryFromIntError),
     #[error("invalid UTF-8 string")]
     InvalidUtf8(#[from] FromUtf8Error),
     #[error("indexed string out of range: {0}")]
     StrOutOfRange(usize),
+    #[error("invalid variable-length integer")]
+    InvalidVarInt,
+    #[error("collection length {0} exceeds limit")]
+    CollectionTooLong(usize),
+    #[error("string length {0} exceeds limit")]
+    StrTooLong(usize),
+    #[error("input exceeds size limit of {0} bytes")]
+    InputTooLong(u64),
+    #[error("nesting depth exceeds limit of {0}")]
+    TooDeep(usize),
     #[error("{0}")]
     Custom(String),
 }
 
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        match e.get_ref().and_then(|e| e.downcast_ref::<InputLimitExceeded>()) {
+            Some(InputLimitExceeded(limit)) => Error::InputTooLong(*limit),
+            None => Error::Io(e),
+        }
+    }
+}
+
 impl Error {
     pub fn custom<S: Display>(s: S) -> Self {
         Error::Custom(s.to_string())
@@ -365,3 +503,198 @@ fn serialize_constant_output() {
         .unwrap()
     );
 }
+
+#[test]
+fn mode_options() {
+    let mode = Mode::default().with_usize_len(UsizeLen::U16);
+    assert_eq!(&[3, 0, 97, 98, 99], &*serialize_with("abc", mode).unwrap());
+    let mode = mode.with_endianness(Endianness::Big);
+    assert_eq!(&[0, 3, 97, 98, 99], &*serialize_with("abc", mode).unwrap());
+    assert_eq!(
+        "abc",
+        deserialize_with::<String>(&[0, 3, 97, 98, 99], mode).unwrap()
+    );
+
+    let mode = Mode::default().with_usize_len(UsizeLen::U8);
+    assert!(serialize_with(&vec![0u8; 256], mode).is_err());
+
+    let mode = Mode::default().with_endianness(Endianness::Big);
+    assert_eq!(
+        &[0x12, 0x34, 0x40, 0x49, 0x0F, 0xDB],
+        &*serialize_with(&(0x1234u16, std::f32::consts::PI), mode).unwrap()
+    );
+
+    let mode = Mode::dedup()
+        .with_dedup_idx(UsizeLen::U32)
+        .with_endianness(Endianness::Big);
+    let buf = serialize_with(&("a", "a"), mode).unwrap();
+    assert_eq!(&[1, 1, 97, 0, 0, 0, 0, 0, 0, 0, 0], &*buf);
+    assert_eq!(
+        ("a".to_string(), "a".to_string()),
+        deserialize_with(&buf, mode).unwrap()
+    );
+
+    #[derive(BinSerialize, BinDeserialize, Debug, PartialEq)]
+    struct Header {
+        #[binserde(endian = "big", varint = false)]
+        magic: u16,
+        #[binserde(usize_len = "u32", endian = "big")]
+        name: String,
+        #[binserde(varint)]
+        size: u32,
+        #[binserde(varint = false)]
+        flags: u32,
+    }
+
+    let header = Header {
+        magic: 0xCAFE,
+        name: "n".to_string(),
+        size: 300,
+        flags: 1,
+    };
+    let buf = serialize_with(&header, Mode::default().with_fixed_size_use_varint(true)).unwrap();
+    assert_eq!(
+        &[0xCA, 0xFE, 0, 0, 0, 1, b'n', 0xAC, 0x02, 1, 0, 0, 0],
+        &*buf
+    );
+    assert_eq!(
+        header,
+        deserialize_with(&buf, Mode::default().with_fixed_size_use_varint(true)).unwrap()
+    );
+}
+
+#[test]
+fn deserialize_limits() {
+    let limits = Limits::none()
+        .with_max_len(4)
+        .with_max_str_bytes(4)
+        .with_max_depth(2);
+    let mode = Mode::default().with_limits(limits);
+
+    let buf = serialize(&vec![0u8; 5]).unwrap();
+    assert!(matches!(
+        deserialize_with::<Vec<u8>>(&buf, mode),
+        Err(Error::CollectionTooLong(5))
+    ));
+
+    let buf = serialize("abcde").unwrap();
+    assert!(matches!(
+        deserialize_with::<String>(&buf, mode),
+        Err(Error::StrTooLong(5))
+    ));
+
+    // a length prefix claiming way more data than there is
+    let buf = [0xFF, 0xFF, 0xFF, 0xFF, 0x0F, b'a'];
+    assert!(matches!(
+        deserialize::<String>(&buf),
+        Err(Error::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+    ));
+
+    let buf = serialize(&vec![vec![vec![1u8]]]).unwrap();
+    deserialize_with::<Vec<Vec<u8>>>(&serialize(&vec![vec![1u8]]).unwrap(), mode).unwrap();
+    assert!(matches!(
+        deserialize_with::<Vec<Vec<Vec<u8>>>>(&buf, mode),
+        Err(Error::TooDeep(2))
+    ));
+
+    let buf = serialize_with(&("abc", "def"), Mode::dedup()).unwrap();
+    let mode = Mode::dedup().with_limits(Limits::none().with_max_total_bytes(buf.len() as u64 - 1));
+    assert!(matches!(
+        deserialize_with::<(String, String)>(&buf, mode),
+        Err(Error::InputTooLong(_))
+    ));
+    let mode = Mode::dedup().with_limits(Limits::none().with_max_total_bytes(buf.len() as u64));
+    deserialize_with::<(String, String)>(&buf, mode).unwrap();
+}
+
+#[test]
+fn derive_field_codecs() {
+    mod as_u8 {
+        use crate::{BinDeserialize, BinDeserializer, BinSerialize, BinSerializer, Result};
+
+        pub fn serialize<S: BinSerializer>(value: &u32, serializer: S) -> Result<()> {
+            (*value as u8).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: BinDeserializer<'de>>(deserializer: D) -> Result<u32> {
+            Ok(u8::deserialize(deserializer)? as u32)
+        }
+    }
+
+    fn write_negated<S: BinSerializer>(value: &bool, serializer: S) -> Result<()> {
+        (!*value).serialize(serializer)
+    }
+
+    fn read_negated<'de, D: BinDeserializer<'de>>(deserializer: D) -> Result<bool> {
+        Ok(!bool::deserialize(deserializer)?)
+    }
+
+    fn seven() -> u8 {
+        7
+    }
+
+    #[derive(Debug, PartialEq, BinSerialize, BinDeserialize)]
+    struct S {
+        #[binserde(with = "as_u8")]
+        a: u32,
+        #[binserde(serialize_with = "write_negated", deserialize_with = "read_negated")]
+        b: bool,
+        #[binserde(skip, default = "seven")]
+        c: u8,
+    }
+
+    let buf = serialize(&S {
+        a: 5,
+        b: true,
+        c: 1,
+    })
+    .unwrap();
+    assert_eq!(&[5, 0], &*buf);
+    assert_eq!(
+        S {
+            a: 5,
+            b: true,
+            c: 7
+        },
+        deserialize(&buf).unwrap()
+    );
+
+    let mut s = S {
+        a: 0,
+        b: false,
+        c: 0,
+    };
+    deserialize_in_place(&mut s, &*buf, Mode::default()).unwrap();
+    assert_eq!(
+        S {
+            a: 5,
+            b: true,
+            c: 7
+        },
+        s
+    );
+}
+
+#[test]
+fn derive_enum_repr() {
+    #[derive(Debug, PartialEq, BinSerialize, BinDeserialize)]
+    #[repr(u8)]
+    #[binserde(repr = "u8")]
+    enum E {
+        A,
+        B = 10,
+        C(u16),
+        D = 0x80,
+    }
+
+    assert_eq!(&[0], &*serialize(&E::A).unwrap());
+    assert_eq!(&[10], &*serialize(&E::B).unwrap());
+    assert_eq!(&[11, 3, 0], &*serialize(&E::C(3)).unwrap());
+    assert_eq!(&[0x80], &*serialize(&E::D).unwrap());
+
+    for e in [E::A, E::B, E::C(3), E::D] {
+        assert_eq!(e, deserialize(&serialize(&e).unwrap()).unwrap());
+    }
+
+    assert!(deserialize::<E>(&[1]).is_err());
+}
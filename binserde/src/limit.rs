@@ -0,0 +1,59 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::Read;
+
+/// A reader that fails once more than a set amount of bytes is requested
+/// from it, used to enforce [`Limits::max_total_bytes`].
+///
+/// [`Limits::max_total_bytes`]: crate::serde::Limits::max_total_bytes
+pub(crate) struct LimitedRead<R> {
+    inner: R,
+    limit: u64,
+    remaining: u64,
+}
+
+impl<R> LimitedRead<R> {
+    pub fn new(inner: R, limit: Option<u64>) -> Self {
+        let limit = limit.unwrap_or(u64::MAX);
+
+        LimitedRead {
+            inner,
+            limit,
+            remaining: limit,
+        }
+    }
+}
+
+impl<R> Read for LimitedRead<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.remaining == 0 {
+            return Err(io::Error::other(InputLimitExceeded(self.limit)));
+        }
+
+        let max = buf.len().min(self.remaining.min(usize::MAX as u64) as usize);
+        let len = self.inner.read(&mut buf[..max])?;
+        self.remaining -= len as u64;
+        Ok(len)
+    }
+}
+
+/// Error payload carried through [`io::Error`] so that it can be turned back
+/// into [`Error::InputTooLong`](crate::Error::InputTooLong).
+#[derive(Debug)]
+pub(crate) struct InputLimitExceeded(pub u64);
+
+impl Display for InputLimitExceeded {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "input exceeds size limit of {} bytes", self.0)
+    }
+}
+
+impl std::error::Error for InputLimitExceeded {}
@@ -3,6 +3,7 @@ use std::io::Write;
 
 use crate::dedup::DedupContext;
 use crate::serde::Mode;
+use crate::share::WriteShareContext;
 use crate::Result;
 
 pub trait BinSerialize {
@@ -16,6 +17,13 @@ pub trait BinSerializer: Sized {
 
     fn dedup(&mut self) -> &mut DedupContext;
 
+    /// Returns the context for writing shared pointers only once.
+    /// Serializers that don't track them return `None`, which writes every
+    /// pointee in place.
+    fn share(&mut self) -> Option<&mut WriteShareContext> {
+        None
+    }
+
     fn mode(&self) -> Mode;
 
     fn with_mode(self, mode: Mode) -> WithMode<Self> {
@@ -53,6 +61,10 @@ where
         (**self).dedup()
     }
 
+    fn share(&mut self) -> Option<&mut WriteShareContext> {
+        (**self).share()
+    }
+
     fn mode(&self) -> Mode {
         (**self).mode()
     }
@@ -61,6 +73,7 @@ where
 pub struct BinSerializerBase<W> {
     pipe: W,
     dedup: DedupContext,
+    share: WriteShareContext,
 }
 
 impl<W> BinSerializerBase<W> {
@@ -68,6 +81,7 @@ impl<W> BinSerializerBase<W> {
         BinSerializerBase {
             pipe,
             dedup: DedupContext::new(),
+            share: WriteShareContext::new(),
         }
     }
 
@@ -89,6 +103,10 @@ where
         &mut self.dedup
     }
 
+    fn share(&mut self) -> Option<&mut WriteShareContext> {
+        Some(&mut self.share)
+    }
+
     fn mode(&self) -> Mode {
         Mode::default()
     }
@@ -113,6 +131,10 @@ where
         self.serializer.dedup()
     }
 
+    fn share(&mut self) -> Option<&mut WriteShareContext> {
+        self.serializer.share()
+    }
+
     fn mode(&self) -> Mode {
         self.mode
     }
@@ -121,6 +143,13 @@ where
 pub struct PrescanSerializer {
     pipe: NullWrite,
     dedup: DedupContext,
+    share: WriteShareContext,
+}
+
+impl Default for PrescanSerializer {
+    fn default() -> Self {
+        PrescanSerializer::new()
+    }
 }
 
 impl PrescanSerializer {
@@ -128,6 +157,7 @@ impl PrescanSerializer {
         PrescanSerializer {
             pipe: NullWrite,
             dedup: DedupContext::new(),
+            share: WriteShareContext::new(),
         }
     }
 }
@@ -143,6 +173,10 @@ impl BinSerializer for PrescanSerializer {
         &mut self.dedup
     }
 
+    fn share(&mut self) -> Option<&mut WriteShareContext> {
+        Some(&mut self.share)
+    }
+
     fn mode(&self) -> Mode {
         Mode::default()
     }
@@ -3,9 +3,12 @@ pub struct Mode {
     pub usize_len: UsizeLen,
     pub dedup_idx: UsizeLen,
     pub fixed_size_use_varint: bool,
+    pub endianness: Endianness,
 
     // Do not flip this on if it's off
     pub use_dedup: bool,
+
+    pub limits: Limits,
 }
 
 impl Default for Mode {
@@ -14,7 +17,9 @@ impl Default for Mode {
             usize_len: UsizeLen::Variable,
             dedup_idx: UsizeLen::Variable,
             fixed_size_use_varint: false,
+            endianness: Endianness::Little,
             use_dedup: false,
+            limits: Limits::none(),
         }
     }
 }
@@ -32,10 +37,25 @@ impl Mode {
         self
     }
 
+    pub fn with_dedup_idx(mut self, dedup_idx: UsizeLen) -> Self {
+        self.dedup_idx = dedup_idx;
+        self
+    }
+
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
     pub fn with_fixed_size_use_varint(mut self, enabled: bool) -> Self {
         self.fixed_size_use_varint = enabled;
         self
     }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
@@ -46,3 +66,77 @@ pub enum UsizeLen {
     U64,
     Variable,
 }
+
+/// The byte order fixed size integers and floats are written in.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// Upper bounds that are enforced while deserializing. Exceeding any of them
+/// aborts deserialization with the corresponding [`Error`](crate::Error)
+/// variant instead of allocating or recursing without bound.
+///
+/// Limits are ignored when serializing.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct Limits {
+    /// The maximum number of elements in a single collection.
+    pub max_len: Option<usize>,
+    /// The maximum length of a single string in bytes.
+    pub max_str_bytes: Option<usize>,
+    /// The maximum number of bytes read from the pipe in total, including the
+    /// deduplication table.
+    pub max_total_bytes: Option<u64>,
+    /// The maximum nesting depth of structs, enums and collections.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::none()
+    }
+}
+
+impl Limits {
+    /// No limits at all. This is the default, and only suitable for data from
+    /// a trusted source.
+    pub const fn none() -> Self {
+        Limits {
+            max_len: None,
+            max_str_bytes: None,
+            max_total_bytes: None,
+            max_depth: None,
+        }
+    }
+
+    /// Limits suitable for reading files of unknown origin.
+    pub const fn untrusted() -> Self {
+        Limits {
+            max_len: Some(1 << 24),
+            max_str_bytes: Some(1 << 20),
+            max_total_bytes: Some(1 << 30),
+            max_depth: Some(128),
+        }
+    }
+
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    pub fn with_max_str_bytes(mut self, max_str_bytes: usize) -> Self {
+        self.max_str_bytes = Some(max_str_bytes);
+        self
+    }
+
+    pub fn with_max_total_bytes(mut self, max_total_bytes: u64) -> Self {
+        self.max_total_bytes = Some(max_total_bytes);
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+}
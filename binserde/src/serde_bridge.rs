@@ -0,0 +1,1013 @@
+//! Interoperability with [serde](https://serde.rs).
+//!
+//! This module provides a [`serde::Serializer`](::serde::Serializer) and
+//! [`serde::Deserializer`](::serde::Deserializer) that read and write the
+//! binserde wire format, so that types implementing serde's traits can be
+//! stored in binserde data (including string deduplication), and the other way
+//! around.
+//!
+//! A serde type is encoded exactly like the equivalent type deriving
+//! [`BinSerialize`]/[`BinDeserialize`] would be: structs and tuples as their
+//! fields in order, enums as their variant index followed by the fields,
+//! sequences and maps with a length prefix. Since the format is not
+//! self-describing, types that rely on `deserialize_any` (such as
+//! `serde_json::Value` or `#[serde(flatten)]`/untagged enums) and sequences
+//! of unknown length can't be used.
+//!
+//! There are three ways to use a serde type from binserde:
+//!
+//!  - the [`serialize`] and [`deserialize`] functions,
+//!  - the `#[binserde(with_serde)]` field attribute, which calls the above,
+//!  - the [`Serde`] wrapper type.
+//!
+//! For the other direction, [`Bin`] wraps a binserde type so that it can be
+//! used as a field in a serde type. It is stored as a byte string containing
+//! the binserde serialized value.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use ::serde::de::{
+    DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use ::serde::ser::{
+    SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant,
+};
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::de::{self, BinDeserializeOwned};
+use crate::{BinDeserialize, BinDeserializer, BinSerialize, BinSerializer, Error, Result};
+
+/// Serializes a serde type using a binserde serializer.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<()>
+where
+    T: Serialize + ?Sized,
+    S: BinSerializer,
+{
+    value.serialize(&mut SerdeSerializer::new(serializer))
+}
+
+/// Deserializes a serde type using a binserde deserializer.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T>
+where
+    T: Deserialize<'de>,
+    D: BinDeserializer<'de>,
+{
+    T::deserialize(&mut SerdeDeserializer::new(deserializer))
+}
+
+/// Wraps a type implementing serde's traits to implement [`BinSerialize`] and
+/// [`BinDeserialize`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Serde<T>(pub T);
+
+impl<T> Serde<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Serde<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Serde<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> BinSerialize for Serde<T>
+where
+    T: Serialize,
+{
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for Serde<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        Ok(Serde(deserialize(deserializer)?))
+    }
+}
+
+/// Wraps a type implementing [`BinSerialize`] and [`BinDeserialize`] to
+/// implement serde's traits. The value is stored as a byte string holding its
+/// binserde serialized form, using the default [`Mode`](crate::Mode).
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Bin<T>(pub T);
+
+impl<T> Bin<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Bin<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Bin<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> Serialize for Bin<T>
+where
+    T: BinSerialize,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let buf = crate::serialize(&self.0).map_err(::serde::ser::Error::custom)?;
+        serializer.serialize_bytes(&buf)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Bin<T>
+where
+    T: BinDeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BytesVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> Visitor<'de> for BytesVisitor<T>
+        where
+            T: BinDeserializeOwned,
+        {
+            type Value = Bin<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a byte string")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+            where
+                E: ::serde::de::Error,
+            {
+                crate::deserialize(v).map(Bin).map_err(E::custom)
+            }
+
+            // formats without native byte strings, such as JSON, store them as
+            // a sequence of numbers instead
+            fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut buf = Vec::new();
+
+                while let Some(b) = seq.next_element()? {
+                    buf.push(b);
+                }
+
+                self.visit_bytes(&buf)
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor(PhantomData))
+    }
+}
+
+impl ::serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::custom(msg)
+    }
+}
+
+impl ::serde::de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::custom(msg)
+    }
+}
+
+/// A serde serializer writing the binserde wire format to a [`BinSerializer`].
+pub struct SerdeSerializer<S> {
+    inner: S,
+}
+
+impl<S> SerdeSerializer<S> {
+    pub fn new(inner: S) -> Self {
+        SerdeSerializer { inner }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> SerdeSerializer<S>
+where
+    S: BinSerializer,
+{
+    fn write<T: BinSerialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut self.inner)
+    }
+
+    fn write_variant(&mut self, variant_index: u32) -> Result<()> {
+        // same as the variant index written by #[derive(BinSerialize)]
+        self.write(&(variant_index as usize))
+    }
+
+    fn write_len(&mut self, len: Option<usize>) -> Result<()> {
+        match len {
+            None => Err(Error::custom("can't serialize sequence of unknown length")),
+            Some(len) => self.write(&len),
+        }
+    }
+}
+
+impl<S> Serializer for &mut SerdeSerializer<S>
+where
+    S: BinSerializer,
+{
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.write(&v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.write(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.write(v)
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.write(&0u8)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.write(&1u8)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        self.write_variant(variant_index)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.write_variant(variant_index)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self> {
+        self.write_variant(variant_index)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self> {
+        self.write_variant(variant_index)?;
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+macro_rules! impl_serialize_compound {
+    ($($tr:ident :: $m:ident ($($arg:ident: $argty:ty),*);)*) => {
+        $(
+            impl<S> $tr for &mut SerdeSerializer<S>
+            where
+                S: BinSerializer,
+            {
+                type Ok = ();
+                type Error = Error;
+
+                fn $m<T>(&mut self, $($arg: $argty,)* value: &T) -> Result<()>
+                where
+                    T: Serialize + ?Sized,
+                {
+                    $(let _ = $arg;)*
+                    value.serialize(&mut **self)
+                }
+
+                fn end(self) -> Result<()> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_serialize_compound! {
+    SerializeSeq::serialize_element();
+    SerializeTuple::serialize_element();
+    SerializeTupleStruct::serialize_field();
+    SerializeTupleVariant::serialize_field();
+    SerializeStruct::serialize_field(key: &'static str);
+    SerializeStructVariant::serialize_field(key: &'static str);
+}
+
+impl<S> SerializeMap for &mut SerdeSerializer<S>
+where
+    S: BinSerializer,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A serde deserializer reading the binserde wire format from a
+/// [`BinDeserializer`].
+pub struct SerdeDeserializer<D> {
+    inner: D,
+}
+
+impl<D> SerdeDeserializer<D> {
+    pub fn new(inner: D) -> Self {
+        SerdeDeserializer { inner }
+    }
+
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<'de, D> SerdeDeserializer<D>
+where
+    D: BinDeserializer<'de>,
+{
+    fn read<T: BinDeserialize<'de>>(&mut self) -> Result<T> {
+        T::deserialize(&mut self.inner)
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        let len: usize = self.read()?;
+
+        if let Some(max_len) = self.inner.mode().limits.max_len {
+            if len > max_len {
+                return Err(Error::CollectionTooLong(len));
+            }
+        }
+
+        Ok(len)
+    }
+
+    // Can't use BinDeserializer::nested here since that would need a new
+    // SerdeDeserializer wrapping the borrowed inner deserializer, which doesn't
+    // terminate for recursive types.
+    fn enter(&mut self) -> Result<()> {
+        let max_depth = self.inner.mode().limits.max_depth;
+        de::enter(self.inner.depth_mut(), max_depth)
+    }
+
+    fn leave(&mut self) {
+        de::leave(self.inner.depth_mut());
+    }
+
+    fn visit_compound<V>(&mut self, len: usize, visitor: V, map: bool) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.enter()?;
+
+        let access = Access {
+            de: self,
+            remaining: len,
+        };
+
+        let result = if map {
+            visitor.visit_map(access)
+        } else {
+            visitor.visit_seq(access)
+        };
+
+        self.leave();
+        result
+    }
+}
+
+macro_rules! forward_primitive {
+    ($($m:ident => $visit:ident,)*) => {
+        $(
+            fn $m<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit(self.read()?)
+            }
+        )*
+    };
+}
+
+impl<'de, D> Deserializer<'de> for &mut SerdeDeserializer<D>
+where
+    D: BinDeserializer<'de>,
+{
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(Error::custom(
+            "binserde is not a self-describing format, deserialize_any is not supported",
+        ))
+    }
+
+    forward_primitive! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+        deserialize_string => visit_string,
+        deserialize_byte_buf => visit_byte_buf,
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.inner.mode().use_dedup {
+            // strings from the table live as long as the deserializer input,
+            // so they can be borrowed
+            let idx = <usize as BinDeserialize>::deserialize(
+                (&mut self.inner).change_mode(|mode| mode.usize_len = mode.dedup_idx),
+            )?;
+            let s = self
+                .inner
+                .dedup()
+                .get_str(idx)
+                .ok_or_else(|| Error::custom(format!("index {} not in string table", idx)))?;
+            visitor.visit_borrowed_str(s)
+        } else {
+            visitor.visit_string(self.read()?)
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.read::<u8>()? {
+            0 => visitor.visit_none(),
+            1 => visitor.visit_some(self),
+            x => Err(Error::custom(format!("invalid enum variant index {}", x))),
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let len = self.read_len()?;
+        self.visit_compound(len, visitor, false)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.visit_compound(len, visitor, false)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.visit_compound(len, visitor, false)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let len = self.read_len()?;
+        self.visit_compound(len, visitor, true)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.visit_compound(fields.len(), visitor, false)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.enter()?;
+        let result = visitor.visit_enum(&mut *self);
+        self.leave();
+        result
+    }
+
+    fn deserialize_identifier<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(Error::custom(
+            "binserde is not a self-describing format, identifiers are not supported",
+        ))
+    }
+
+    fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(Error::custom(
+            "binserde is not a self-describing format, values can't be skipped",
+        ))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct Access<'a, D> {
+    de: &'a mut SerdeDeserializer<D>,
+    remaining: usize,
+}
+
+impl<'de, D> SeqAccess<'de> for Access<'_, D>
+where
+    D: BinDeserializer<'de>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            seed.deserialize(&mut *self.de).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de, D> MapAccess<'de> for Access<'_, D>
+where
+    D: BinDeserializer<'de>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            seed.deserialize(&mut *self.de).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de, D> EnumAccess<'de> for &mut SerdeDeserializer<D>
+where
+    D: BinDeserializer<'de>,
+{
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let idx: usize = self.read()?;
+        let idx =
+            u32::try_from(idx).map_err(|_| Error::custom(format!("invalid variant {}", idx)))?;
+        let value = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(idx))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de, D> VariantAccess<'de> for &mut SerdeDeserializer<D>
+where
+    D: BinDeserializer<'de>,
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.visit_compound(len, visitor, false)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.visit_compound(fields.len(), visitor, false)
+    }
+}
+
+/// Serializes a serde type into a buffer using the binserde wire format.
+/// Equivalent to [`crate::serialize_with`] for binserde types.
+pub fn to_vec<T>(value: &T, mode: crate::Mode) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    crate::serialize_with(&Serde(value), mode)
+}
+
+/// Deserializes a serde type from a buffer using the binserde wire format.
+/// Equivalent to [`crate::deserialize_with`] for binserde types.
+pub fn from_slice<T>(buf: &[u8], mode: crate::Mode) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    Ok(crate::deserialize_with::<Serde<T>>(buf, mode)?.0)
+}
+
+#[test]
+fn serde_round_trip() {
+    use std::collections::HashMap;
+
+    use crate::Mode;
+
+    #[derive(Debug, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+    struct Test {
+        name: String,
+        tags: Vec<String>,
+        map: HashMap<String, (u8, i64)>,
+        kind: Kind,
+        maybe: Option<Box<Test>>,
+    }
+
+    #[derive(Debug, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+    enum Kind {
+        A,
+        B(u32),
+        C { x: f32, y: char },
+    }
+
+    let value = Test {
+        name: "outer".to_string(),
+        tags: vec!["a".to_string(), "outer".to_string(), "a".to_string()],
+        map: vec![("k".to_string(), (1, -600))].into_iter().collect(),
+        kind: Kind::C { x: 1.5, y: 'x' },
+        maybe: Some(Box::new(Test {
+            name: "inner".to_string(),
+            tags: vec![],
+            map: HashMap::new(),
+            kind: Kind::B(5),
+            maybe: None,
+        })),
+    };
+
+    for mode in [Mode::default(), Mode::dedup()].iter().copied() {
+        let buf = to_vec(&value, mode).unwrap();
+        let copy: Test = from_slice(&buf, mode).unwrap();
+        assert_eq!(value, copy);
+    }
+
+    assert_eq!(
+        from_slice::<Kind>(&to_vec(&Kind::A, Mode::default()).unwrap(), Mode::default()).unwrap(),
+        Kind::A
+    );
+}
+
+#[test]
+fn serde_same_format_as_binserde() {
+    #[derive(::serde::Serialize)]
+    struct SerdeStruct {
+        a: String,
+        b: Vec<u16>,
+        c: SerdeEnum,
+    }
+
+    #[derive(::serde::Serialize)]
+    enum SerdeEnum {
+        #[allow(dead_code)]
+        X,
+        Y(i32, String),
+    }
+
+    #[derive(BinSerialize)]
+    struct BinStruct {
+        a: String,
+        b: Vec<u16>,
+        c: BinEnum,
+    }
+
+    #[derive(BinSerialize)]
+    enum BinEnum {
+        #[allow(dead_code)]
+        X,
+        Y(i32, String),
+    }
+
+    let mode = crate::Mode::dedup();
+    let serde = to_vec(
+        &SerdeStruct {
+            a: "a".to_string(),
+            b: vec![1, 2],
+            c: SerdeEnum::Y(-1, "a".to_string()),
+        },
+        mode,
+    )
+    .unwrap();
+    let bin = crate::serialize_with(
+        &BinStruct {
+            a: "a".to_string(),
+            b: vec![1, 2],
+            c: BinEnum::Y(-1, "a".to_string()),
+        },
+        mode,
+    )
+    .unwrap();
+
+    assert_eq!(serde, bin);
+}
+
+#[test]
+fn serde_adapters() {
+    use crate::Mode;
+
+    #[derive(Debug, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+    struct SerdeOnly {
+        x: String,
+    }
+
+    #[derive(Debug, PartialEq, BinSerialize, BinDeserialize)]
+    struct BinOnly {
+        y: String,
+    }
+
+    #[derive(Debug, PartialEq, BinSerialize, BinDeserialize)]
+    struct Outer {
+        #[binserde(with_serde)]
+        a: SerdeOnly,
+        b: Serde<SerdeOnly>,
+        c: BinOnly,
+    }
+
+    let value = Outer {
+        a: SerdeOnly { x: "x".to_string() },
+        b: Serde(SerdeOnly { x: "x".to_string() }),
+        c: BinOnly { y: "x".to_string() },
+    };
+
+    let buf = crate::serialize_with(&value, Mode::dedup()).unwrap();
+    let copy: Outer = crate::deserialize_with(&buf, Mode::dedup()).unwrap();
+    assert_eq!(value, copy);
+
+    #[derive(Debug, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+    struct SerdeOuter {
+        inner: Bin<BinOnly>,
+    }
+
+    let value = SerdeOuter {
+        inner: Bin(BinOnly { y: "y".to_string() }),
+    };
+
+    let json = serde_json::to_string(&value).unwrap();
+    let copy: SerdeOuter = serde_json::from_str(&json).unwrap();
+    assert_eq!(value, copy);
+}
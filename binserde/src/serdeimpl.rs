@@ -1,13 +1,25 @@
+use std::any::Any;
 use std::cell::{Cell, RefCell};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::convert::{Infallible, TryInto};
 use std::hash::Hash;
-use std::mem::MaybeUninit;
+use std::io;
+use std::io::Read;
+use std::marker::PhantomData;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+};
+use std::ops::{Range, RangeInclusive};
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use byteorder::{ReadBytesExt, WriteBytesExt, BE, LE};
 
-use crate::serde::UsizeLen;
+use crate::serde::{Endianness, UsizeLen};
 use crate::try_iter::try_iter;
 use crate::util::{serialize_iter, VecLikeIter};
 use crate::write_ext::{ReadExt, WriteExt};
@@ -38,27 +50,44 @@ impl BinSerialize for bool {
     }
 }
 
+/// Reads or writes a fixed size number from/to `pipe` using the byte order
+/// `order`.
+macro_rules! with_order {
+    ($order:expr, $pipe:expr, $method:ident($($arg:expr)?)) => {
+        match $order {
+            Endianness::Little => $pipe.$method::<LE>($($arg)?),
+            Endianness::Big => $pipe.$method::<BE>($($arg)?),
+        }
+    };
+}
+
 impl<'de> BinDeserialize<'de> for usize {
     fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
-        match deserializer.mode().usize_len {
-            UsizeLen::U8 => Ok(deserializer.pipe().read_u8()? as usize),
-            UsizeLen::U16 => Ok(deserializer.pipe().read_u16::<LE>()? as usize),
-            UsizeLen::U32 => Ok(deserializer.pipe().read_u32::<LE>()?.try_into()?),
-            UsizeLen::U64 => Ok(deserializer.pipe().read_u64::<LE>()?.try_into()?),
-            UsizeLen::Variable => deserializer.pipe().read_varusize(),
+        let mode = deserializer.mode();
+        let pipe = deserializer.pipe();
+
+        match mode.usize_len {
+            UsizeLen::U8 => Ok(pipe.read_u8()? as usize),
+            UsizeLen::U16 => Ok(with_order!(mode.endianness, pipe, read_u16())? as usize),
+            UsizeLen::U32 => Ok(with_order!(mode.endianness, pipe, read_u32())?.try_into()?),
+            UsizeLen::U64 => Ok(with_order!(mode.endianness, pipe, read_u64())?.try_into()?),
+            UsizeLen::Variable => pipe.read_varusize(),
         }
     }
 }
 
 impl BinSerialize for usize {
     fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
-        match serializer.mode().usize_len {
-            UsizeLen::U8 => serializer.pipe().write_u8((*self).try_into()?)?,
-            UsizeLen::U16 => serializer.pipe().write_u8((*self).try_into()?)?,
-            UsizeLen::U32 => serializer.pipe().write_u8((*self).try_into()?)?,
-            UsizeLen::U64 => serializer.pipe().write_u8((*self).try_into()?)?,
+        let mode = serializer.mode();
+        let pipe = serializer.pipe();
+
+        match mode.usize_len {
+            UsizeLen::U8 => pipe.write_u8((*self).try_into()?)?,
+            UsizeLen::U16 => with_order!(mode.endianness, pipe, write_u16((*self).try_into()?))?,
+            UsizeLen::U32 => with_order!(mode.endianness, pipe, write_u32((*self).try_into()?))?,
+            UsizeLen::U64 => with_order!(mode.endianness, pipe, write_u64((*self).try_into()?))?,
             UsizeLen::Variable => {
-                serializer.pipe().write_varusize(*self)?;
+                pipe.write_varusize(*self)?;
             }
         };
 
@@ -78,24 +107,40 @@ impl BinSerialize for u8 {
     }
 }
 
+impl<'de> BinDeserialize<'de> for i8 {
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        Ok(deserializer.pipe().read_i8()?)
+    }
+}
+
+impl BinSerialize for i8 {
+    fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
+        Ok(serializer.pipe().write_i8(*self)?)
+    }
+}
+
 macro_rules! impl_int {
     ($type:ty, $rm:ident, $wm:ident, $rvm:ident, $wvm:ident, $varint_type:ty) => {
         impl<'de> BinDeserialize<'de> for $type {
             fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
-                if deserializer.mode().fixed_size_use_varint {
+                let mode = deserializer.mode();
+
+                if mode.fixed_size_use_varint {
                     Ok(deserializer.pipe().$rvm()?.try_into()?)
                 } else {
-                    Ok(deserializer.pipe().$rm::<LE>()?)
+                    Ok(with_order!(mode.endianness, deserializer.pipe(), $rm())?)
                 }
             }
         }
 
         impl BinSerialize for $type {
             fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
-                if serializer.mode().fixed_size_use_varint {
+                let mode = serializer.mode();
+
+                if mode.fixed_size_use_varint {
                     serializer.pipe().$wvm(*self as $varint_type)?;
                 } else {
-                    serializer.pipe().$wm::<LE>(*self)?;
+                    with_order!(mode.endianness, serializer.pipe(), $wm(*self))?;
                 }
 
                 Ok(())
@@ -116,6 +161,92 @@ impl_int!(u64, read_u64, write_u64, read_varuint, write_varuint, u64);
 impl_int!(i16, read_i16, write_i16, read_varint, write_varint, i64);
 impl_int!(i32, read_i32, write_i32, read_varint, write_varint, i64);
 impl_int!(i64, read_i64, write_i64, read_varint, write_varint, i64);
+impl_int!(u128, read_u128, write_u128, read_varuint128, write_varuint128, u128);
+impl_int!(i128, read_i128, write_i128, read_varint128, write_varint128, i128);
+
+// isize doesn't have a fixed size, so always write it as an i64 (similar to how
+// usize is handled with UsizeLen::U64)
+impl<'de> BinDeserialize<'de> for isize {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        Ok(i64::deserialize(deserializer)?.try_into()?)
+    }
+}
+
+impl BinSerialize for isize {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        (*self as i64).serialize(serializer)
+    }
+}
+
+macro_rules! impl_float {
+    ($type:ty, $rm:ident, $wm:ident) => {
+        // floats don't compress well as varints, so fixed_size_use_varint
+        // doesn't apply to them
+        impl<'de> BinDeserialize<'de> for $type {
+            fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+                let order = deserializer.mode().endianness;
+                Ok(with_order!(order, deserializer.pipe(), $rm())?)
+            }
+        }
+
+        impl BinSerialize for $type {
+            fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
+                let order = serializer.mode().endianness;
+                Ok(with_order!(order, serializer.pipe(), $wm(*self))?)
+            }
+        }
+    };
+}
+
+impl_float!(f32, read_f32, write_f32);
+impl_float!(f64, read_f64, write_f64);
+
+impl<'de> BinDeserialize<'de> for char {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        let v = u32::deserialize(deserializer)?;
+        char::from_u32(v).ok_or_else(|| Error::custom(format!("invalid char {:#X}", v)))
+    }
+}
+
+impl BinSerialize for char {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        (*self as u32).serialize(serializer)
+    }
+}
+
+macro_rules! impl_nonzero {
+    ($($type:ty => $inner:ty),* $(,)?) => {
+        $(
+            impl<'de> BinDeserialize<'de> for $type {
+                fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+                    <$type>::new(<$inner>::deserialize(deserializer)?)
+                        .ok_or_else(|| Error::custom(concat!("zero value for ", stringify!($type))))
+                }
+            }
+
+            impl BinSerialize for $type {
+                fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+                    self.get().serialize(serializer)
+                }
+            }
+        )*
+    };
+}
+
+impl_nonzero! {
+    NonZeroU8 => u8,
+    NonZeroU16 => u16,
+    NonZeroU32 => u32,
+    NonZeroU64 => u64,
+    NonZeroU128 => u128,
+    NonZeroUsize => usize,
+    NonZeroI8 => i8,
+    NonZeroI16 => i16,
+    NonZeroI32 => i32,
+    NonZeroI64 => i64,
+    NonZeroI128 => i128,
+    NonZeroIsize => isize,
+}
 
 impl<'de> BinDeserialize<'de> for String {
     fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
@@ -129,7 +260,26 @@ impl<'de> BinDeserialize<'de> for String {
                 .map(|s| s.to_string())
                 .ok_or_else(|| Error::custom(format!("index {} not in string table", idx)))
         } else {
-            Ok(String::from_utf8(Vec::deserialize(deserializer)?)?)
+            let len = usize::deserialize(&mut deserializer)?;
+
+            if let Some(max_str_bytes) = deserializer.mode().limits.max_str_bytes {
+                if len > max_str_bytes {
+                    return Err(Error::StrTooLong(len));
+                }
+            }
+
+            // don't trust the length to preallocate, it might be garbage
+            let mut buf = Vec::new();
+            deserializer
+                .pipe()
+                .take(len as u64)
+                .read_to_end(&mut buf)?;
+
+            if buf.len() != len {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+
+            Ok(String::from_utf8(buf)?)
         }
     }
 }
@@ -155,15 +305,22 @@ impl<'de, T> BinDeserialize<'de> for Vec<T>
 where
     T: BinDeserialize<'de>,
 {
-    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
-        let iter = VecLikeIter::new(deserializer)?;
-        try_iter(iter, |iter| iter.collect())
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| iter.collect())
+        })
     }
 
-    fn deserialize_in_place<D: BinDeserializer<'de>>(&mut self, deserializer: D) -> Result<()> {
+    fn deserialize_in_place<D: BinDeserializer<'de>>(
+        &mut self,
+        mut deserializer: D,
+    ) -> Result<()> {
         self.clear();
-        let iter = VecLikeIter::new(deserializer)?;
-        try_iter(iter, |iter| self.extend(iter))
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| self.extend(iter))
+        })
     }
 }
 
@@ -203,23 +360,21 @@ where
     T: BinDeserialize<'de> + Sized,
 {
     fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
-        // this is safe since MaybeUninit<T>'s Drop is a no-op
-        // TODO: https://github.com/rust-lang/rust/issues/61956
-        let mut arr: [MaybeUninit<T>; LEN] =
-            unsafe { std::mem::transmute_copy(&MaybeUninit::<T>::uninit()) };
+        let mut vec = Vec::with_capacity(LEN);
 
-        for idx in 0..LEN {
-            arr[idx] = MaybeUninit::new(T::deserialize(&mut deserializer)?);
+        for _ in 0..LEN {
+            vec.push(T::deserialize(&mut deserializer)?);
         }
 
-        // this is safe since [MaybeUninit<T>; LEN] doesn't do anything on drop,
-        // since MaybeUninit<T>'s Drop is a no-op
-        Ok(unsafe { std::mem::transmute_copy(&arr) })
+        match vec.try_into() {
+            Ok(arr) => Ok(arr),
+            Err(_) => unreachable!(),
+        }
     }
 
     fn deserialize_in_place<D: BinDeserializer<'de>>(&mut self, mut deserializer: D) -> Result<()> {
-        for idx in 0..LEN {
-            self[idx] = T::deserialize(&mut deserializer)?;
+        for el in self.iter_mut() {
+            *el = T::deserialize(&mut deserializer)?;
         }
 
         Ok(())
@@ -241,15 +396,22 @@ where
     K: BinDeserialize<'de> + Eq + Hash,
     V: BinDeserialize<'de>,
 {
-    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
-        let iter = VecLikeIter::new(deserializer)?;
-        try_iter(iter, |iter| iter.collect())
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| iter.collect())
+        })
     }
 
-    fn deserialize_in_place<D: BinDeserializer<'de>>(&mut self, deserializer: D) -> Result<()> {
+    fn deserialize_in_place<D: BinDeserializer<'de>>(
+        &mut self,
+        mut deserializer: D,
+    ) -> Result<()> {
         self.clear();
-        let iter = VecLikeIter::new(deserializer)?;
-        try_iter(iter, |iter| self.extend(iter))
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| self.extend(iter))
+        })
     }
 }
 
@@ -266,15 +428,120 @@ impl<'de, T> BinDeserialize<'de> for HashSet<T>
 where
     T: BinDeserialize<'de> + Hash + Eq,
 {
-    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
-        let iter = VecLikeIter::new(deserializer)?;
-        try_iter(iter, |iter| iter.collect())
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| iter.collect())
+        })
+    }
+
+    fn deserialize_in_place<D: BinDeserializer<'de>>(
+        &mut self,
+        mut deserializer: D,
+    ) -> Result<()> {
+        self.clear();
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| self.extend(iter))
+        })
+    }
+}
+
+impl<K, V> BinSerialize for BTreeMap<K, V>
+where
+    K: BinSerialize,
+    V: BinSerialize,
+{
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        serialize_iter(self.iter(), serializer)
+    }
+}
+
+impl<'de, K, V> BinDeserialize<'de> for BTreeMap<K, V>
+where
+    K: BinDeserialize<'de> + Ord,
+    V: BinDeserialize<'de>,
+{
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| iter.collect())
+        })
+    }
+
+    fn deserialize_in_place<D: BinDeserializer<'de>>(
+        &mut self,
+        mut deserializer: D,
+    ) -> Result<()> {
+        self.clear();
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| self.extend(iter))
+        })
+    }
+}
+
+impl<T> BinSerialize for BTreeSet<T>
+where
+    T: BinSerialize,
+{
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        serialize_iter(self.iter(), serializer)
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for BTreeSet<T>
+where
+    T: BinDeserialize<'de> + Ord,
+{
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| iter.collect())
+        })
     }
 
-    fn deserialize_in_place<D: BinDeserializer<'de>>(&mut self, deserializer: D) -> Result<()> {
+    fn deserialize_in_place<D: BinDeserializer<'de>>(
+        &mut self,
+        mut deserializer: D,
+    ) -> Result<()> {
         self.clear();
-        let iter = VecLikeIter::new(deserializer)?;
-        try_iter(iter, |iter| self.extend(iter))
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| self.extend(iter))
+        })
+    }
+}
+
+impl<T> BinSerialize for VecDeque<T>
+where
+    T: BinSerialize,
+{
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        serialize_iter(self.iter(), serializer)
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for VecDeque<T>
+where
+    T: BinDeserialize<'de>,
+{
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| iter.collect())
+        })
+    }
+
+    fn deserialize_in_place<D: BinDeserializer<'de>>(
+        &mut self,
+        mut deserializer: D,
+    ) -> Result<()> {
+        self.clear();
+        deserializer.nested(|deserializer| {
+            let iter = VecLikeIter::new(deserializer)?;
+            try_iter(iter, |iter| self.extend(iter))
+        })
     }
 }
 
@@ -385,7 +652,7 @@ where
         Ok(match variant {
             0 => None,
             1 => Some(T::deserialize(deserializer)?),
-            x @ _ => Err(Error::custom(format!("invalid enum variant index {}", x)))?,
+            x => Err(Error::custom(format!("invalid enum variant index {}", x)))?,
         })
     }
 }
@@ -419,7 +686,7 @@ where
         Ok(match variant {
             0 => Ok(T::deserialize(deserializer)?),
             1 => Err(R::deserialize(deserializer)?),
-            x @ _ => Err(Error::custom(format!("invalid enum variant index {}", x)))?,
+            x => Err(Error::custom(format!("invalid enum variant index {}", x)))?,
         })
     }
 }
@@ -430,7 +697,7 @@ where
 {
     fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<(), Error> {
         self.try_borrow()
-            .map_err(|e| Error::custom(e))?
+            .map_err(Error::custom)?
             .serialize(serializer)
     }
 }
@@ -461,3 +728,454 @@ where
         Ok(Cell::new(T::deserialize(deserializer)?))
     }
 }
+
+impl<T> BinSerialize for Box<T>
+where
+    T: BinSerialize + ?Sized,
+{
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for Box<T>
+where
+    T: BinDeserialize<'de>,
+{
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        Ok(Box::new(deserializer.nested(|deserializer| T::deserialize(deserializer))?))
+    }
+
+    fn deserialize_in_place<D: BinDeserializer<'de>>(
+        &mut self,
+        mut deserializer: D,
+    ) -> Result<()> {
+        deserializer.nested(|deserializer| (**self).deserialize_in_place(deserializer))
+    }
+}
+
+impl<'de> BinDeserialize<'de> for Box<str> {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        Ok(String::deserialize(deserializer)?.into_boxed_str())
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for Box<[T]>
+where
+    T: BinDeserialize<'de>,
+{
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        Ok(Vec::deserialize(deserializer)?.into_boxed_slice())
+    }
+}
+
+fn fill_shared<'de, D, P>(deserializer: &mut D, idx: Option<usize>, ptr: &P)
+where
+    D: BinDeserializer<'de>,
+    P: Any + Clone,
+{
+    if let (Some(share), Some(idx)) = (deserializer.share(), idx) {
+        share.fill(idx, ptr.clone());
+    }
+}
+
+fn get_shared<'de, D, P>(deserializer: &mut D, tag: usize) -> Result<P>
+where
+    D: BinDeserializer<'de>,
+    P: Any + Clone,
+{
+    match deserializer.share() {
+        Some(share) => share.get(tag),
+        None => Err(Error::custom(
+            "shared pointer references aren't supported by this deserializer",
+        )),
+    }
+}
+
+macro_rules! impl_shared_ptr {
+    ($type:ident) => {
+        impl<T> BinSerialize for $type<T>
+        where
+            T: BinSerialize + ?Sized,
+        {
+            fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
+                let addr = $type::as_ptr(self) as *const () as usize;
+                let tag = serializer.share().map_or(0, |share| share.tag_for(addr));
+                tag.serialize(&mut serializer)?;
+
+                if tag == 0 {
+                    (**self).serialize(&mut serializer)?;
+                }
+
+                Ok(())
+            }
+        }
+
+        impl<'de, T> BinDeserialize<'de> for $type<T>
+        where
+            T: BinDeserialize<'de> + 'static,
+        {
+            fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+                let tag = usize::deserialize(&mut deserializer)?;
+
+                if tag == 0 {
+                    let idx = deserializer.share().map(|share| share.reserve());
+                    let ptr = $type::new(
+                        deserializer.nested(|deserializer| T::deserialize(deserializer))?,
+                    );
+                    fill_shared(&mut deserializer, idx, &ptr);
+                    Ok(ptr)
+                } else {
+                    get_shared(&mut deserializer, tag)
+                }
+            }
+        }
+
+        impl<'de> BinDeserialize<'de> for $type<str> {
+            fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+                let tag = usize::deserialize(&mut deserializer)?;
+
+                if tag == 0 {
+                    let idx = deserializer.share().map(|share| share.reserve());
+                    let ptr: $type<str> = String::deserialize(&mut deserializer)?.into();
+                    fill_shared(&mut deserializer, idx, &ptr);
+                    Ok(ptr)
+                } else {
+                    get_shared(&mut deserializer, tag)
+                }
+            }
+        }
+    };
+}
+
+impl_shared_ptr!(Rc);
+impl_shared_ptr!(Arc);
+
+impl BinSerialize for Duration {
+    fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
+        self.as_secs().serialize(&mut serializer)?;
+        self.subsec_nanos().serialize(&mut serializer)
+    }
+}
+
+impl<'de> BinDeserialize<'de> for Duration {
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        let secs = u64::deserialize(&mut deserializer)?;
+        let nanos = u32::deserialize(&mut deserializer)?;
+
+        if nanos >= 1_000_000_000 {
+            return Err(Error::custom(format!("invalid nanoseconds {}", nanos)));
+        }
+
+        Ok(Duration::new(secs, nanos))
+    }
+}
+
+/// Written as the [`Duration`] relative to [`UNIX_EPOCH`], with a leading
+/// `u8` that is 0 for times after the epoch and 1 for times before it.
+impl BinSerialize for SystemTime {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(d) => Ok::<_, Duration>(d),
+            Err(e) => Err(e.duration()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> BinDeserialize<'de> for SystemTime {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        let time = match Result::<Duration, Duration>::deserialize(deserializer)? {
+            Ok(d) => UNIX_EPOCH.checked_add(d),
+            Err(d) => UNIX_EPOCH.checked_sub(d),
+        };
+
+        time.ok_or_else(|| Error::custom("time out of range for SystemTime"))
+    }
+}
+
+impl BinSerialize for Ipv4Addr {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        self.octets().serialize(serializer)
+    }
+}
+
+impl<'de> BinDeserialize<'de> for Ipv4Addr {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        Ok(<[u8; 4]>::deserialize(deserializer)?.into())
+    }
+}
+
+impl BinSerialize for Ipv6Addr {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        self.octets().serialize(serializer)
+    }
+}
+
+impl<'de> BinDeserialize<'de> for Ipv6Addr {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        Ok(<[u8; 16]>::deserialize(deserializer)?.into())
+    }
+}
+
+impl BinSerialize for IpAddr {
+    fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
+        match self {
+            IpAddr::V4(addr) => {
+                0u8.serialize(&mut serializer)?;
+                addr.serialize(&mut serializer)
+            }
+            IpAddr::V6(addr) => {
+                1u8.serialize(&mut serializer)?;
+                addr.serialize(&mut serializer)
+            }
+        }
+    }
+}
+
+impl<'de> BinDeserialize<'de> for IpAddr {
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        let variant = u8::deserialize(&mut deserializer)?;
+        Ok(match variant {
+            0 => IpAddr::V4(Ipv4Addr::deserialize(deserializer)?),
+            1 => IpAddr::V6(Ipv6Addr::deserialize(deserializer)?),
+            x => Err(Error::custom(format!("invalid enum variant index {}", x)))?,
+        })
+    }
+}
+
+impl<T> BinSerialize for Range<T>
+where
+    T: BinSerialize,
+{
+    fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
+        self.start.serialize(&mut serializer)?;
+        self.end.serialize(&mut serializer)
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for Range<T>
+where
+    T: BinDeserialize<'de>,
+{
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        let start = T::deserialize(&mut deserializer)?;
+        let end = T::deserialize(&mut deserializer)?;
+        Ok(start..end)
+    }
+}
+
+impl<T> BinSerialize for RangeInclusive<T>
+where
+    T: BinSerialize,
+{
+    fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
+        self.start().serialize(&mut serializer)?;
+        self.end().serialize(&mut serializer)
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for RangeInclusive<T>
+where
+    T: BinDeserialize<'de>,
+{
+    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self> {
+        let start = T::deserialize(&mut deserializer)?;
+        let end = T::deserialize(&mut deserializer)?;
+        Ok(start..=end)
+    }
+}
+
+impl<T> BinSerialize for PhantomData<T>
+where
+    T: ?Sized,
+{
+    fn serialize<S: BinSerializer>(&self, _serializer: S) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for PhantomData<T>
+where
+    T: ?Sized,
+{
+    fn deserialize<D: BinDeserializer<'de>>(_deserializer: D) -> Result<Self> {
+        Ok(PhantomData)
+    }
+}
+
+#[cfg(test)]
+fn round_trip<T>(value: T)
+where
+    T: BinSerialize + crate::de::BinDeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    use crate::Mode;
+
+    let modes = [
+        Mode::default(),
+        Mode::dedup(),
+        Mode::default().with_fixed_size_use_varint(true),
+    ];
+
+    for mode in modes.iter().copied() {
+        let buf = crate::serialize_with(&value, mode).unwrap();
+        let copy: T = crate::deserialize_with(&buf, mode).unwrap();
+        assert_eq!(value, copy, "in mode {:?}", mode);
+    }
+}
+
+#[test]
+fn round_trip_collections() {
+    round_trip(
+        vec![(3, "c"), (1, "a"), (2, "b")]
+            .into_iter()
+            .map(|(k, v)| (k, v.to_string()))
+            .collect::<BTreeMap<i32, String>>(),
+    );
+    round_trip(vec![5u16, 1, 3].into_iter().collect::<BTreeSet<_>>());
+    round_trip(vec!['a', 'b', 'c'].into_iter().collect::<VecDeque<_>>());
+}
+
+#[test]
+fn round_trip_numbers() {
+    round_trip(-5i8);
+    round_trip(i8::MIN);
+    round_trip(u128::MAX);
+    round_trip(i128::MIN);
+    round_trip(-23456788765432isize);
+    round_trip(1.5f32);
+    round_trip(-0.1f64);
+    round_trip(f64::INFINITY);
+    round_trip('ü');
+    round_trip('\u{1F980}');
+
+    round_trip(NonZeroU8::new(1).unwrap());
+    round_trip(NonZeroU32::new(123456).unwrap());
+    round_trip(NonZeroI64::new(-5).unwrap());
+    round_trip(NonZeroU128::new(u128::MAX).unwrap());
+    round_trip(NonZeroUsize::new(7).unwrap());
+    assert!(crate::deserialize::<NonZeroU16>(&[0, 0]).is_err());
+    assert!(crate::deserialize::<char>(&[0x00, 0xD8, 0x00, 0x00]).is_err());
+}
+
+#[test]
+fn round_trip_misc() {
+    round_trip(Box::new(17u32));
+    round_trip(Box::<str>::from("boxed"));
+    round_trip(vec![1u8, 2, 3].into_boxed_slice());
+
+    round_trip(Duration::new(5, 999_999_999));
+    round_trip(UNIX_EPOCH + Duration::new(1_600_000_000, 123));
+    round_trip(UNIX_EPOCH - Duration::new(86400, 5));
+
+    round_trip(Ipv4Addr::new(127, 0, 0, 1));
+    round_trip(IpAddr::V6(Ipv6Addr::LOCALHOST));
+
+    round_trip(3u64..17);
+    round_trip(-4i32..=4);
+    round_trip(PhantomData::<String>);
+}
+
+#[test]
+fn shared_pointers() {
+    round_trip(Rc::new("shared".to_string()));
+    round_trip(Arc::new(5u16));
+    round_trip(Rc::<str>::from("shared str"));
+
+    let a = Rc::new("a".to_string());
+    let b = Rc::new("b".to_string());
+    let value = vec![a.clone(), b.clone(), a.clone(), a, b];
+
+    let buf = crate::serialize(&value).unwrap();
+    let copy: Vec<Rc<String>> = crate::deserialize(&buf).unwrap();
+
+    assert_eq!(value, copy);
+    assert!(Rc::ptr_eq(&copy[0], &copy[2]));
+    assert!(Rc::ptr_eq(&copy[0], &copy[3]));
+    assert!(Rc::ptr_eq(&copy[1], &copy[4]));
+    assert!(!Rc::ptr_eq(&copy[0], &copy[1]));
+
+    // nested pointers get their index before their contents are read
+    let inner = Arc::new(1u8);
+    let outer = Arc::new(vec![inner.clone(), inner.clone()]);
+    let value = (outer.clone(), inner, outer);
+
+    let buf = crate::serialize(&value).unwrap();
+    let copy: (_, Arc<u8>, _) = crate::deserialize(&buf).unwrap();
+
+    assert_eq!(value, copy);
+    assert!(Arc::ptr_eq(&copy.0, &copy.2));
+    assert!(Arc::ptr_eq(&copy.0[0], &copy.1));
+}
+
+#[test]
+fn shared_pointers_without_context() {
+    use crate::dedup::DedupContext;
+    use crate::Mode;
+
+    // implementations that only provide the required methods
+    struct Plain<P> {
+        pipe: P,
+        dedup: DedupContext,
+    }
+
+    impl<W: io::Write> BinSerializer for Plain<W> {
+        type Pipe = W;
+
+        fn pipe(&mut self) -> &mut W {
+            &mut self.pipe
+        }
+
+        fn dedup(&mut self) -> &mut DedupContext {
+            &mut self.dedup
+        }
+
+        fn mode(&self) -> Mode {
+            Mode::default()
+        }
+    }
+
+    struct PlainDe<'de, R> {
+        pipe: R,
+        dedup: &'de DedupContext,
+    }
+
+    impl<'de, R: Read> BinDeserializer<'de> for PlainDe<'de, R> {
+        type Pipe = R;
+
+        fn pipe(&mut self) -> &mut R {
+            &mut self.pipe
+        }
+
+        fn dedup(&self) -> &'de DedupContext {
+            self.dedup
+        }
+
+        fn mode(&self) -> Mode {
+            Mode::default()
+        }
+    }
+
+    let a = Rc::new("a".to_string());
+    let value = vec![a.clone(), a];
+
+    // without a context, every pointee is written in place
+    let mut serializer = Plain {
+        pipe: Vec::new(),
+        dedup: DedupContext::new(),
+    };
+    value.serialize(&mut serializer).unwrap();
+    let copy: Vec<Rc<String>> = crate::deserialize(&serializer.pipe).unwrap();
+    assert_eq!(value, copy);
+    assert!(!Rc::ptr_eq(&copy[0], &copy[1]));
+
+    let dedup = DedupContext::new();
+    let read = |buf: &[u8]| {
+        Vec::<Rc<String>>::deserialize(PlainDe {
+            pipe: buf,
+            dedup: &dedup,
+        })
+    };
+    assert_eq!(value, read(&serializer.pipe).unwrap());
+    assert!(read(&crate::serialize(&value).unwrap()).is_err());
+}
@@ -0,0 +1,82 @@
+//! Bookkeeping for serializing shared pointers ([`Rc`](std::rc::Rc) and
+//! [`Arc`](std::sync::Arc)) so that values pointed to from several places are
+//! only written once and come back shared after deserializing.
+//!
+//! Each shared pointer is written as a `usize` tag. A tag of 0 means the
+//! pointee is written inline right after it, and is then assigned the next
+//! free index. Any other tag `n` refers back to the pointee with index `n - 1`.
+
+use std::any::Any;
+use std::collections::HashMap;
+
+use crate::{Error, Result};
+
+pub struct WriteShareContext {
+    seen: HashMap<usize, usize>,
+}
+
+impl WriteShareContext {
+    pub fn new() -> Self {
+        WriteShareContext {
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Returns the tag to write for the pointee at `addr`, registering it if
+    /// it hasn't been seen yet.
+    pub fn tag_for(&mut self, addr: usize) -> usize {
+        let next = self.seen.len();
+
+        match self.seen.get(&addr) {
+            Some(idx) => idx + 1,
+            None => {
+                self.seen.insert(addr, next);
+                0
+            }
+        }
+    }
+}
+
+impl Default for WriteShareContext {
+    fn default() -> Self {
+        WriteShareContext::new()
+    }
+}
+
+pub struct ReadShareContext {
+    values: Vec<Option<Box<dyn Any>>>,
+}
+
+impl ReadShareContext {
+    pub fn new() -> Self {
+        ReadShareContext { values: Vec::new() }
+    }
+
+    /// Reserves the index for a pointee that is about to be read. This has to
+    /// happen before reading it so that pointers nested inside of it get the
+    /// same indices as when they were written.
+    pub fn reserve(&mut self) -> usize {
+        self.values.push(None);
+        self.values.len() - 1
+    }
+
+    pub fn fill<P: Any>(&mut self, idx: usize, ptr: P) {
+        self.values[idx] = Some(Box::new(ptr));
+    }
+
+    /// Returns a clone of the pointer previously stored for `tag`.
+    pub fn get<P: Any + Clone>(&self, tag: usize) -> Result<P> {
+        self.values
+            .get(tag - 1)
+            .and_then(|v| v.as_ref())
+            .and_then(|v| v.downcast_ref::<P>())
+            .cloned()
+            .ok_or_else(|| Error::custom(format!("invalid shared pointer reference {}", tag)))
+    }
+}
+
+impl Default for ReadShareContext {
+    fn default() -> Self {
+        ReadShareContext::new()
+    }
+}
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::{BinDeserialize, BinDeserializer, BinSerialize, BinSerializer, Result};
+use crate::{BinDeserialize, BinDeserializer, BinSerialize, BinSerializer, Error, Result};
 
 pub struct VecLikeIter<D, T> {
     deserializer: D,
@@ -15,6 +15,13 @@ where
 {
     pub fn new(mut deserializer: D) -> Result<Self> {
         let len = usize::deserialize(&mut deserializer)?;
+
+        if let Some(max_len) = deserializer.mode().limits.max_len {
+            if len > max_len {
+                return Err(Error::CollectionTooLong(len));
+            }
+        }
+
         Ok(VecLikeIter {
             deserializer,
             remaining: len,
@@ -51,7 +58,7 @@ where
 {
 }
 
-pub fn serialize_iter<I, S>(mut iter: I, mut serializer: S) -> Result<()>
+pub fn serialize_iter<I, S>(iter: I, mut serializer: S) -> Result<()>
 where
     I: Iterator,
     I::Item: BinSerialize,
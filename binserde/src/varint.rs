@@ -3,6 +3,8 @@ use std::io::{Read, Write};
 
 use byteorder::ReadBytesExt;
 
+use crate::{Error, Result};
+
 pub fn encode_min(num: i64) -> u64 {
     let u_num = num as u64;
     (u_num << 1 ^ (num >> 63) as u64) | u_num >> 63
@@ -15,7 +17,7 @@ pub fn decode_min(num: u64) -> i64 {
 pub fn varint_write<W: Write>(num: u64, mut pipe: W) -> io::Result<usize> {
     let mut num_pos = 0;
     let mut idx = 0;
-    let mut buf = [0; 9];
+    let mut buf = [0; 10];
     let data_bits = 64 - num.leading_zeros();
 
     loop {
@@ -29,23 +31,30 @@ pub fn varint_write<W: Write>(num: u64, mut pipe: W) -> io::Result<usize> {
         idx += 1;
         num_pos += 7;
 
-        if !(num_pos < data_bits) {
+        if num_pos >= data_bits {
             break;
         }
     }
 
-    pipe.write(&buf[..idx])?;
+    pipe.write_all(&buf[..idx])?;
 
     Ok(idx)
 }
 
-pub fn varint_read<R: Read>(mut pipe: R) -> io::Result<u64> {
+pub fn varint_read<R: Read>(mut pipe: R) -> Result<u64> {
     let mut offset = 0;
     let mut num = 0;
 
     loop {
         let byte = pipe.read_u8()?;
         let has_next = byte & 0b10000000 != 0;
+
+        // the 10th byte may only contribute the topmost bit of a u64, anything
+        // longer than that is an overlong or corrupted encoding
+        if offset == 63 && (has_next || byte > 1) {
+            return Err(Error::InvalidVarInt);
+        }
+
         num |= (byte as u64 & 0b01111111) << offset;
         offset += 7;
 
@@ -57,6 +66,62 @@ pub fn varint_read<R: Read>(mut pipe: R) -> io::Result<u64> {
     Ok(num)
 }
 
+pub fn encode_min128(num: i128) -> u128 {
+    let u_num = num as u128;
+    (u_num << 1 ^ (num >> 127) as u128) | u_num >> 127
+}
+
+pub fn decode_min128(num: u128) -> i128 {
+    (num >> 1) as i128 ^ ((num << 127) as i128) >> 127
+}
+
+pub fn varint128_write<W: Write>(mut num: u128, mut pipe: W) -> io::Result<usize> {
+    let mut idx = 0;
+    let mut buf = [0; 19];
+
+    loop {
+        let piece = num as u8 & 0b01111111;
+        num >>= 7;
+
+        if num != 0 {
+            buf[idx] = piece | 0b10000000;
+            idx += 1;
+        } else {
+            buf[idx] = piece;
+            idx += 1;
+            break;
+        }
+    }
+
+    pipe.write_all(&buf[..idx])?;
+
+    Ok(idx)
+}
+
+pub fn varint128_read<R: Read>(mut pipe: R) -> Result<u128> {
+    let mut offset = 0;
+    let mut num = 0;
+
+    loop {
+        let byte = pipe.read_u8()?;
+        let has_next = byte & 0b10000000 != 0;
+
+        // same as in varint_read, the 19th byte only has 2 bits left to fill
+        if offset == 126 && (has_next || byte > 0b11) {
+            return Err(Error::InvalidVarInt);
+        }
+
+        num |= (byte as u128 & 0b01111111) << offset;
+        offset += 7;
+
+        if !has_next {
+            break;
+        }
+    }
+
+    Ok(num)
+}
+
 #[test]
 fn test_encode_min() {
     for i in -5..5 {
@@ -107,3 +172,40 @@ fn test_varint() {
     assert_eq!(encode_min(-18), varint_read(&mut buf).unwrap());
     assert_eq!(encode_min(20000000), varint_read(&mut buf).unwrap());
 }
+
+#[test]
+fn test_varint_overlong() {
+    varint_read(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01][..]).unwrap();
+
+    assert!(matches!(
+        varint_read(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02][..]),
+        Err(Error::InvalidVarInt)
+    ));
+    assert!(matches!(
+        varint_read(&[0x80; 11][..]),
+        Err(Error::InvalidVarInt)
+    ));
+}
+
+#[test]
+fn test_varint128() {
+    use std::io::Cursor;
+
+    let mut buf = Cursor::new(Vec::new());
+
+    varint128_write(0, &mut buf).unwrap();
+    varint128_write(234567892322414124, &mut buf).unwrap();
+    varint128_write(u128::MAX, &mut buf).unwrap();
+    varint128_write(encode_min128(i128::MIN), &mut buf).unwrap();
+
+    buf.set_position(0);
+    assert_eq!(0, varint128_read(&mut buf).unwrap());
+    assert_eq!(234567892322414124, varint128_read(&mut buf).unwrap());
+    assert_eq!(u128::MAX, varint128_read(&mut buf).unwrap());
+    assert_eq!(i128::MIN, decode_min128(varint128_read(&mut buf).unwrap()));
+
+    // small values are encoded the same way as with the 64-bit variant
+    let mut buf = Vec::new();
+    varint_write(234567892322414124, &mut buf).unwrap();
+    assert_eq!(234567892322414124, varint128_read(&*buf).unwrap());
+}
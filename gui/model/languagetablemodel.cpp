@@ -7,16 +7,20 @@
 using std::optional;
 using mcrtlib::ffi::LanguageTable;
 using mcrtlib::ffi::languagetable_load;
+using mcrtlib::ffi::languagetablehistory_new;
 using mcrtlib::to_qstring;
 
-LanguageTableModel::LanguageTableModel(LanguageTable lt, QObject* parent) : QAbstractTableModel(parent), m_lt(std::move(lt)) {}
+LanguageTableModel::LanguageTableModel(LanguageTable lt, QObject* parent) : QAbstractTableModel(parent),
+                                                                            m_lt(std::move(lt)),
+                                                                            m_history(languagetablehistory_new()) {}
 
 void LanguageTableModel::set_entry(QString language, QString key, QString value) {
     std::string s1 = language.toStdString();
     std::string s2 = key.toStdString();
     std::string s3 = value.toStdString();
-    m_lt.insert(s1, s2, s3);
+    m_history.set_value(m_lt, s1, s2, s3);
     emit changed(language, key, value);
+    emit history_changed();
 }
 
 LanguageTableModel* LanguageTableModel::from_dir(const mcrtlib::ffi::DataSource& ds, const QString& path, QObject* parent) {
@@ -82,15 +86,110 @@ Qt::ItemFlags LanguageTableModel::flags(const QModelIndex& index) const {
 void LanguageTableModel::add_locale_key(QString locale_key) {
     emit layoutAboutToBeChanged();
     std::string s = locale_key.toStdString();
-    m_lt.add_key(s);
+    m_history.add_key(m_lt, s);
     emit layoutChanged();
+    emit history_changed();
 }
 
 void LanguageTableModel::add_language(QString language) {
     emit layoutAboutToBeChanged();
     std::string s = language.toStdString();
-    m_lt.add_language(s);
+    m_history.add_language(m_lt, s);
     emit layoutChanged();
+    emit history_changed();
+}
+
+void LanguageTableModel::remove_locale_key(QString locale_key) {
+    emit layoutAboutToBeChanged();
+    std::string s = locale_key.toStdString();
+    m_history.remove_key(m_lt, s);
+    emit layoutChanged();
+    emit history_changed();
+}
+
+void LanguageTableModel::remove_language(QString language) {
+    emit layoutAboutToBeChanged();
+    std::string s = language.toStdString();
+    m_history.remove_language(m_lt, s);
+    emit layoutChanged();
+    emit history_changed();
+}
+
+bool LanguageTableModel::rename_locale_key(QString from, QString to) {
+    std::string s1 = from.toStdString();
+    std::string s2 = to.toStdString();
+    try {
+        m_history.rename_key(m_lt, s1, s2);
+    } catch (const std::exception& e) {
+        return false;
+    }
+    emit_keys_changed();
+    return true;
+}
+
+optional<size_t> LanguageTableModel::rename_locale_key_prefix(QString from, QString to) {
+    std::string s1 = from.toStdString();
+    std::string s2 = to.toStdString();
+    size_t count;
+    try {
+        count = m_history.rename_key_prefix(m_lt, s1, s2);
+    } catch (const std::exception& e) {
+        return std::nullopt;
+    }
+    if (count > 0) {
+        emit_keys_changed();
+    }
+    return count;
+}
+
+void LanguageTableModel::emit_keys_changed() {
+    int rows = rowCount(QModelIndex());
+    if (rows > 0) {
+        emit headerDataChanged(Qt::Vertical, 0, rows - 1);
+    }
+    emit history_changed();
+}
+
+bool LanguageTableModel::undo() {
+    emit layoutAboutToBeChanged();
+    bool result = m_history.undo(m_lt);
+    emit layoutChanged();
+    emit history_changed();
+    return result;
+}
+
+bool LanguageTableModel::redo() {
+    emit layoutAboutToBeChanged();
+    bool result = m_history.redo(m_lt);
+    emit layoutChanged();
+    emit history_changed();
+    return result;
+}
+
+bool LanguageTableModel::can_undo() const {
+    return m_history.can_undo();
+}
+
+bool LanguageTableModel::can_redo() const {
+    return m_history.can_redo();
+}
+
+void LanguageTableModel::end_edit() {
+    m_history.end_merge();
+}
+
+bool LanguageTableModel::is_dirty() const {
+    return m_history.is_dirty();
+}
+
+void LanguageTableModel::mark_saved() {
+    m_history.mark_saved();
+    emit history_changed();
+}
+
+void LanguageTableModel::clear_history() {
+    m_history.clear();
+    emit history_changed();
 }
 
 LanguageTable& LanguageTableModel::data() {
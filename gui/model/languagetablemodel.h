@@ -4,6 +4,7 @@
 #include <QObject>
 #include <QMap>
 #include <QAbstractTableModel>
+#include <optional>
 #include <mcrtlib.h>
 
 class LanguageTableModel : public QAbstractTableModel {
@@ -32,19 +33,48 @@ public:
 
     void add_language(QString language);
 
+    void remove_locale_key(QString locale_key);
+
+    void remove_language(QString language);
+
+    bool rename_locale_key(QString from, QString to);
+
+    std::optional<size_t> rename_locale_key_prefix(QString from, QString to);
+
+    bool undo();
+
+    bool redo();
+
+    [[nodiscard]] bool can_undo() const;
+
+    [[nodiscard]] bool can_redo() const;
+
+    void end_edit();
+
+    [[nodiscard]] bool is_dirty() const;
+
+    void mark_saved();
+
+    void clear_history();
+
     [[nodiscard]] mcrtlib::ffi::LanguageTable& data();
 
 signals:
 
     void changed(const QString& language, const QString& key, const QString& value);
 
+    void history_changed();
+
 private:
     mcrtlib::ffi::LanguageTable m_lt;
+    mcrtlib::ffi::LanguageTableHistory m_history;
 
     [[nodiscard]] QString get_column_name(int idx) const;
 
     [[nodiscard]] QString get_row_name(int idx) const;
 
+    void emit_keys_changed();
+
 };
 
 #endif //MCRESTOOL_LANGUAGETABLEMODEL_H
@@ -19,10 +19,9 @@ LanguageTableContainer::LanguageTableContainer(
     m_path(std::move(path)),
     m_lt(new LanguageTableModel(languagetable_new(), this)) {
     this->m_persistent = false;
-    this->m_changed = false;
     this->m_deleted = false;
 
-    connect(m_lt, SIGNAL(changed(const QString&, const QString&, const QString&)), this, SLOT(on_changed()));
+    connect(m_lt, SIGNAL(history_changed()), this, SLOT(on_changed()));
 }
 
 LanguageTableContainer::~LanguageTableContainer() {
@@ -38,7 +37,7 @@ bool LanguageTableContainer::is_persistent() const {
 }
 
 bool LanguageTableContainer::is_changed() const {
-    return this->m_changed;
+    return this->m_lt->is_dirty();
 }
 
 bool LanguageTableContainer::is_read_only() const {
@@ -57,7 +56,7 @@ void LanguageTableContainer::save() {
     this->m_lt->data().save(this->m_ds, path);
 
     m_persistent = true;
-    m_changed = false;
+    m_lt->mark_saved();
 }
 
 void LanguageTableContainer::load() {
@@ -65,11 +64,10 @@ void LanguageTableContainer::load() {
     this->m_lt->data() = languagetable_load(this->m_ds, path);
 
     m_persistent = true;
-    m_changed = false;
+    m_lt->clear_history();
     emit m_lt->layoutChanged();
 }
 
 void LanguageTableContainer::on_changed() {
-    m_changed = true;
     emit changed();
 }
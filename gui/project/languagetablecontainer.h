@@ -43,7 +43,6 @@ private:
     LanguageTableModel* m_lt;
 
     bool m_persistent;
-    bool m_changed;
     bool m_deleted;
 };
 
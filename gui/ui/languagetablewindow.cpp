@@ -1,4 +1,5 @@
 #include <QInputDialog>
+#include <QMessageBox>
 #include "languagetablewindow.h"
 #include "ui_languagetablewindow.h"
 #include <mcrtutil.h>
@@ -10,9 +11,15 @@ LanguageTableWindow::LanguageTableWindow(LanguageTableContainer* ltc, QWidget* p
     const QString& string = ltc->path();
     this->setWindowTitle(this->windowTitle() + " - " + string);
 
-    ltc->language_table()->add_language("en_us");
+    // not through the model, so that it isn't an edit that can be undone
+    ltc->language_table()->data().add_language("en_us");
 
     ui->language_table_view->setModel(ltc->language_table());
+
+    // edits of the same cell are only merged until the editor is closed
+    connect(ui->language_table_view->itemDelegate(), &QAbstractItemDelegate::closeEditor,
+            ltc->language_table(), &LanguageTableModel::end_edit);
+    connect(ltc->language_table(), &LanguageTableModel::history_changed, this, &LanguageTableWindow::history_changed);
 }
 
 void LanguageTableWindow::add_language() {
@@ -32,6 +39,71 @@ void LanguageTableWindow::add_locale_key() {
     }
 }
 
+void LanguageTableWindow::remove_language() {
+    QString language = current_header(Qt::Horizontal);
+    if (!language.isEmpty()) {
+        ltc->language_table()->remove_language(language);
+    }
+}
+
+void LanguageTableWindow::remove_locale_key() {
+    QString key = current_header(Qt::Vertical);
+    if (!key.isEmpty()) {
+        ltc->language_table()->remove_locale_key(key);
+    }
+}
+
+void LanguageTableWindow::rename_locale_key() {
+    QString from = current_header(Qt::Vertical);
+    if (from.isEmpty()) return;
+
+    bool ok;
+    QString to = QInputDialog::getText(this, tr("Rename Localization Key…"), "Localization Key:", QLineEdit::Normal,
+                                       from, &ok);
+    if (ok && to != from && !ltc->language_table()->rename_locale_key(from, to)) {
+        QMessageBox::warning(this, tr("Rename Localization Key"), tr("Could not rename '%1' to '%2'.").arg(from, to));
+    }
+}
+
+void LanguageTableWindow::rename_locale_key_prefix() {
+    bool ok;
+    QString from = QInputDialog::getText(this, tr("Rename Key Prefix…"), "Old prefix:", QLineEdit::Normal,
+                                         current_header(Qt::Vertical), &ok);
+    if (!ok || from.isEmpty()) return;
+
+    QString to = QInputDialog::getText(this, tr("Rename Key Prefix…"), "New prefix:", QLineEdit::Normal, from, &ok);
+    if (!ok || to == from) return;
+
+    if (!ltc->language_table()->rename_locale_key_prefix(from, to)) {
+        QMessageBox::warning(this, tr("Rename Key Prefix"),
+                             tr("Could not rename keys starting with '%1' to '%2'.").arg(from, to));
+    }
+}
+
+QString LanguageTableWindow::current_header(Qt::Orientation orientation) const {
+    QModelIndex index = ui->language_table_view->currentIndex();
+    if (!index.isValid()) return QString();
+
+    int section = orientation == Qt::Horizontal ? index.column() : index.row();
+    return ltc->language_table()->headerData(section, orientation, Qt::DisplayRole).toString();
+}
+
+void LanguageTableWindow::undo() {
+    ltc->language_table()->undo();
+}
+
+void LanguageTableWindow::redo() {
+    ltc->language_table()->redo();
+}
+
+bool LanguageTableWindow::can_undo() const {
+    return ltc->language_table()->can_undo();
+}
+
+bool LanguageTableWindow::can_redo() const {
+    return ltc->language_table()->can_redo();
+}
+
 void LanguageTableWindow::save() {
     ltc->save();
 }
@@ -22,16 +22,38 @@ public:
 
     void add_locale_key();
 
+    void remove_language();
+
+    void remove_locale_key();
+
+    void rename_locale_key();
+
+    void rename_locale_key_prefix();
+
+    void undo();
+
+    void redo();
+
+    [[nodiscard]] bool can_undo() const;
+
+    [[nodiscard]] bool can_redo() const;
+
     void save() override;
 
     void reload() override;
 
     EditorStatus status() override;
 
+signals:
+
+    void history_changed();
+
 private:
     QScopedPointer<Ui::LanguageTableWindow> ui;
     LanguageTableContainer* ltc;
 
+    [[nodiscard]] QString current_header(Qt::Orientation orientation) const;
+
 };
 
 #endif //MCRESTOOL_LANGUAGETABLEWINDOW_H
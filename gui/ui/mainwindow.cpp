@@ -239,14 +239,45 @@ void MainWindow::open_selected() {
 void MainWindow::sub_window_focus_change(QMdiSubWindow* window) {
     disconnect(ui->action_insert_language, &QAction::triggered, nullptr, nullptr);
     disconnect(ui->action_insert_translation_key, &QAction::triggered, nullptr, nullptr);
+    disconnect(ui->action_remove_language, &QAction::triggered, nullptr, nullptr);
+    disconnect(ui->action_remove_translation_key, &QAction::triggered, nullptr, nullptr);
+    disconnect(ui->action_rename_translation_key, &QAction::triggered, nullptr, nullptr);
+    disconnect(ui->action_rename_translation_key_prefix, &QAction::triggered, nullptr, nullptr);
+    disconnect(ui->action_undo, &QAction::triggered, nullptr, nullptr);
+    disconnect(ui->action_redo, &QAction::triggered, nullptr, nullptr);
+    disconnect(m_history_connection);
     ui->action_insert_language->setVisible(false);
     ui->action_insert_translation_key->setVisible(false);
-
-    if (auto win = qobject_cast<LanguageTableWindow*>(window)) {
+    ui->action_remove_language->setVisible(false);
+    ui->action_remove_translation_key->setVisible(false);
+    ui->action_rename_translation_key->setVisible(false);
+    ui->action_rename_translation_key_prefix->setVisible(false);
+    ui->action_undo->setEnabled(false);
+    ui->action_redo->setEnabled(false);
+
+    if (auto win = qobject_cast<LanguageTableWindow*>(window ? window->widget() : nullptr)) {
         connect(ui->action_insert_language, &QAction::triggered, win, &LanguageTableWindow::add_language);
         connect(ui->action_insert_translation_key, &QAction::triggered, win, &LanguageTableWindow::add_locale_key);
+        connect(ui->action_remove_language, &QAction::triggered, win, &LanguageTableWindow::remove_language);
+        connect(ui->action_remove_translation_key, &QAction::triggered, win, &LanguageTableWindow::remove_locale_key);
+        connect(ui->action_rename_translation_key, &QAction::triggered, win, &LanguageTableWindow::rename_locale_key);
+        connect(ui->action_rename_translation_key_prefix, &QAction::triggered, win,
+                &LanguageTableWindow::rename_locale_key_prefix);
+        connect(ui->action_undo, &QAction::triggered, win, &LanguageTableWindow::undo);
+        connect(ui->action_redo, &QAction::triggered, win, &LanguageTableWindow::redo);
         ui->action_insert_language->setVisible(true);
         ui->action_insert_translation_key->setVisible(true);
+        ui->action_remove_language->setVisible(true);
+        ui->action_remove_translation_key->setVisible(true);
+        ui->action_rename_translation_key->setVisible(true);
+        ui->action_rename_translation_key_prefix->setVisible(true);
+
+        auto update_history_actions = [this, win]() {
+            ui->action_undo->setEnabled(win->can_undo());
+            ui->action_redo->setEnabled(win->can_redo());
+        };
+        m_history_connection = connect(win, &LanguageTableWindow::history_changed, this, update_history_actions);
+        update_history_actions();
     }
 }
 
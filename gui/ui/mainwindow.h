@@ -77,6 +77,7 @@ private:
     FsTreeModel* m_fstree_model;
     GameObjectTreeModel* m_gameobject_model;
     QString m_ws_path;
+    QMetaObject::Connection m_history_connection;
 
 };
 
@@ -0,0 +1,89 @@
+//! Benchmarks for language tables the size of a large modpack's.
+
+use std::fs;
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use matryoshka::DataSource;
+
+use mcrtlib_rs::langtable::LanguageTable;
+
+const KEYS: usize = 100_000;
+const LANGUAGES: usize = 30;
+
+fn key(idx: usize) -> String {
+    format!("item.mod{}.thing{}.name", idx % 100, idx)
+}
+
+fn language(idx: usize) -> String {
+    format!("l{}_xx", idx)
+}
+
+fn build_table() -> LanguageTable {
+    let mut lt = LanguageTable::new();
+
+    for l in 0..LANGUAGES {
+        let language = language(l);
+
+        for k in 0..KEYS {
+            lt.insert(language.as_str(), key(k), format!("Value {} {}", l, k));
+        }
+    }
+
+    lt
+}
+
+/// Writes a language directory with a JSON file for each language.
+fn write_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mcrtlib-bench-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let ds = DataSource::new_dir(&dir).unwrap();
+    build_table().save(&ds, "/").unwrap();
+
+    dir
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("langtable");
+    group.sample_size(10);
+
+    group.bench_function("insert 100k keys x 30 languages", |b| {
+        b.iter(build_table)
+    });
+
+    let dir = write_dir();
+    let ds = DataSource::new_dir(&dir).unwrap();
+
+    group.bench_function("load 100k keys x 30 languages", |b| {
+        b.iter(|| LanguageTable::load(&ds, "/").unwrap())
+    });
+
+    group.finish();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn lookup(c: &mut Criterion) {
+    let lt = build_table();
+    let keys: Vec<String> = (0..KEYS).step_by(97).map(key).collect();
+
+    c.bench_function("get_key_at", |b| {
+        b.iter(|| {
+            for idx in (0..KEYS).step_by(97) {
+                black_box(lt.get_key_at(idx));
+            }
+        })
+    });
+
+    c.bench_function("get", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box(lt.get("l15_xx", key));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, insert, lookup);
+criterion_main!(benches);
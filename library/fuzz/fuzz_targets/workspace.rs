@@ -0,0 +1,17 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+
+use binserde::Mode;
+use mcrtlib_rs::workspace::serde::LIMITS;
+use mcrtlib_rs::workspace::Workspace;
+
+fuzz_target!(|data: &[u8]| {
+    // the raw binserde payload, without the workspace file header
+    let _ = binserde::deserialize_with::<Workspace>(data, Mode::dedup().with_limits(LIMITS));
+
+    // the full file as it is opened in the GUI
+    let _ = Workspace::read_from(Cursor::new(data.to_vec()));
+});
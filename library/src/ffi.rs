@@ -1,6 +1,6 @@
 use std::cell::RefCell;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Write};
+use std::io::{BufReader, Read, Write};
 use std::path::Path;
 use std::pin::Pin;
 use std::rc::Rc;
@@ -9,7 +9,11 @@ use std::{io, mem};
 use matryoshka::resfile::ResFile;
 use matryoshka::DataSource;
 
-use crate::langtable::LanguageTable;
+use crate::langtable::coverage::Coverage;
+use crate::langtable::history::EditHistory;
+use crate::langtable::query::{Filter, Pattern, Query};
+use crate::langtable::text;
+use crate::langtable::{LanguageTable, RcString};
 use crate::workspace::{DataSourceProto, FsTreeEntry, FsTreeRoot, Workspace};
 use crate::{langtable, workspace, FileType};
 
@@ -47,6 +51,7 @@ define_wrapper! {
     FsTreeEntryPrivate(Option<Rc<RefCell<FsTreeEntry>>>);
     DataSourcePrivate(Rc<DataSource>);
     LanguageTablePrivate(LanguageTable);
+    LanguageTableHistoryPrivate(EditHistory);
     WorkspacePrivate(Workspace);
 }
 
@@ -90,6 +95,56 @@ mod types {
         pub inner: Box<LanguageTablePrivate>,
     }
 
+    pub struct LanguageTableHistory {
+        pub inner: Box<LanguageTableHistoryPrivate>,
+    }
+
+    pub struct LanguageCoverage {
+        pub language: String,
+        pub total: usize,
+        pub translated: usize,
+        pub percentage: f64,
+        pub missing: Vec<String>,
+        pub extra: Vec<String>,
+        pub identical: Vec<String>,
+        /// The keys whose format specifiers don't match the reference
+        /// language.
+        pub mismatched: Vec<String>,
+    }
+
+    pub struct TextSpan {
+        pub text: String,
+        pub has_color: bool,
+        /// The color as 0xRRGGBB, if has_color is set.
+        pub color: u32,
+        pub bold: bool,
+        pub italic: bool,
+        pub underlined: bool,
+        pub strikethrough: bool,
+        pub obfuscated: bool,
+    }
+
+    pub struct LanguageTableQuery {
+        /// The text to search for, or an empty string to match every key.
+        pub pattern: String,
+        pub mode: QueryMode,
+        pub case_sensitive: bool,
+        pub in_keys: bool,
+        pub in_languages: Vec<String>,
+        /// Only match keys without a value in each of these languages.
+        pub missing_in: Vec<String>,
+        /// Only match keys with a value that changed since loading the table.
+        pub changed: bool,
+        /// Only match keys starting with this, if it isn't empty.
+        pub key_prefix: String,
+    }
+
+    pub enum QueryMode {
+        QUERY_SUBSTRING,
+        QUERY_REGEX,
+        QUERY_FUZZY,
+    }
+
     pub enum FileType {
         FILETYPE_NONE,
         FILETYPE_LANGUAGE,
@@ -128,6 +183,7 @@ mod types {
         type FsTreeRootPrivate;
         type FsTreeEntryPrivate;
         type LanguageTablePrivate;
+        type LanguageTableHistoryPrivate;
 
         fn get_file_type(ds: &DataSource, path: &str) -> FileType;
 
@@ -242,6 +298,22 @@ mod types {
 
         fn add_language(self: &mut LanguageTable, language: &str);
 
+        fn remove_value(self: &mut LanguageTable, language: &str, key: &str) -> bool;
+
+        fn remove_key(self: &mut LanguageTable, key: &str) -> bool;
+
+        fn remove_keys(self: &mut LanguageTable, keys: &[String]) -> usize;
+
+        fn remove_language(self: &mut LanguageTable, language: &str) -> bool;
+
+        fn remove_languages(self: &mut LanguageTable, languages: &[String]) -> usize;
+
+        fn rename_key(self: &mut LanguageTable, from: &str, to: &str) -> Result<()>;
+
+        fn rename_key_prefix(self: &mut LanguageTable, from: &str, to: &str) -> Result<usize>;
+
+        fn rename_keys(self: &mut LanguageTable, from: &[String], to: &[String]) -> Result<()>;
+
         fn key_count(self: &LanguageTable) -> usize;
 
         fn language_count(self: &LanguageTable) -> usize;
@@ -252,7 +324,95 @@ mod types {
 
         fn get_key_at(self: &LanguageTable, idx: usize) -> Result<String>;
 
+        fn coverage(
+            self: &LanguageTable,
+            reference: &str,
+            language: &str,
+        ) -> Result<LanguageCoverage>;
+
+        fn validate(
+            self: &LanguageTable,
+            reference: &str,
+            language: &str,
+            key: &str,
+        ) -> Vec<String>;
+
+        fn query(self: &LanguageTable, query: &LanguageTableQuery) -> Result<Vec<usize>>;
+
+        fn query_in(
+            self: &LanguageTable,
+            query: &LanguageTableQuery,
+            rows: &[usize],
+        ) -> Result<Vec<usize>>;
+
         fn save(self: &LanguageTable, ds: &DataSource, path: &str) -> Result<()>;
+
+        // LanguageTableHistory
+        fn languagetablehistory_new() -> LanguageTableHistory;
+
+        fn set_value(
+            self: &mut LanguageTableHistory,
+            lt: &mut LanguageTable,
+            language: &str,
+            key: &str,
+            value: &str,
+        );
+
+        fn clear_value(
+            self: &mut LanguageTableHistory,
+            lt: &mut LanguageTable,
+            language: &str,
+            key: &str,
+        );
+
+        fn paste(
+            self: &mut LanguageTableHistory,
+            lt: &mut LanguageTable,
+            languages: &[String],
+            keys: &[String],
+            values: &[String],
+        );
+
+        fn add_key(self: &mut LanguageTableHistory, lt: &mut LanguageTable, key: &str);
+
+        fn remove_key(self: &mut LanguageTableHistory, lt: &mut LanguageTable, key: &str);
+
+        fn add_language(self: &mut LanguageTableHistory, lt: &mut LanguageTable, language: &str);
+
+        fn remove_language(self: &mut LanguageTableHistory, lt: &mut LanguageTable, language: &str);
+
+        fn rename_key(
+            self: &mut LanguageTableHistory,
+            lt: &mut LanguageTable,
+            from: &str,
+            to: &str,
+        ) -> Result<()>;
+
+        fn rename_key_prefix(
+            self: &mut LanguageTableHistory,
+            lt: &mut LanguageTable,
+            from: &str,
+            to: &str,
+        ) -> Result<usize>;
+
+        fn undo(self: &mut LanguageTableHistory, lt: &mut LanguageTable) -> bool;
+
+        fn redo(self: &mut LanguageTableHistory, lt: &mut LanguageTable) -> bool;
+
+        fn can_undo(self: &LanguageTableHistory) -> bool;
+
+        fn can_redo(self: &LanguageTableHistory) -> bool;
+
+        fn end_merge(self: &mut LanguageTableHistory);
+
+        fn is_dirty(self: &LanguageTableHistory) -> bool;
+
+        fn mark_saved(self: &mut LanguageTableHistory);
+
+        fn clear(self: &mut LanguageTableHistory);
+
+        // Language values
+        fn text_preview(value: &str) -> Vec<TextSpan>;
     }
 }
 
@@ -357,7 +517,12 @@ impl types::Workspace {
     }
 
     fn save(&self, path: &str) -> workspace::Result<()> {
-        self.inner.write_into(BufWriter::new(File::create(path)?))?;
+        // The game data might not be fully decoded yet and still be read
+        // from the file that is about to be overwritten, so write it into a
+        // buffer first.
+        let mut buf = Vec::new();
+        self.inner.write_into(&mut buf)?;
+        File::create(path)?.write_all(&buf)?;
 
         Ok(())
     }
@@ -383,19 +548,16 @@ impl types::FsTreeRoot {
     }
 
     fn is_open(&self) -> bool {
-        let inner: &Box<FsTreeRootPrivate> = &self.inner;
+        let inner: &FsTreeRootPrivate = &self.inner;
         let inner = (**inner).as_ref();
         match inner {
             None => false,
-            Some(el) => match el.borrow().data() {
-                None => false,
-                Some(_) => true,
-            },
+            Some(el) => el.borrow().data().is_some(),
         }
     }
 
     fn tree(&self) -> types::FsTreeEntry {
-        let inner: &Box<FsTreeRootPrivate> = &self.inner;
+        let inner: &FsTreeRootPrivate = &self.inner;
         types::FsTreeEntry {
             inner: Box::new(
                 (**inner)
@@ -407,17 +569,14 @@ impl types::FsTreeRoot {
     }
 
     fn is_container_zip(&self) -> bool {
-        let inner: &Box<FsTreeRootPrivate> = &self.inner;
+        let inner: &FsTreeRootPrivate = &self.inner;
         (**inner)
             .as_ref()
-            .map_or(false, |el| match el.borrow().proto() {
-                DataSourceProto::Zip(_) => true,
-                _ => false,
-            })
+            .is_some_and(|el| matches!(el.borrow().proto(), DataSourceProto::Zip(_)))
     }
 
     fn ds(&self) -> types::DataSource {
-        let inner: &Box<FsTreeRootPrivate> = &self.inner;
+        let inner: &FsTreeRootPrivate = &self.inner;
         let inner = (**inner)
             .as_ref()
             .expect("can't get DataSource from null FsTreeRoot");
@@ -463,7 +622,7 @@ impl types::FsTreeEntry {
     }
 
     fn name(&self) -> String {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
+        let inner: &FsTreeEntryPrivate = &self.inner;
         (**inner)
             .as_ref()
             .map(|s| (**s).borrow().display_name().into())
@@ -471,7 +630,7 @@ impl types::FsTreeEntry {
     }
 
     fn file_type(&self) -> types::FileType {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
+        let inner: &FsTreeEntryPrivate = &self.inner;
         (**inner)
             .as_ref()
             .map(|s| (**s).borrow().file_type().into())
@@ -479,7 +638,7 @@ impl types::FsTreeEntry {
     }
 
     fn children_count(&self) -> usize {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
+        let inner: &FsTreeEntryPrivate = &self.inner;
         (**inner)
             .as_ref()
             .map(|a| (**a).borrow().children().len())
@@ -487,7 +646,7 @@ impl types::FsTreeEntry {
     }
 
     fn by_index(&self, idx: usize) -> types::FsTreeEntry {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
+        let inner: &FsTreeEntryPrivate = &self.inner;
         let content = (**inner)
             .as_ref()
             .and_then(|a| (**a).borrow().children().get(idx).cloned());
@@ -497,26 +656,26 @@ impl types::FsTreeEntry {
     }
 
     fn index_of(&self, child: &types::FsTreeEntry) -> isize {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
-        let ch_inner: &Box<FsTreeEntryPrivate> = &child.inner;
+        let inner: &FsTreeEntryPrivate = &self.inner;
+        let ch_inner: &FsTreeEntryPrivate = &child.inner;
         (**ch_inner)
             .as_ref()
             .and_then(|ch_inner| {
                 (**inner)
                     .as_ref()
-                    .and_then(|a| (**a).borrow().index_of(&ch_inner))
+                    .and_then(|a| (**a).borrow().index_of(ch_inner))
             })
             .map_or(-1, |a| a as isize)
     }
 
     fn parent(&self) -> types::FsTreeEntry {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
+        let inner: &FsTreeEntryPrivate = &self.inner;
         (**inner)
             .as_ref()
             .and_then(|e| (**e).borrow().parent().clone())
             .and_then(|s| s.upgrade())
             .map_or_else(
-                || types::FsTreeEntry::null(),
+                types::FsTreeEntry::null,
                 |s| types::FsTreeEntry {
                     inner: Box::new(Some(s).into()),
                 },
@@ -524,13 +683,13 @@ impl types::FsTreeEntry {
     }
 
     fn root(&self) -> types::FsTreeRoot {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
+        let inner: &FsTreeEntryPrivate = &self.inner;
         (**inner)
             .as_ref()
             .map(|e| (**e).borrow().root().clone())
             .and_then(|s| s.upgrade())
             .map_or_else(
-                || types::FsTreeRoot::null(),
+                types::FsTreeRoot::null,
                 |s| types::FsTreeRoot {
                     inner: Box::new(Some(s).into()),
                 },
@@ -538,7 +697,7 @@ impl types::FsTreeEntry {
     }
 
     fn path(&self) -> String {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
+        let inner: &FsTreeEntryPrivate = &self.inner;
         (**inner)
             .as_ref()
             .map(|e| (**e).borrow().path().to_str().unwrap().to_string())
@@ -546,7 +705,7 @@ impl types::FsTreeEntry {
     }
 
     fn is_root(&self) -> bool {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
+        let inner: &FsTreeEntryPrivate = &self.inner;
         Option::as_ref(inner)
             .map(|e| (**e).borrow().is_root())
             .unwrap_or(false)
@@ -558,8 +717,8 @@ impl types::FsTreeEntry {
 
     // Returns usize because cxx doesn't support pointer types yet
     fn to_ptr(&self) -> usize {
-        let inner: &Box<FsTreeEntryPrivate> = &self.inner;
-        match ***inner {
+        let inner: &FsTreeEntryPrivate = &self.inner;
+        match **inner {
             Some(ref a) => (Rc::as_ptr(a)) as usize,
             None => 0,
         }
@@ -689,6 +848,40 @@ impl types::LanguageTable {
         self.inner.add_language(language);
     }
 
+    fn remove_value(&mut self, language: &str, key: &str) -> bool {
+        self.inner.remove_value(language, key).is_some()
+    }
+
+    fn remove_key(&mut self, key: &str) -> bool {
+        self.inner.remove_key(key)
+    }
+
+    fn remove_keys(&mut self, keys: &[String]) -> usize {
+        self.inner.remove_keys(keys)
+    }
+
+    fn remove_language(&mut self, language: &str) -> bool {
+        self.inner.remove_language(language)
+    }
+
+    fn remove_languages(&mut self, languages: &[String]) -> usize {
+        self.inner.remove_languages(languages)
+    }
+
+    fn rename_key(&mut self, from: &str, to: &str) -> langtable::Result<()> {
+        self.inner.rename_key(from, to)
+    }
+
+    fn rename_key_prefix(&mut self, from: &str, to: &str) -> langtable::Result<usize> {
+        self.inner.rename_key_prefix(from, to)
+    }
+
+    // Takes two lists because cxx doesn't support tuples yet
+    fn rename_keys(&mut self, from: &[String], to: &[String]) -> langtable::Result<()> {
+        self.inner
+            .rename_keys(from.iter().map(|s| &**s).zip(to.iter().map(|s| &**s)))
+    }
+
     fn key_count(&self) -> usize {
         self.inner.key_count()
     }
@@ -718,11 +911,166 @@ impl types::LanguageTable {
             .ok_or("key not found in table")
     }
 
+    fn coverage(
+        &self,
+        reference: &str,
+        language: &str,
+    ) -> Result<types::LanguageCoverage, &'static str> {
+        self.inner
+            .coverage(reference, language)
+            .map(|c| c.into())
+            .ok_or("language not found in table")
+    }
+
+    // Returns the problems with the value of key in language as messages
+    fn validate(&self, reference: &str, language: &str, key: &str) -> Vec<String> {
+        match (
+            self.inner.get(reference, key),
+            self.inner.get(language, key),
+        ) {
+            (Some(expected), Some(actual)) => text::validate(expected, actual)
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn query(&self, query: &types::LanguageTableQuery) -> langtable::Result<Vec<usize>> {
+        self.inner.query(&query.into())
+    }
+
+    // Only searches the rows in rows, for refining the last result while the
+    // user is typing
+    fn query_in(
+        &self,
+        query: &types::LanguageTableQuery,
+        rows: &[usize],
+    ) -> langtable::Result<Vec<usize>> {
+        self.inner.query_in(&query.into(), rows.iter().copied())
+    }
+
     fn save(&self, ds: &types::DataSource, path: &str) -> langtable::Result<()> {
         self.inner.save(&ds.inner, path)
     }
 }
 
+fn languagetablehistory_new() -> types::LanguageTableHistory {
+    types::LanguageTableHistory {
+        inner: Box::new(EditHistory::new().into()),
+    }
+}
+
+impl types::LanguageTableHistory {
+    fn set_value(&mut self, lt: &mut types::LanguageTable, language: &str, key: &str, value: &str) {
+        self.inner
+            .set_value(&mut lt.inner, language, key, Some(value.to_string()));
+    }
+
+    fn clear_value(&mut self, lt: &mut types::LanguageTable, language: &str, key: &str) {
+        self.inner.set_value(&mut lt.inner, language, key, None);
+    }
+
+    // Takes three lists because cxx doesn't support tuples yet. Empty values
+    // clear their cell.
+    fn paste(
+        &mut self,
+        lt: &mut types::LanguageTable,
+        languages: &[String],
+        keys: &[String],
+        values: &[String],
+    ) {
+        let cells = languages
+            .iter()
+            .zip(keys.iter())
+            .zip(values.iter())
+            .map(|((l, k), v)| (&**l, &**k, Some(v.clone()).filter(|v| !v.is_empty())));
+        self.inner.paste(&mut lt.inner, cells);
+    }
+
+    fn add_key(&mut self, lt: &mut types::LanguageTable, key: &str) {
+        self.inner.add_key(&mut lt.inner, key);
+    }
+
+    fn remove_key(&mut self, lt: &mut types::LanguageTable, key: &str) {
+        self.inner.remove_key(&mut lt.inner, key);
+    }
+
+    fn add_language(&mut self, lt: &mut types::LanguageTable, language: &str) {
+        self.inner.add_language(&mut lt.inner, language);
+    }
+
+    fn remove_language(&mut self, lt: &mut types::LanguageTable, language: &str) {
+        self.inner.remove_language(&mut lt.inner, language);
+    }
+
+    fn rename_key(
+        &mut self,
+        lt: &mut types::LanguageTable,
+        from: &str,
+        to: &str,
+    ) -> langtable::Result<()> {
+        self.inner.rename_key(&mut lt.inner, from, to)
+    }
+
+    fn rename_key_prefix(
+        &mut self,
+        lt: &mut types::LanguageTable,
+        from: &str,
+        to: &str,
+    ) -> langtable::Result<usize> {
+        self.inner.rename_key_prefix(&mut lt.inner, from, to)
+    }
+
+    fn undo(&mut self, lt: &mut types::LanguageTable) -> bool {
+        self.inner.undo(&mut lt.inner)
+    }
+
+    fn redo(&mut self, lt: &mut types::LanguageTable) -> bool {
+        self.inner.redo(&mut lt.inner)
+    }
+
+    fn can_undo(&self) -> bool {
+        self.inner.can_undo()
+    }
+
+    fn can_redo(&self) -> bool {
+        self.inner.can_redo()
+    }
+
+    fn end_merge(&mut self) {
+        self.inner.end_merge();
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.inner.is_dirty()
+    }
+
+    fn mark_saved(&mut self) {
+        self.inner.mark_saved();
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+fn text_preview(value: &str) -> Vec<types::TextSpan> {
+    text::preview(value)
+        .into_iter()
+        .map(|span| types::TextSpan {
+            text: span.text,
+            has_color: span.style.color.is_some(),
+            color: span.style.color.map_or(0, |c| c.rgb()),
+            bold: span.style.bold,
+            italic: span.style.italic,
+            underlined: span.style.underlined,
+            strikethrough: span.style.strikethrough,
+            obfuscated: span.style.obfuscated,
+        })
+        .collect()
+}
+
 impl From<matryoshka::DirEntry> for types::DirEntry {
     fn from(e: matryoshka::DirEntry) -> Self {
         types::DirEntry {
@@ -747,6 +1095,57 @@ impl From<matryoshka::FileInfo> for types::FileInfo {
     }
 }
 
+impl From<Coverage> for types::LanguageCoverage {
+    fn from(c: Coverage) -> Self {
+        let names = |keys: Vec<RcString>| keys.iter().map(|k| k.to_string()).collect();
+
+        types::LanguageCoverage {
+            language: c.language.to_string(),
+            total: c.total,
+            translated: c.translated,
+            percentage: c.percentage(),
+            missing: names(c.missing),
+            extra: names(c.extra),
+            identical: names(c.identical),
+            mismatched: c.mismatched.iter().map(|m| m.key.to_string()).collect(),
+        }
+    }
+}
+
+impl From<&types::LanguageTableQuery> for Query {
+    fn from(q: &types::LanguageTableQuery) -> Self {
+        let text = q.pattern.clone();
+        let pattern = match q.mode {
+            _ if text.is_empty() => None,
+            types::QueryMode::QUERY_REGEX => Some(Pattern::Regex(text)),
+            types::QueryMode::QUERY_FUZZY => Some(Pattern::Fuzzy(text)),
+            _ => Some(Pattern::Substring(text)),
+        };
+
+        let mut filters: Vec<Filter> = q
+            .missing_in
+            .iter()
+            .map(|l| Filter::MissingIn(l.clone()))
+            .collect();
+
+        if q.changed {
+            filters.push(Filter::Changed);
+        }
+
+        if !q.key_prefix.is_empty() {
+            filters.push(Filter::KeyPrefix(q.key_prefix.clone()));
+        }
+
+        Query {
+            pattern,
+            case_sensitive: q.case_sensitive,
+            in_keys: q.in_keys,
+            in_languages: q.in_languages.clone(),
+            filters,
+        }
+    }
+}
+
 impl From<crate::FileType> for types::FileType {
     fn from(t: crate::FileType) -> Self {
         match t {
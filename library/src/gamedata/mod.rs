@@ -1,36 +1,50 @@
 use std::borrow::Cow;
 use std::cell::{Ref, RefCell, RefMut};
 use std::collections::{HashMap, HashSet};
-use std::ffi::OsStr;
 use std::io::Read;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
-use binserde::{BinDeserialize, BinSerialize};
+use binserde::lazy::Lazy;
+use binserde::{BinDeserialize, BinDeserializer, BinSerialize, BinSerializer};
 use matryoshka::OpenOptions;
-use mcplatfm::{Ident, Identifier};
+use mcplatfm::resource;
+use mcplatfm::{Ident, IdentId, Identifier, Interner, PackType, ParseError, ResourceKind};
 
 use crate::workspace::{FsTreeRoot, TreeChangeDispatcher};
 
 pub mod serde;
 
+/// The game objects of a workspace and the references between them.
+///
+/// The references and registries are each stored as a [`Lazy`] value, so
+/// that when a workspace is opened, they are only decoded once they are
+/// first used. The identifiers they refer to are interned in `ids`, which is
+/// always read eagerly.
 #[derive(BinDeserialize, BinSerialize)]
 pub struct GameData {
-    refs: GameDataReferences,
+    ids: Interner,
 
-    blocks: Registry<Block>,
-    items: Registry<Item>,
+    refs: Lazy<GameDataReferences>,
+
+    blocks: Lazy<Registry<Block>>,
+    items: Lazy<Registry<Item>>,
 
     #[binserde(skip)]
     dispatcher: Rc<RefCell<TreeChangeDispatcher>>,
 }
 
+impl Default for GameData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GameData {
     pub fn new() -> Self {
         GameData {
-            refs: GameDataReferences {
-                map: Default::default(),
-            },
+            ids: Interner::new(),
+            refs: Default::default(),
             blocks: Default::default(),
             items: Default::default(),
             dispatcher: Rc::new(RefCell::new(TreeChangeDispatcher::new())),
@@ -38,13 +52,14 @@ impl GameData {
     }
 
     pub fn reset(&mut self) {
-        self.refs.map.clear();
-        self.items.clear();
-        self.blocks.clear();
+        self.ids.clear();
+        self.refs = Lazy::default();
+        self.items = Lazy::default();
+        self.blocks = Lazy::default();
     }
 
     pub fn collect_usages(&mut self, roots: &[Rc<RefCell<FsTreeRoot>>]) {
-        self.refs.map.clear();
+        let mut refs = GameDataReferences::default();
 
         for x in roots.iter() {
             let x = x.borrow();
@@ -53,18 +68,21 @@ impl GameData {
                 Some(data) => data.ds(),
             };
 
-            for entry in ds.list_dir("assets").unwrap_or_default() {
+            for entry in ds.list_dir(PackType::Assets.dir()).unwrap_or_default() {
                 if entry.info().is_dir() {
                     let namespace = entry.file_name().to_str().unwrap();
+                    let lang_dir = ResourceKind::Lang.namespace_dir(namespace);
+
+                    for lang_file in ds.list_dir(lang_dir).unwrap_or_default() {
+                        let lang = match resource::parse_path(lang_file.path()) {
+                            Some((ResourceKind::Lang, id)) => id,
+                            _ => continue,
+                        };
 
-                    for lang_file in ds.list_dir(entry.path().join("lang")).unwrap_or_default() {
-                        if lang_file.info().is_file()
-                            && lang_file.path().extension() == Some(OsStr::new("json"))
-                        {
-                            let lang_name = lang_file.path().file_stem().unwrap().to_str().unwrap();
+                        if lang_file.info().is_file() {
                             let dl_source = DependencyLink::Language(
-                                namespace.to_string(),
-                                lang_name.to_string(),
+                                lang.namespace().to_string(),
+                                lang.path().to_string(),
                             );
 
                             // Read entire file into string to increase speed (serde-rs/json#160)
@@ -87,28 +105,38 @@ impl GameData {
                                 }
                             };
 
-                            for k in part.keys() {
-                                if let Some(k) = k.strip_prefix("block.") {
+                            for key in part.keys() {
+                                if let Some(k) = key.strip_prefix("block.") {
                                     let mut split = k.split('.');
                                     if let Some(block_name) =
                                         split.next().and_then(|a| split.next().map(|b| (a, b)))
                                     {
-                                        let id =
-                                            Identifier::from_components(block_name.0, block_name.1);
-
-                                        self.refs
-                                            .insert(dl_source.clone(), DependencyLink::Block(id));
+                                        match Identifier::try_from_components(
+                                            block_name.0,
+                                            block_name.1,
+                                        ) {
+                                            Ok(id) => refs.insert(
+                                                dl_source.clone(),
+                                                DependencyLink::Block(self.ids.intern(&id)),
+                                            ),
+                                            Err(e) => warn_invalid_key(key, &e),
+                                        }
                                     }
-                                } else if let Some(k) = k.strip_prefix("item.") {
+                                } else if let Some(k) = key.strip_prefix("item.") {
                                     let mut split = k.split('.');
                                     if let Some(item_name) =
                                         split.next().and_then(|a| split.next().map(|b| (a, b)))
                                     {
-                                        let id =
-                                            Identifier::from_components(item_name.0, item_name.1);
-
-                                        self.refs
-                                            .insert(dl_source.clone(), DependencyLink::Item(id));
+                                        match Identifier::try_from_components(
+                                            item_name.0,
+                                            item_name.1,
+                                        ) {
+                                            Ok(id) => refs.insert(
+                                                dl_source.clone(),
+                                                DependencyLink::Item(self.ids.intern(&id)),
+                                            ),
+                                            Err(e) => warn_invalid_key(key, &e),
+                                        }
                                     }
                                 }
                             }
@@ -117,72 +145,123 @@ impl GameData {
                 }
             }
         }
+
+        self.refs = Lazy::new(refs);
     }
 
-    pub fn create_dummies(&mut self) {
-        let vs: HashSet<_> = self.refs.map.values().flat_map(|v| v.iter()).collect();
+    /// Registers an automatic game object for every referenced ID that
+    /// doesn't have one yet.
+    ///
+    /// This decodes the registries if they haven't been yet. If one of them
+    /// can't be decoded, the error is returned and nothing is changed; the
+    /// registry keeps its unreadable contents, so saving fails too instead of
+    /// writing it out empty.
+    pub fn create_dummies(&mut self) -> binserde::Result<()> {
+        let refs = self.refs.get_mut()?;
+        let blocks = self.blocks.get_mut()?;
+        let items = self.items.get_mut()?;
+        let ids = &self.ids;
+        let vs: HashSet<_> = refs.map.values().flat_map(|v| v.iter()).collect();
 
-        self.blocks.iter_mut().for_each(|b| b.mark_auto(false));
-        self.items.iter_mut().for_each(|i| i.mark_auto(false));
+        blocks.iter_mut().for_each(|b| b.mark_auto(false));
+        items.iter_mut().for_each(|i| i.mark_auto(false));
 
         for entry in vs {
             match entry {
                 DependencyLink::Block(id) => {
-                    match self
-                        .blocks
-                        .register(Block::new(GameObjectBase::auto(id.clone())))
-                    {
+                    match blocks.register(ids, Block::new(GameObjectBase::auto(*id))) {
                         Ok(_) => {}
-                        Err(b) => {
-                            b.mark_auto(true);
-                        }
+                        Err(RegisterError::Exists(b)) => b.mark_auto(true),
+                        Err(e) => warn_not_registered(&e),
                     }
                 }
                 DependencyLink::Item(id) => {
-                    match self
-                        .items
-                        .register(Item::new(GameObjectBase::auto(id.clone())))
-                    {
+                    match items.register(ids, Item::new(GameObjectBase::auto(*id))) {
                         Ok(_) => {}
-                        Err(i) => {
-                            i.mark_auto(true);
-                        }
+                        Err(RegisterError::Exists(i)) => i.mark_auto(true),
+                        Err(e) => warn_not_registered(&e),
                     }
                 }
                 _ => {}
             }
         }
+
+        Ok(())
     }
 
-    pub fn dispatcher(&self) -> Ref<TreeChangeDispatcher> {
+    pub fn dispatcher(&self) -> Ref<'_, TreeChangeDispatcher> {
         self.dispatcher.borrow()
     }
 
-    pub fn dispatcher_mut(&self) -> RefMut<TreeChangeDispatcher> {
+    pub fn dispatcher_mut(&self) -> RefMut<'_, TreeChangeDispatcher> {
         self.dispatcher.borrow_mut()
     }
 
-    pub fn blocks(&self) -> &Registry<Block> {
-        &self.blocks
+    /// Returns the identifiers the game objects' IDs refer to.
+    pub fn ids(&self) -> &Interner {
+        &self.ids
     }
 
-    pub fn items(&self) -> &Registry<Item> {
-        &self.items
+    /// Returns the blocks, decoding them first if they haven't been since
+    /// the workspace was opened.
+    pub fn blocks(&self) -> binserde::Result<&Registry<Block>> {
+        self.blocks.get()
     }
 
-    pub fn get_block(&self, id: &Ident) -> Option<&Block> {
-        self.blocks.by_id(id)
+    /// Returns the items, decoding them first if they haven't been since the
+    /// workspace was opened.
+    pub fn items(&self) -> binserde::Result<&Registry<Item>> {
+        self.items.get()
+    }
+
+    pub fn get_block(&self, id: &Ident) -> binserde::Result<Option<&Block>> {
+        // an ID that was never interned can't be registered
+        match self.ids.get(id) {
+            None => Ok(None),
+            Some(id) => Ok(self.blocks()?.by_id(id)),
+        }
     }
 }
 
-#[derive(BinSerialize, BinDeserialize)]
+fn warn_invalid_key(key: &str, e: &ParseError) {
+    eprintln!("warning: skipping invalid language key '{}': {}", key, e);
+}
+
+fn warn_not_registered<T>(e: &RegisterError<'_, T>) {
+    match e {
+        RegisterError::Exists(_) => {}
+        RegisterError::InvalidId(id, e) => {
+            eprintln!("warning: skipping object with invalid ID '{}': {}", id, e)
+        }
+        RegisterError::UnknownId => {
+            eprintln!("warning: skipping object with an ID from another workspace")
+        }
+    }
+}
+
+/// Game objects of one type, looked up by their interned ID.
+///
+/// Objects are kept in the order they were registered in.
 pub struct Registry<T> {
     inner: Vec<T>,
+    index: HashMap<IdentId, usize>,
+}
+
+/// Why an object couldn't be added to a [`Registry`].
+pub enum RegisterError<'a, T> {
+    /// An object with the same ID is already registered, which is returned.
+    Exists(&'a mut T),
+    InvalidId(Identifier, ParseError),
+    /// The ID doesn't come from the interner that was passed in.
+    UnknownId,
 }
 
 impl<T> Registry<T> {
     pub fn new() -> Self {
-        Registry { inner: Vec::new() }
+        Registry {
+            inner: Vec::new(),
+            index: HashMap::new(),
+        }
     }
 }
 
@@ -196,52 +275,96 @@ impl<T> Registry<T>
 where
     T: GameObject,
 {
-    pub fn register(&mut self, object: T) -> Result<&mut T, &mut T> {
+    /// Adds `object` to the registry, unless its ID is invalid or an object
+    /// with the same ID is already registered. `ids` is the interner the
+    /// object's ID comes from.
+    pub fn register(&mut self, ids: &Interner, object: T) -> Result<&mut T, RegisterError<'_, T>> {
         let id = object.base().id();
 
-        match self.find(id) {
-            Ok(idx) => {
-                // this id already exists
-                Err(&mut self.inner[idx])
-            }
-            Err(idx) => {
-                self.inner.insert(idx, object);
-                Ok(&mut self.inner[idx])
+        match self.index.get(&id) {
+            Some(&idx) => Err(RegisterError::Exists(&mut self.inner[idx])),
+            None => {
+                let ident = ids.resolve(id).ok_or(RegisterError::UnknownId)?;
+                ident
+                    .validate()
+                    .map_err(|e| RegisterError::InvalidId(ident.to_identifier(), e))?;
+
+                Ok(self.insert(object))
             }
         }
     }
 
-    pub fn deregister(&mut self, id: &Ident) -> Result<T, ()> {
-        match self.find(id) {
-            Ok(idx) => Ok(self.inner.remove(idx)),
-            Err(_) => Err(()),
+    fn insert(&mut self, object: T) -> &mut T {
+        let idx = self.inner.len();
+        self.index.insert(object.base().id(), idx);
+        self.inner.push(object);
+        &mut self.inner[idx]
+    }
+
+    /// Removes the object with the ID `id` from the registry. The objects
+    /// after it move up by one, so that the registration order is kept.
+    pub fn deregister(&mut self, id: IdentId) -> Option<T> {
+        let idx = self.index.remove(&id)?;
+        let object = self.inner.remove(idx);
+
+        for moved in &self.inner[idx..] {
+            *self.index.get_mut(&moved.base().id()).unwrap() -= 1;
         }
+
+        Some(object)
     }
 
     pub fn clear(&mut self) {
         self.inner.clear();
+        self.index.clear();
     }
 
-    pub fn ids(&self) -> impl Iterator<Item = &Ident> {
+    pub fn ids(&self) -> impl Iterator<Item = IdentId> + '_ {
         self.inner.iter().map(|el| el.base().id())
     }
 
-    pub fn by_id(&self, id: &Ident) -> Option<&T> {
-        let idx = self.find(id).ok()?;
+    pub fn by_id(&self, id: IdentId) -> Option<&T> {
+        let idx = *self.index.get(&id)?;
         Some(&self.inner[idx])
     }
 
-    pub fn by_id_mut(&mut self, id: &Ident) -> Option<&mut T> {
-        let idx = self.find(id).ok()?;
+    pub fn by_id_mut(&mut self, id: IdentId) -> Option<&mut T> {
+        let idx = *self.index.get(&id)?;
         Some(&mut self.inner[idx])
     }
 
-    pub fn contains(&self, id: &Ident) -> bool {
-        self.find(id).is_ok()
+    pub fn contains(&self, id: IdentId) -> bool {
+        self.index.contains_key(&id)
     }
+}
+
+impl<T> BinSerialize for Registry<T>
+where
+    T: BinSerialize,
+{
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> binserde::Result<()> {
+        self.inner.serialize(serializer)
+    }
+}
+
+impl<'de, T> BinDeserialize<'de> for Registry<T>
+where
+    T: GameObject + BinDeserialize<'de>,
+{
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> binserde::Result<Self> {
+        let mut registry = Registry::new();
+
+        // The interner the IDs come from has already rejected invalid
+        // identifiers when it was read.
+        for object in Vec::<T>::deserialize(deserializer)? {
+            if registry.contains(object.base().id()) {
+                eprintln!("warning: skipping duplicate game object");
+            } else {
+                registry.insert(object);
+            }
+        }
 
-    fn find(&self, id: &Ident) -> Result<usize, usize> {
-        self.inner.binary_search_by_key(&id, |el| el.base().id())
+        Ok(registry)
     }
 }
 
@@ -259,7 +382,7 @@ impl<T> DerefMut for Registry<T> {
     }
 }
 
-#[derive(BinSerialize, BinDeserialize)]
+#[derive(Default, BinSerialize, BinDeserialize)]
 struct GameDataReferences {
     map: HashMap<DependencyLink, HashSet<DependencyLink>>,
 }
@@ -273,8 +396,8 @@ impl GameDataReferences {
 #[derive(Debug, Clone, Hash, Eq, PartialEq, BinDeserialize, BinSerialize)]
 enum DependencyLink {
     Language(String, String),
-    Block(Identifier),
-    Item(Identifier),
+    Block(IdentId),
+    Item(IdentId),
 }
 
 pub trait GameObject {
@@ -297,51 +420,64 @@ macro_rules! impl_game_object {
     };
 }
 
+#[derive(BinSerialize, BinDeserialize)]
 pub struct GameObjectBase {
-    manual: bool,
-    auto: AutoStatus,
-    id: Identifier,
+    id: IdentId,
+    #[binserde(with = "self::serde::status_bits")]
+    status: Status,
 }
 
 impl GameObjectBase {
-    pub fn new(id: Identifier) -> Self {
+    pub fn new(id: IdentId) -> Self {
         GameObjectBase {
-            manual: true,
-            auto: AutoStatus::No,
             id,
+            status: Status {
+                manual: true,
+                auto: AutoStatus::No,
+            },
         }
     }
 
-    pub fn auto(id: Identifier) -> Self {
+    pub fn auto(id: IdentId) -> Self {
         GameObjectBase {
-            manual: false,
-            auto: AutoStatus::Yes,
             id,
+            status: Status {
+                manual: false,
+                auto: AutoStatus::Yes,
+            },
         }
     }
 
-    pub fn id(&self) -> &Ident {
-        &self.id
+    pub fn id(&self) -> IdentId {
+        self.id
     }
 
     pub fn mark_manual(&mut self, flag: bool) {
-        self.manual = flag;
+        self.status.manual = flag;
     }
 
     pub fn mark_auto(&mut self, flag: bool) {
-        if !flag && self.auto == AutoStatus::Yes {
-            self.auto = AutoStatus::No;
-        } else if flag && self.auto == AutoStatus::No {
-            self.auto = AutoStatus::Yes;
+        let auto = &mut self.status.auto;
+
+        if !flag && *auto == AutoStatus::Yes {
+            *auto = AutoStatus::No;
+        } else if flag && *auto == AutoStatus::No {
+            *auto = AutoStatus::Yes;
         }
     }
 
     pub fn marked_for_deletion(&self) -> bool {
-        !self.manual && self.auto != AutoStatus::Yes
+        !self.status.manual && self.status.auto != AutoStatus::Yes
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Status {
+    manual: bool,
+    auto: AutoStatus,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 enum AutoStatus {
     No,
     Yes,
@@ -397,3 +533,79 @@ impl Item {
 }
 
 impl_game_object!(Item, base);
+
+#[test]
+fn lazy_registries() {
+    use std::io::Cursor;
+
+    use binserde::Mode;
+
+    let stone = Ident::new("test:stone");
+    let mut gd = GameData::new();
+    let block = Block::new(GameObjectBase::new(gd.ids.intern(stone)));
+    let blocks = gd.blocks.get_mut().unwrap();
+    assert!(blocks.register(&gd.ids, block).is_ok());
+
+    let buf = binserde::serialize_with(&gd, Mode::dedup()).unwrap();
+    let gd: GameData = binserde::lazy::deserialize_from(Cursor::new(buf), Mode::dedup()).unwrap();
+    assert!(!gd.blocks.is_loaded());
+    assert!(!gd.items.is_loaded());
+
+    assert!(gd.get_block(stone).unwrap().is_some());
+    assert!(gd.blocks.is_loaded());
+    assert!(!gd.items.is_loaded());
+}
+
+#[test]
+fn register_validates_ids() {
+    let mut ids = Interner::new();
+    let mut blocks = Registry::new();
+    let stone = ids.intern(Ident::new("test:stone"));
+    let invalid = ids.intern(Ident::new("Test:Stone Block"));
+    let mut other = Interner::new();
+    let unknown = ["test:a", "test:b", "test:c"]
+        .iter()
+        .map(|n| other.intern(Ident::new(n)))
+        .last()
+        .unwrap();
+
+    assert!(blocks
+        .register(&ids, Block::new(GameObjectBase::new(stone)))
+        .is_ok());
+    assert!(matches!(
+        blocks.register(&ids, Block::new(GameObjectBase::auto(stone))),
+        Err(RegisterError::Exists(_))
+    ));
+    assert!(matches!(
+        blocks.register(&ids, Block::new(GameObjectBase::new(invalid))),
+        Err(RegisterError::InvalidId(..))
+    ));
+    assert!(matches!(
+        blocks.register(&ids, Block::new(GameObjectBase::new(unknown))),
+        Err(RegisterError::UnknownId)
+    ));
+    assert_eq!(1, blocks.len());
+}
+
+#[test]
+fn deregister_keeps_order() {
+    let mut ids = Interner::new();
+    let mut blocks = Registry::new();
+    let names = ["test:a", "test:b", "test:c", "test:d"];
+    let handles: Vec<_> = names.iter().map(|n| ids.intern(Ident::new(n))).collect();
+
+    for &id in &handles {
+        assert!(blocks
+            .register(&ids, Block::new(GameObjectBase::new(id)))
+            .is_ok());
+    }
+
+    assert!(blocks.deregister(handles[1]).is_some());
+    assert!(blocks.deregister(handles[1]).is_none());
+    assert_eq!(
+        vec![handles[0], handles[2], handles[3]],
+        blocks.ids().collect::<Vec<_>>()
+    );
+    assert!(blocks.by_id(handles[3]).is_some());
+    assert!(!blocks.contains(handles[1]));
+}
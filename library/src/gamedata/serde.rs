@@ -1,46 +1,45 @@
-use byteorder::{ReadBytesExt, WriteBytesExt};
+/// Packs a [`Status`] into a single byte.
+pub(super) mod status_bits {
+    use byteorder::{ReadBytesExt, WriteBytesExt};
 
-use binserde::de::BinDeserializer;
-use binserde::{BinDeserialize, BinSerialize, BinSerializer};
-use binserde::{Error, Result};
+    use binserde::{BinDeserializer, BinSerializer, Result};
 
-use crate::gamedata::*;
+    use crate::gamedata::{AutoStatus, Status};
 
-impl<'de> BinDeserialize<'de> for GameObjectBase {
-    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Self, Error> {
-        let id = Identifier::deserialize(&mut deserializer)?;
-        let bits = deserializer.pipe().read_u8()?;
-        let manual = bits & 1 != 0;
-        let auto = if bits & 2 != 0 {
-            if bits & 4 != 0 {
-                AutoStatus::Deleted
-            } else {
-                AutoStatus::Yes
-            }
-        } else {
-            AutoStatus::No
-        };
-
-        Ok(GameObjectBase { manual, auto, id })
-    }
-}
+    const MANUAL: u8 = 1;
+    const AUTO: u8 = 2;
+    const DELETED: u8 = 4;
 
-impl BinSerialize for GameObjectBase {
-    fn serialize<S: BinSerializer>(&self, mut serializer: S) -> Result<()> {
-        self.id.serialize(&mut serializer)?;
+    pub fn serialize<S: BinSerializer>(status: &Status, mut serializer: S) -> Result<()> {
         let mut bits = 0;
 
-        if self.manual {
-            bits |= 1;
+        if status.manual {
+            bits |= MANUAL;
         }
 
-        bits |= match self.auto {
+        bits |= match status.auto {
             AutoStatus::No => 0,
-            AutoStatus::Yes => 2,
-            AutoStatus::Deleted => 5,
+            AutoStatus::Yes => AUTO,
+            AutoStatus::Deleted => AUTO | DELETED,
         };
 
         serializer.pipe().write_u8(bits)?;
         Ok(())
     }
+
+    pub fn deserialize<'de, D: BinDeserializer<'de>>(mut deserializer: D) -> Result<Status> {
+        let bits = deserializer.pipe().read_u8()?;
+        let manual = bits & MANUAL != 0;
+        let auto = if bits & AUTO != 0 {
+            if bits & DELETED != 0 {
+                AutoStatus::Deleted
+            } else {
+                AutoStatus::Yes
+            }
+        } else {
+            AutoStatus::No
+        };
+
+        Ok(Status { manual, auto })
+    }
 }
@@ -0,0 +1,137 @@
+//! How complete the translations in a [`LanguageTable`] are, compared to a
+//! reference language.
+
+use crate::langtable::text::{self, FormatSpecifier, Problem};
+use crate::langtable::{LanguageTable, RcString};
+
+/// The translation status of a language compared to the reference language.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Coverage {
+    pub language: RcString,
+    /// The number of keys that have a value in the reference language.
+    pub total: usize,
+    /// The number of those keys that also have a value in this language.
+    pub translated: usize,
+    /// Keys that have a value in the reference language but not in this
+    /// one.
+    pub missing: Vec<RcString>,
+    /// Keys that have a value in this language but not in the reference
+    /// language.
+    pub extra: Vec<RcString>,
+    /// Keys whose value is the same as in the reference language, which
+    /// usually means they haven't been translated yet.
+    pub identical: Vec<RcString>,
+    /// Keys whose value doesn't use the same format arguments as in the
+    /// reference language, as found by [`text::validate`].
+    pub mismatched: Vec<FormatMismatch>,
+}
+
+impl Coverage {
+    /// Returns the percentage of the keys of the reference language that
+    /// have a value in this language.
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.translated as f64 / self.total as f64 * 100.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FormatMismatch {
+    pub key: RcString,
+    pub expected: Vec<FormatSpecifier>,
+    pub actual: Vec<FormatSpecifier>,
+}
+
+impl LanguageTable {
+    /// Compares `language` to `reference`. Returns `None` if either of them
+    /// isn't in the table.
+    pub fn coverage(&self, reference: &str, language: &str) -> Option<Coverage> {
+        if !self.contains_language(reference) {
+            return None;
+        }
+
+        let language = self.languages.get(language)?;
+
+        let mut c = Coverage {
+            language: language.clone(),
+            total: 0,
+            translated: 0,
+            missing: vec![],
+            extra: vec![],
+            identical: vec![],
+            mismatched: vec![],
+        };
+
+        for key in self.keys.iter() {
+            match (self.get(reference, key), self.get(language, key)) {
+                (Some(expected), Some(actual)) => {
+                    c.total += 1;
+                    c.translated += 1;
+
+                    if expected == actual {
+                        c.identical.push(key.clone());
+                        continue;
+                    }
+
+                    for problem in text::validate(expected, actual) {
+                        if let Problem::PlaceholderMismatch { expected, actual } = problem {
+                            c.mismatched.push(FormatMismatch {
+                                key: key.clone(),
+                                expected,
+                                actual,
+                            });
+                        }
+                    }
+                }
+                (Some(_), None) => {
+                    c.total += 1;
+                    c.missing.push(key.clone());
+                }
+                (None, Some(_)) => c.extra.push(key.clone()),
+                (None, None) => {}
+            }
+        }
+
+        Some(c)
+    }
+
+    /// Compares every language in the table except `reference` to it.
+    pub fn coverage_report(&self, reference: &str) -> Vec<Coverage> {
+        self.languages
+            .iter()
+            .filter(|l| &***l != reference)
+            .filter_map(|l| self.coverage(reference, l))
+            .collect()
+    }
+}
+
+#[test]
+fn language_coverage() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "Stone");
+    lt.insert("en_us", "b", "%s and %s");
+    lt.insert("en_us", "c", "Dirt");
+    lt.insert("en_us", "d", "%s by %s");
+    lt.insert("de_de", "a", "Stone");
+    lt.insert("de_de", "b", "%1$s und %1$s");
+    lt.insert("de_de", "d", "%2$s von %1$s");
+    lt.insert("de_de", "e", "Extra");
+
+    let names = |keys: &[RcString]| keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+
+    let c = lt.coverage("en_us", "de_de").unwrap();
+    assert_eq!(4, c.total);
+    assert_eq!(3, c.translated);
+    assert_eq!(75.0, c.percentage());
+    assert_eq!(vec!["c"], names(&c.missing));
+    assert_eq!(vec!["e"], names(&c.extra));
+    assert_eq!(vec!["a"], names(&c.identical));
+    assert_eq!(1, c.mismatched.len());
+    assert_eq!("b", &*c.mismatched[0].key);
+
+    assert_eq!(None, lt.coverage("en_us", "fr_fr"));
+    assert_eq!(1, lt.coverage_report("en_us").len());
+}
@@ -0,0 +1,568 @@
+//! Undoable edits of a [`LanguageTable`].
+//!
+//! [`EditHistory`] applies edits to a table and records how to revert them,
+//! so that they can be undone and redone. It doesn't own the table, but
+//! expects every edit of it to go through the history: editing the table
+//! directly in between makes undoing edits restore the wrong state.
+
+use crate::langtable::{LanguageTable, LanguageTablePart, RcString, Result};
+
+#[derive(Debug, Clone)]
+enum Command {
+    /// Sets or, if `value` is `None`, removes a value. Setting a value adds
+    /// the language and key if they aren't in the table.
+    SetValue {
+        language: RcString,
+        key: RcString,
+        value: Option<String>,
+    },
+    InsertKey {
+        key: RcString,
+        index: usize,
+        values: Vec<(RcString, String)>,
+    },
+    RemoveKey(RcString),
+    InsertLanguage {
+        language: RcString,
+        index: usize,
+        part: LanguageTablePart,
+    },
+    RemoveLanguage(RcString),
+    /// Renames keys from the first to the second string of each pair, all at
+    /// once, as [`LanguageTable::rename_keys`] does.
+    RenameKeys(Vec<(RcString, RcString)>),
+    Batch(Vec<Command>),
+}
+
+impl Command {
+    /// Applies this command to `lt`, returning the command that reverts it,
+    /// or `None` if it didn't change anything.
+    fn apply(self, lt: &mut LanguageTable) -> Option<Command> {
+        match self {
+            Command::SetValue {
+                language,
+                key,
+                value,
+            } => {
+                let old = lt.get(&language, &key).map(|s| s.to_string());
+
+                if old == value && (value.is_none() || lt.contains_key(&key)) {
+                    return None;
+                }
+
+                let mut undo = vec![Command::SetValue {
+                    language: language.clone(),
+                    key: key.clone(),
+                    value: old,
+                }];
+
+                if lt.add_language(language.clone()) {
+                    undo.push(Command::RemoveLanguage(language.clone()));
+                }
+
+                if lt.add_key(key.clone()) {
+                    undo.push(Command::RemoveKey(key.clone()));
+                }
+
+                match value {
+                    Some(value) => lt.insert(language, key, value),
+                    None => {
+                        lt.remove_value(&language, &key);
+                    }
+                }
+
+                Some(Command::Batch(undo))
+            }
+            Command::InsertKey { key, index, values } => {
+                if !lt.insert_key_at(index, key.clone()) {
+                    return None;
+                }
+
+                for (language, value) in values {
+                    lt.insert(language, key.clone(), value);
+                }
+
+                Some(Command::RemoveKey(key))
+            }
+            Command::RemoveKey(key) => {
+                let index = lt.key_position(&key)?;
+                let values = lt
+                    .languages
+                    .iter()
+                    .filter_map(|l| Some((l.clone(), lt.get(l, &key)?.to_string())))
+                    .collect();
+                lt.remove_key(&key);
+
+                Some(Command::InsertKey { key, index, values })
+            }
+            Command::InsertLanguage {
+                language,
+                index,
+                part,
+            } => {
+                if !lt.insert_language_at(index, language.clone(), part) {
+                    return None;
+                }
+
+                Some(Command::RemoveLanguage(language))
+            }
+            Command::RemoveLanguage(language) => {
+                let index = lt.language_position(&language)?;
+                let part = lt.columns[index].clone();
+                lt.remove_language(&language);
+
+                Some(Command::InsertLanguage {
+                    language,
+                    index,
+                    part,
+                })
+            }
+            Command::RenameKeys(renames) => {
+                let renames: Vec<_> = renames.into_iter().filter(|(f, t)| f != t).collect();
+
+                if renames.is_empty() {
+                    return None;
+                }
+
+                lt.rename_keys(renames.iter().cloned()).ok()?;
+
+                Some(Command::RenameKeys(
+                    renames.into_iter().map(|(f, t)| (t, f)).collect(),
+                ))
+            }
+            Command::Batch(commands) => {
+                let mut undo: Vec<_> = commands.into_iter().filter_map(|c| c.apply(lt)).collect();
+
+                if undo.is_empty() {
+                    None
+                } else {
+                    undo.reverse();
+                    Some(Command::Batch(undo))
+                }
+            }
+        }
+    }
+
+    /// Returns whether this command, the undo command of a single edit of a
+    /// cell, restores exactly what `redo` sets the cell to, meaning that
+    /// applying `redo` brought the table back to where the edit started.
+    fn restores_value(&self, redo: &Command) -> bool {
+        match (self, redo) {
+            (Command::Batch(undo), Command::SetValue { value, .. }) => matches!(
+                &undo[..],
+                [Command::SetValue { value: old, .. }] if old == value
+            ),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Identifies the state of the table after this entry, for telling
+    /// whether the table is at the state it was saved in.
+    id: u64,
+    undo: Command,
+    redo: Command,
+    /// The cell this entry set, if it set a single one, which following
+    /// edits of the same cell are merged into.
+    cell: Option<(RcString, RcString)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EditHistory {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+    next_id: u64,
+    /// The ID of the state the table was last saved in, where 0 is the state
+    /// before any of the edits in the history.
+    saved: u64,
+    merging: bool,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EditHistory {
+    pub fn new() -> Self {
+        EditHistory {
+            undo: vec![],
+            redo: vec![],
+            next_id: 1,
+            saved: 0,
+            merging: false,
+        }
+    }
+
+    /// Sets the value of `key` in `language`, or removes it if `value` is
+    /// `None`. Setting a value adds the key and language if they aren't in
+    /// the table. Consecutive edits of the same cell are undone together,
+    /// until [`EditHistory::end_merge`] is called.
+    pub fn set_value<L, K>(
+        &mut self,
+        lt: &mut LanguageTable,
+        language: L,
+        key: K,
+        value: Option<String>,
+    ) where
+        L: Into<RcString>,
+        K: Into<RcString>,
+    {
+        let language = language.into();
+        let key = key.into();
+        let cell = Some((language.clone(), key.clone()));
+        let command = Command::SetValue {
+            language,
+            key,
+            value,
+        };
+
+        let merge = self.merging && self.undo.last().is_some_and(|e| e.cell == cell);
+
+        if merge {
+            // keep the undo command of the first edit, which restores the
+            // value from before all of them
+            if command.clone().apply(lt).is_some() {
+                let top = self.undo.last().unwrap();
+
+                if top.undo.restores_value(&command) {
+                    // back to where the first edit started, so the table is
+                    // in the state before the entry again
+                    self.undo.pop();
+                } else {
+                    let id = self.new_id();
+                    let top = self.undo.last_mut().unwrap();
+                    top.redo = command;
+                    top.id = id;
+                }
+            }
+        } else {
+            self.push(lt, command, cell);
+        }
+
+        self.merging = true;
+    }
+
+    /// Sets many values at once, such as when pasting into the table, which
+    /// are undone together.
+    pub fn paste<I, L, K>(&mut self, lt: &mut LanguageTable, cells: I)
+    where
+        I: IntoIterator<Item = (L, K, Option<String>)>,
+        L: Into<RcString>,
+        K: Into<RcString>,
+    {
+        let commands = cells
+            .into_iter()
+            .map(|(language, key, value)| Command::SetValue {
+                language: language.into(),
+                key: key.into(),
+                value,
+            })
+            .collect();
+
+        self.push(lt, Command::Batch(commands), None);
+    }
+
+    pub fn add_key<K: Into<RcString>>(&mut self, lt: &mut LanguageTable, key: K) {
+        let command = Command::InsertKey {
+            key: key.into(),
+            index: usize::MAX,
+            values: vec![],
+        };
+
+        self.push(lt, command, None);
+    }
+
+    pub fn remove_key<K: Into<RcString>>(&mut self, lt: &mut LanguageTable, key: K) {
+        self.push(lt, Command::RemoveKey(key.into()), None);
+    }
+
+    pub fn add_language<L: Into<RcString>>(&mut self, lt: &mut LanguageTable, language: L) {
+        let command = Command::InsertLanguage {
+            language: language.into(),
+            index: usize::MAX,
+            part: LanguageTablePart::default(),
+        };
+
+        self.push(lt, command, None);
+    }
+
+    pub fn remove_language<L: Into<RcString>>(&mut self, lt: &mut LanguageTable, language: L) {
+        self.push(lt, Command::RemoveLanguage(language.into()), None);
+    }
+
+    /// Renames `from` to `to`, as [`LanguageTable::rename_key`] does. The
+    /// table and the history are left as they are if the key can't be
+    /// renamed.
+    pub fn rename_key(&mut self, lt: &mut LanguageTable, from: &str, to: &str) -> Result<()> {
+        self.rename_keys(lt, vec![(from.into(), to.into())])
+    }
+
+    /// Renames every key starting with `from` so that it starts with `to`
+    /// instead, as [`LanguageTable::rename_key_prefix`] does. Returns how
+    /// many keys were renamed.
+    pub fn rename_key_prefix(
+        &mut self,
+        lt: &mut LanguageTable,
+        from: &str,
+        to: &str,
+    ) -> Result<usize> {
+        let renames = lt.prefix_renames(from, to);
+        let count = renames.len();
+
+        self.rename_keys(lt, renames)?;
+        Ok(count)
+    }
+
+    fn rename_keys(
+        &mut self,
+        lt: &mut LanguageTable,
+        renames: Vec<(RcString, RcString)>,
+    ) -> Result<()> {
+        self.merging = false;
+
+        // rename directly first to find out whether it's possible
+        lt.rename_keys(renames.iter().cloned())?;
+
+        let renames: Vec<_> = renames.into_iter().filter(|(f, t)| f != t).collect();
+
+        if !renames.is_empty() {
+            let undo = renames
+                .iter()
+                .map(|(f, t)| (t.clone(), f.clone()))
+                .collect();
+            self.record(
+                Command::RenameKeys(undo),
+                Command::RenameKeys(renames),
+                None,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Reverts the last edit. Returns whether there was one and it could be
+    /// reverted; if it couldn't, such as when the table was edited without
+    /// going through the history, it stays the last edit.
+    pub fn undo(&mut self, lt: &mut LanguageTable) -> bool {
+        self.merging = false;
+
+        match self.undo.last() {
+            Some(entry) if entry.undo.clone().apply(lt).is_some() => {
+                let entry = self.undo.pop().unwrap();
+                self.redo.push(entry);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Applies the last undone edit again. Returns whether there was one and
+    /// it could be applied; if it couldn't, it stays the last undone edit.
+    pub fn redo(&mut self, lt: &mut LanguageTable) -> bool {
+        self.merging = false;
+
+        match self.redo.last() {
+            Some(entry) if entry.redo.clone().apply(lt).is_some() => {
+                let entry = self.redo.pop().unwrap();
+                self.undo.push(entry);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Stops merging edits of the same cell, so that the next edit is undone
+    /// on its own. Call this when the user stops editing a cell.
+    pub fn end_merge(&mut self) {
+        self.merging = false;
+    }
+
+    /// Returns whether the table was changed since it was last saved, or
+    /// since the history was created if it wasn't saved since.
+    pub fn is_dirty(&self) -> bool {
+        self.current_id() != self.saved
+    }
+
+    /// Marks the current state of the table as saved.
+    pub fn mark_saved(&mut self) {
+        self.merging = false;
+        self.saved = self.current_id();
+    }
+
+    /// Removes all edits from the history, such as after loading the table
+    /// again. The current state is considered saved.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.merging = false;
+        self.saved = 0;
+    }
+
+    fn push(
+        &mut self,
+        lt: &mut LanguageTable,
+        command: Command,
+        cell: Option<(RcString, RcString)>,
+    ) {
+        self.merging = false;
+
+        if let Some(undo) = command.clone().apply(lt) {
+            self.record(undo, command, cell);
+        }
+    }
+
+    /// Adds an edit that has already been applied to the table.
+    fn record(&mut self, undo: Command, redo: Command, cell: Option<(RcString, RcString)>) {
+        let id = self.new_id();
+        self.redo.clear();
+        self.undo.push(Entry {
+            id,
+            undo,
+            redo,
+            cell,
+        });
+    }
+
+    fn current_id(&self) -> u64 {
+        self.undo.last().map_or(0, |e| e.id)
+    }
+
+    fn new_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
+
+#[test]
+fn undo_redo() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "A");
+    lt.insert("en_us", "b", "B");
+    lt.insert("de_de", "a", "Ä");
+
+    let mut h = EditHistory::new();
+    assert!(!h.is_dirty());
+
+    // consecutive edits of the same cell are merged
+    h.set_value(&mut lt, "en_us", "a", Some("A1".to_string()));
+    h.set_value(&mut lt, "en_us", "a", Some("A2".to_string()));
+    h.end_merge();
+    h.set_value(&mut lt, "en_us", "a", Some("A3".to_string()));
+    assert!(h.is_dirty());
+    assert!(h.undo(&mut lt));
+    assert_eq!(Some("A2"), lt.get("en_us", "a"));
+    assert!(h.undo(&mut lt));
+    assert_eq!(Some("A"), lt.get("en_us", "a"));
+    assert!(!h.is_dirty());
+    assert!(h.redo(&mut lt));
+    assert_eq!(Some("A2"), lt.get("en_us", "a"));
+
+    // setting a cell back to where it started removes the merged edit
+    h.set_value(&mut lt, "en_us", "b", Some("B1".to_string()));
+    h.set_value(&mut lt, "en_us", "b", Some("B".to_string()));
+    h.end_merge();
+    assert!(h.undo(&mut lt));
+    assert_eq!(Some("A"), lt.get("en_us", "a"));
+    assert!(!h.is_dirty());
+    assert!(h.redo(&mut lt));
+
+    // removing a key and undoing it restores its position and values
+    h.remove_key(&mut lt, "a");
+    assert_eq!(1, lt.key_count());
+    h.mark_saved();
+    assert!(!h.is_dirty());
+    h.undo(&mut lt);
+    assert!(h.is_dirty());
+    assert_eq!(Some("a"), lt.get_key_at(0).map(|s| &**s));
+    assert_eq!(Some("Ä"), lt.get("de_de", "a"));
+    h.redo(&mut lt);
+    assert!(!h.is_dirty());
+
+    h.remove_language(&mut lt, "en_us");
+    h.add_language(&mut lt, "fr_fr");
+    assert_eq!(Some("de_de"), lt.get_language_at(0).map(|s| &**s));
+    h.undo(&mut lt);
+    h.undo(&mut lt);
+    assert_eq!(Some("en_us"), lt.get_language_at(0).map(|s| &**s));
+    assert_eq!(Some("B"), lt.get("en_us", "b"));
+    assert!(!lt.contains_language("fr_fr"));
+
+    // pasting adds missing keys, which undoing removes again
+    h.paste(
+        &mut lt,
+        vec![("en_us", "b", None), ("en_us", "c", Some("C".to_string()))],
+    );
+    assert_eq!(None, lt.get("en_us", "b"));
+    assert_eq!(Some("C"), lt.get("en_us", "c"));
+    h.undo(&mut lt);
+    assert_eq!(Some("B"), lt.get("en_us", "b"));
+    assert_eq!(1, lt.key_count());
+    assert!(h.can_redo());
+
+    // a new edit drops the undone ones
+    h.add_key(&mut lt, "d");
+    assert!(!h.can_redo());
+    assert_eq!(Some("d"), lt.get_key_at(1).map(|s| &**s));
+
+    // edits that don't change anything aren't recorded
+    let count = h.undo.len();
+    h.set_value(&mut lt, "en_us", "b", Some("B".to_string()));
+    h.add_key(&mut lt, "d");
+    assert_eq!(count, h.undo.len());
+}
+
+#[test]
+fn undo_rename() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "block.a.x", "X");
+    lt.insert("en_us", "block.a.y", "Y");
+    lt.insert("en_us", "item.a.x", "Item");
+
+    let mut h = EditHistory::new();
+    h.rename_key(&mut lt, "item.a.x", "item.b.x").unwrap();
+    assert_eq!(
+        2,
+        h.rename_key_prefix(&mut lt, "block.a.", "block.b.")
+            .unwrap()
+    );
+    assert_eq!(Some("X"), lt.get("en_us", "block.b.x"));
+    assert!(h.is_dirty());
+
+    // failed renames don't change the table or the history
+    assert!(h.rename_key(&mut lt, "block.b.x", "block.b.y").is_err());
+    assert!(h.rename_key(&mut lt, "missing", "other").is_err());
+    assert_eq!(2, h.undo.len());
+
+    h.undo(&mut lt);
+    assert_eq!(Some("Y"), lt.get("en_us", "block.a.y"));
+    assert_eq!(Some("block.a.x"), lt.get_key_at(0).map(|s| &**s));
+    h.undo(&mut lt);
+    assert_eq!(Some("Item"), lt.get("en_us", "item.a.x"));
+    assert!(!h.is_dirty());
+    h.redo(&mut lt);
+    h.redo(&mut lt);
+    assert_eq!(Some("Y"), lt.get("en_us", "block.b.y"));
+
+    // renaming to the same key isn't an edit
+    h.rename_key(&mut lt, "block.b.x", "block.b.x").unwrap();
+    assert_eq!(2, h.undo.len());
+
+    // an edit that can't be reverted stays in the history
+    lt.insert("en_us", "block.a.x", "Other");
+    assert!(!h.undo(&mut lt));
+    assert_eq!(2, h.undo.len());
+    assert_eq!(Some("X"), lt.get("en_us", "block.b.x"));
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::slice;
+
+use crate::langtable::RcString;
+
+/// A list of unique strings that can also be looked up by value, used for
+/// the keys and languages of a table.
+#[derive(Debug, Clone, Default)]
+pub struct Index {
+    items: Vec<RcString>,
+    positions: HashMap<RcString, usize>,
+}
+
+impl Index {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, RcString> {
+        self.items.iter()
+    }
+
+    pub fn get_at(&self, idx: usize) -> Option<&RcString> {
+        self.items.get(idx)
+    }
+
+    /// Returns the string equal to `s`, so that strings read from elsewhere
+    /// can share their allocation with the index's.
+    pub fn get(&self, s: &str) -> Option<&RcString> {
+        self.position(s).map(|idx| &self.items[idx])
+    }
+
+    pub fn position(&self, s: &str) -> Option<usize> {
+        self.positions.get(s).copied()
+    }
+
+    pub fn contains(&self, s: &str) -> bool {
+        self.positions.contains_key(s)
+    }
+
+    /// Appends `s` if it isn't in the index yet. Returns its position and
+    /// whether it was added.
+    pub fn insert(&mut self, s: RcString) -> (usize, bool) {
+        match self.position(&s) {
+            Some(idx) => (idx, false),
+            None => {
+                let idx = self.items.len();
+                self.positions.insert(s.clone(), idx);
+                self.items.push(s);
+                (idx, true)
+            }
+        }
+    }
+
+    /// Like [`Index::insert`], but only allocates a new string if `s` isn't
+    /// in the index yet.
+    pub fn insert_str(&mut self, s: &str) -> usize {
+        match self.position(s) {
+            Some(idx) => idx,
+            None => self.insert(s.into()).0,
+        }
+    }
+
+    /// Inserts `s` at `idx`, moving the strings after it back. Returns
+    /// whether `s` was added.
+    pub fn insert_at(&mut self, idx: usize, s: RcString) -> bool {
+        if self.contains(&s) {
+            return false;
+        }
+
+        self.items.insert(idx, s);
+        self.reindex(idx);
+        true
+    }
+
+    /// Replaces each string `f` returns a new one for, keeping its position.
+    /// The strings must still be unique afterwards.
+    pub fn replace_all<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&RcString) -> Option<RcString>,
+    {
+        let mut changed = false;
+
+        for s in self.items.iter_mut() {
+            if let Some(new) = f(s) {
+                *s = new;
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.positions.clear();
+            self.reindex(0);
+            debug_assert_eq!(self.items.len(), self.positions.len());
+        }
+    }
+
+    /// Removes the strings `f` returns false for. Returns for each string
+    /// whether it was kept, for removing the values of the same positions
+    /// elsewhere with [`retain_mask`].
+    pub fn retain<F>(&mut self, mut f: F) -> Vec<bool>
+    where
+        F: FnMut(&RcString) -> bool,
+    {
+        let keep: Vec<bool> = self.items.iter().map(&mut f).collect();
+
+        if let Some(first) = keep.iter().position(|&k| !k) {
+            for (s, _) in self.items.iter().zip(keep.iter()).filter(|(_, &k)| !k) {
+                self.positions.remove(s);
+            }
+
+            retain_mask(&mut self.items, &keep);
+            self.reindex(first);
+        }
+
+        keep
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.positions.clear();
+    }
+
+    fn reindex(&mut self, from: usize) {
+        for (idx, s) in self.items.iter().enumerate().skip(from) {
+            self.positions.insert(s.clone(), idx);
+        }
+    }
+}
+
+/// Removes the elements of `v` whose position is false in `keep`. Elements
+/// past the end of `keep` are kept.
+pub fn retain_mask<T>(v: &mut Vec<T>, keep: &[bool]) {
+    let mut keep = keep.iter();
+    v.retain(|_| *keep.next().unwrap_or(&true));
+}
+
+#[cfg(test)]
+fn index_of(items: &[&str]) -> Index {
+    let mut index = Index::default();
+    items.iter().for_each(|&s| {
+        index.insert_str(s);
+    });
+    index
+}
+
+#[cfg(test)]
+fn assert_positions(index: &Index, items: &[&str]) {
+    assert_eq!(items.len(), index.len());
+
+    for (idx, &s) in items.iter().enumerate() {
+        assert_eq!(Some(s), index.get_at(idx).map(|s| &**s));
+        assert_eq!(Some(idx), index.position(s));
+    }
+}
+
+#[test]
+fn index_insert() {
+    let mut index = index_of(&["a", "b"]);
+    assert_eq!((1, false), index.insert("b".into()));
+    assert_eq!(2, index.insert_str("c"));
+
+    assert!(index.insert_at(1, "x".into()));
+    assert!(!index.insert_at(0, "c".into()));
+    assert_positions(&index, &["a", "x", "b", "c"]);
+
+    assert!(index.insert_at(4, "y".into()));
+    assert_positions(&index, &["a", "x", "b", "c", "y"]);
+}
+
+#[test]
+fn index_retain() {
+    let mut index = index_of(&["a", "b", "c", "d", "e"]);
+    let keep = index.retain(|s| &**s != "b" && &**s != "d");
+    assert_eq!(vec![true, false, true, false, true], keep);
+    assert_positions(&index, &["a", "c", "e"]);
+    assert!(!index.contains("b"));
+
+    let mut values = vec![1, 2, 3, 4, 5, 6];
+    retain_mask(&mut values, &keep);
+    assert_eq!(vec![1, 3, 5, 6], values);
+
+    assert_eq!(vec![true; 3], index.retain(|_| true));
+    assert_positions(&index, &["a", "c", "e"]);
+}
+
+#[test]
+fn index_replace_all() {
+    let mut index = index_of(&["en_US", "de_de", "fr_FR"]);
+    index.replace_all(|s| {
+        let lower = s.to_lowercase();
+        Some(lower.into()).filter(|l: &RcString| l != s)
+    });
+    assert_positions(&index, &["en_us", "de_de", "fr_fr"]);
+    assert!(!index.contains("en_US"));
+}
@@ -0,0 +1,362 @@
+//! JSON language files, as used since 1.13.
+//!
+//! [`JsonFile`] keeps the order of the entries and the way each key and value
+//! was written, and detects the indentation and spacing of the file. Saving
+//! a file after changing some values only changes the lines of those values.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::langtable::{Error, RcString, Result};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Entry {
+    key: String,
+    value: String,
+    /// The key and value as they were written in the file, including quotes
+    /// and escapes.
+    raw_key: String,
+    raw_value: String,
+}
+
+/// How a JSON file is formatted.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct JsonStyle {
+    /// The indentation of each entry, or `None` if the whole object is on a
+    /// single line.
+    indent: Option<String>,
+    /// What is written between a key and its value, such as `": "`.
+    separator: String,
+    line_ending: &'static str,
+    /// What comes after the closing brace.
+    trailing: String,
+}
+
+impl Default for JsonStyle {
+    /// The style of the game's own language files.
+    fn default() -> Self {
+        JsonStyle {
+            indent: Some("  ".to_string()),
+            separator: ": ".to_string(),
+            line_ending: "\n",
+            trailing: "\n".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct JsonFile {
+    entries: Vec<Entry>,
+    style: JsonStyle,
+    /// The position of the entry whose value the game uses for each key.
+    positions: HashMap<String, usize>,
+}
+
+impl JsonFile {
+    pub fn new(style: JsonStyle) -> Self {
+        JsonFile {
+            entries: Vec::new(),
+            style,
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.strip_prefix('\u{FEFF}').unwrap_or(s);
+
+        match Scanner::new(s).file() {
+            Some(file) => Ok(file),
+            // let serde_json produce the error message
+            None => match serde_json::from_str::<HashMap<String, String>>(s) {
+                Err(e) => Err(e.into()),
+                Ok(_) => Err(Error::Serde(serde::de::Error::custom(
+                    "unsupported JSON language file",
+                ))),
+            },
+        }
+    }
+
+    pub fn style(&self) -> &JsonStyle {
+        &self.style
+    }
+
+    /// Returns the entries of this file in order. If a key appears more than
+    /// once, the last value is the one the game uses.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|e| (&*e.key, &*e.value))
+    }
+
+    /// Returns the value of `key`, which is the last one if it appears more
+    /// than once.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.positions
+            .get(key)
+            .map(|&idx| &*self.entries[idx].value)
+    }
+
+    /// Updates the entries of this file to the keys and values in `values`,
+    /// keeping the position and formatting of unchanged entries. Entries
+    /// whose key isn't in `values` are removed, and keys that aren't in the
+    /// file yet are appended in the order they appear in `values`.
+    pub fn update<'a, I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let values: Vec<(&str, &str)> = values.into_iter().collect();
+        let map: HashMap<&str, &str> = values.iter().copied().collect();
+
+        self.entries.retain(|e| map.contains_key(&*e.key));
+
+        let mut present = HashSet::new();
+
+        for e in self.entries.iter_mut() {
+            let new_value = map[&*e.key];
+
+            if e.value != new_value {
+                e.raw_value = quote(new_value);
+                e.value = new_value.to_string();
+            }
+
+            present.insert(e.key.clone());
+        }
+
+        for (key, value) in values {
+            if present.insert(key.to_string()) {
+                self.entries.push(Entry {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    raw_key: quote(key),
+                    raw_value: quote(value),
+                });
+            }
+        }
+
+        self.update_positions();
+    }
+
+    /// Renames the keys of the entries whose key is in `renames`, keeping
+    /// their position in the file.
+    pub fn rename_keys(&mut self, renames: &HashMap<RcString, RcString>) {
+        for e in self.entries.iter_mut() {
+            if let Some(to) = renames.get(&*e.key) {
+                e.key = to.to_string();
+                e.raw_key = quote(to);
+            }
+        }
+
+        self.update_positions();
+    }
+
+    fn update_positions(&mut self) {
+        self.positions = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(idx, e)| (e.key.clone(), idx))
+            .collect();
+    }
+}
+
+impl Display for JsonFile {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let style = &self.style;
+        f.write_str("{")?;
+
+        match &style.indent {
+            _ if self.entries.is_empty() => {}
+            None => {
+                for (idx, e) in self.entries.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(",")?;
+                    }
+
+                    write!(f, "{}{}{}", e.raw_key, style.separator, e.raw_value)?;
+                }
+            }
+            Some(indent) => {
+                for (idx, e) in self.entries.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(",")?;
+                    }
+
+                    f.write_str(style.line_ending)?;
+                    write!(
+                        f,
+                        "{}{}{}{}",
+                        indent, e.raw_key, style.separator, e.raw_value
+                    )?;
+                }
+
+                f.write_str(style.line_ending)?;
+            }
+        }
+
+        write!(f, "}}{}", style.trailing)
+    }
+}
+
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+/// Reads a flat JSON object of strings, keeping track of how it is written.
+/// Returns `None` for anything else, including invalid JSON.
+struct Scanner<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(s: &'a str) -> Self {
+        Scanner { s, pos: 0 }
+    }
+
+    fn file(mut self) -> Option<JsonFile> {
+        let line_ending = if self.s.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let mut style = JsonStyle {
+            line_ending,
+            ..JsonStyle::default()
+        };
+
+        self.whitespace();
+        self.expect('{')?;
+        let before_first = self.whitespace();
+        let mut entries = Vec::new();
+
+        if !self.eat('}') {
+            style.indent = before_first
+                .rfind('\n')
+                .map(|idx| before_first[idx + 1..].to_string());
+
+            loop {
+                let raw_key = self.string()?;
+                let sep_start = self.pos;
+                self.whitespace();
+                self.expect(':')?;
+                self.whitespace();
+
+                if entries.is_empty() {
+                    style.separator = self.s[sep_start..self.pos].to_string();
+                }
+
+                let raw_value = self.string()?;
+
+                entries.push(Entry {
+                    key: serde_json::from_str(raw_key).ok()?,
+                    value: serde_json::from_str(raw_value).ok()?,
+                    raw_key: raw_key.to_string(),
+                    raw_value: raw_value.to_string(),
+                });
+
+                self.whitespace();
+
+                if self.eat('}') {
+                    break;
+                }
+
+                self.expect(',')?;
+                self.whitespace();
+            }
+        }
+
+        let trailing = self.whitespace();
+
+        if self.pos != self.s.len() {
+            return None;
+        }
+
+        style.trailing = trailing.to_string();
+        let mut file = JsonFile {
+            entries,
+            style,
+            positions: HashMap::new(),
+        };
+        file.update_positions();
+        Some(file)
+    }
+
+    fn whitespace(&mut self) -> &'a str {
+        let start = self.pos;
+        let rest = &self.s[start..];
+        self.pos += rest.len() - rest.trim_start().len();
+        &self.s[start..self.pos]
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.s[self.pos..].starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Option<()> {
+        if self.eat(c) {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Reads a string token, returning it with its quotes.
+    fn string(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        self.expect('"')?;
+        let bytes = self.s.as_bytes();
+
+        loop {
+            match bytes.get(self.pos)? {
+                b'"' => break,
+                b'\\' => self.pos += 2,
+                _ => self.pos += 1,
+            }
+        }
+
+        self.pos += 1;
+        self.s.get(start..self.pos)
+    }
+}
+
+#[test]
+fn json_round_trip() {
+    let sources = [
+        "{\n    \"b\": \"B\",\n    \"a\": \"\\u00e9\"\n}\n",
+        "{\"b\":\"B\",\"a\":\"\\u00e9\"}",
+        "{\r\n\t\"b\" : \"B\",\r\n\t\"a\" : \"\\u00e9\"\r\n}",
+    ];
+
+    for src in sources.iter() {
+        let file = JsonFile::parse(src).unwrap();
+        assert_eq!(
+            vec![("b", "B"), ("a", "\u{e9}")],
+            file.entries().collect::<Vec<_>>()
+        );
+        assert_eq!(*src, file.to_string());
+    }
+
+    let mut file = JsonFile::parse(sources[0]).unwrap();
+    file.update(vec![("c", "line\n\"2\""), ("a", "\u{e9}")]);
+
+    assert_eq!(
+        "{\n    \"a\": \"\\u00e9\",\n    \"c\": \"line\\n\\\"2\\\"\"\n}\n",
+        file.to_string()
+    );
+    assert_eq!(Some("\u{e9}"), file.get("a"));
+    assert_eq!(None, file.get("b"));
+
+    assert_eq!(
+        Some("2"),
+        JsonFile::parse("{\"a\": \"1\", \"a\": \"2\"}")
+            .unwrap()
+            .get("a")
+    );
+    assert_eq!("{}\n", JsonFile::parse("{}\n").unwrap().to_string());
+    assert!(JsonFile::parse("{\"a\": 1}").is_err());
+    assert!(JsonFile::parse("{\"a\": \"b\"").is_err());
+}
@@ -0,0 +1,307 @@
+//! The `key=value` language file format used before 1.13.
+//!
+//! Each line is either an entry, which is split into key and value at the
+//! first `=`, or a comment starting with `#`. Values may contain `\uXXXX`
+//! escapes, as well as `\n`, `\t` and `\\`.
+//!
+//! [`LangFile`] keeps every line of the file, so that comments, blank lines
+//! and the order of entries survive loading and saving a file.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::langtable::RcString;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Line {
+    Entry {
+        key: String,
+        value: String,
+        /// The value as it was written in the file, with escapes.
+        raw: String,
+    },
+    /// A comment, blank line, or anything else that isn't an entry, written
+    /// back as is.
+    Other(String),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LangFile {
+    lines: Vec<Line>,
+    line_ending: &'static str,
+    /// The position of the line whose value the game uses for each key.
+    positions: HashMap<String, usize>,
+}
+
+impl Default for LangFile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LangFile {
+    pub fn new() -> Self {
+        LangFile {
+            lines: Vec::new(),
+            line_ending: "\n",
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let s = s.strip_prefix('\u{FEFF}').unwrap_or(s);
+        let line_ending = if s.contains("\r\n") { "\r\n" } else { "\n" };
+
+        let lines = s
+            .lines()
+            .map(|line| match line.find('=') {
+                Some(idx) if !line.starts_with('#') => {
+                    let raw = &line[idx + 1..];
+
+                    Line::Entry {
+                        key: line[..idx].to_string(),
+                        value: unescape(raw).into_owned(),
+                        raw: raw.to_string(),
+                    }
+                }
+                _ => Line::Other(line.to_string()),
+            })
+            .collect();
+
+        let mut file = LangFile {
+            lines,
+            line_ending,
+            positions: HashMap::new(),
+        };
+        file.update_positions();
+        file
+    }
+
+    /// Returns the entries of this file in order. If a key appears more than
+    /// once, the last value is the one the game uses.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry { key, value, .. } => Some((&**key, &**value)),
+            Line::Other(_) => None,
+        })
+    }
+
+    /// Returns the value of `key`, which is the last one if it appears more
+    /// than once.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        match &self.lines[*self.positions.get(key)?] {
+            Line::Entry { value, .. } => Some(value),
+            Line::Other(_) => None,
+        }
+    }
+
+    /// Updates the entries of this file to the keys and values in `values`,
+    /// keeping comments and the position of existing entries. Entries whose
+    /// key isn't in `values` are removed, and keys that aren't in the file
+    /// yet are appended in the order they appear in `values`.
+    pub fn update<'a, I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let values: Vec<(&str, &str)> = values.into_iter().collect();
+        let map: HashMap<&str, &str> = values.iter().copied().collect();
+
+        self.lines.retain(|line| match line {
+            Line::Entry { key, .. } => map.contains_key(&**key),
+            Line::Other(_) => true,
+        });
+
+        let mut present = HashSet::new();
+
+        for line in self.lines.iter_mut() {
+            if let Line::Entry { key, value, raw } = line {
+                let new_value = map[&**key];
+
+                if value != new_value {
+                    *raw = escape(new_value).into_owned();
+                    *value = new_value.to_string();
+                }
+
+                present.insert(key.clone());
+            }
+        }
+
+        for (key, value) in values {
+            if present.insert(key.to_string()) {
+                self.lines.push(Line::Entry {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    raw: escape(value).into_owned(),
+                });
+            }
+        }
+
+        self.update_positions();
+    }
+
+    /// Renames the keys of the entries whose key is in `renames`, keeping
+    /// their position in the file.
+    pub fn rename_keys(&mut self, renames: &HashMap<RcString, RcString>) {
+        for line in self.lines.iter_mut() {
+            if let Line::Entry { key, .. } = line {
+                if let Some(to) = renames.get(&**key) {
+                    *key = to.to_string();
+                }
+            }
+        }
+
+        self.update_positions();
+    }
+
+    fn update_positions(&mut self) {
+        self.positions = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, line)| match line {
+                Line::Entry { key, .. } => Some((key.clone(), idx)),
+                Line::Other(_) => None,
+            })
+            .collect();
+    }
+}
+
+impl Display for LangFile {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for line in self.lines.iter() {
+            match line {
+                Line::Entry { key, raw, .. } => write!(f, "{}={}", key, raw)?,
+                Line::Other(s) => f.write_str(s)?,
+            }
+
+            f.write_str(self.line_ending)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves the escapes in a value. Backslashes that don't start a known
+/// escape are kept.
+pub fn unescape(raw: &str) -> Cow<'_, str> {
+    if !raw.contains('\\') {
+        return Cow::Borrowed(raw);
+    }
+
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    while let Some(idx) = rest.find('\\') {
+        out.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        let (decoded, len) = match rest.as_bytes().get(1) {
+            Some(b'n') => ('\n', 2),
+            Some(b't') => ('\t', 2),
+            Some(b'\\') => ('\\', 2),
+            Some(b'u') => unescape_unicode(rest).unwrap_or(('\\', 1)),
+            _ => ('\\', 1),
+        };
+
+        out.push(decoded);
+        rest = &rest[len..];
+    }
+
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Decodes a `\uXXXX` escape at the start of `s`, or two of them if they
+/// form a surrogate pair. Returns the character and the length of the
+/// escape.
+fn unescape_unicode(s: &str) -> Option<(char, usize)> {
+    fn unit(s: &str) -> Option<u16> {
+        let hex = s.strip_prefix("\\u")?.get(..4)?;
+
+        if hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            u16::from_str_radix(hex, 16).ok()
+        } else {
+            None
+        }
+    }
+
+    let high = unit(s)?;
+
+    if let Some(c) = std::char::from_u32(high as u32) {
+        return Some((c, 6));
+    }
+
+    let low = unit(&s[6..])?;
+    let c = std::char::decode_utf16([high, low].iter().copied())
+        .next()?
+        .ok()?;
+    Some((c, 12))
+}
+
+/// Escapes a value so that it can be written to a file. Only characters
+/// that can't be written as they are get escaped.
+pub fn escape(value: &str) -> Cow<'_, str> {
+    if !value.contains(['\\', '\n', '\r']) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut out = String::with_capacity(value.len() + 2);
+
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\u000D"),
+            c => out.push(c),
+        }
+    }
+
+    Cow::Owned(out)
+}
+
+#[test]
+fn lang_round_trip() {
+    let src =
+        "# Blocks\ntile.stone.name=Stone\n\ntile.dirt.name=Dirt\\u00A7r\nbroken line\nitem.a=x=y\n";
+    let file = LangFile::parse(src);
+
+    assert_eq!(
+        vec![
+            ("tile.stone.name", "Stone"),
+            ("tile.dirt.name", "Dirt\u{A7}r"),
+            ("item.a", "x=y")
+        ],
+        file.entries().collect::<Vec<_>>()
+    );
+    assert_eq!(src, file.to_string());
+
+    let mut file = file;
+    file.update(vec![
+        ("tile.dirt.name", "Dirt\u{A7}r"),
+        ("item.a", "a\nb"),
+        ("item.new", "New"),
+    ]);
+
+    // the escape in the unchanged value is kept
+    assert_eq!(
+        "# Blocks\n\ntile.dirt.name=Dirt\\u00A7r\nbroken line\nitem.a=a\\nb\nitem.new=New\n",
+        file.to_string()
+    );
+    assert_eq!(Some("New"), file.get("item.new"));
+    assert_eq!(None, file.get("tile.stone.name"));
+    assert_eq!(Some("2"), LangFile::parse("a=1\na=2\n").get("a"));
+}
+
+#[test]
+fn lang_escapes() {
+    assert_eq!("\u{1F600}", unescape("\\uD83D\\uDE00"));
+    assert_eq!("a\\b\tc\\uXY", unescape("a\\\\b\\tc\\uXY"));
+    assert_eq!("\\q", unescape("\\q"));
+    assert_eq!("a\\\\b\\nc", escape("a\\b\nc"));
+    assert!(matches!(escape("plain §r"), Cow::Borrowed(_)));
+
+    let file = LangFile::parse("a=1\r\nb=2\r\n");
+    assert_eq!("a=1\r\nb=2\r\n", file.to_string());
+}
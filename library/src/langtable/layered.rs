@@ -0,0 +1,407 @@
+//! Language tables of several roots, merged into one.
+//!
+//! Resource packs are stacked, so that each pack overrides the values of the
+//! packs below it. [`LayeredLanguageTable`] loads the language directory of
+//! a namespace from each root as a layer, in the order of the roots, with
+//! later layers overriding earlier ones, and keeps track of which layer each
+//! value in the merged table comes from.
+//!
+//! Layers can be in different formats, such as an old `.lang` resource pack
+//! below a mod's JSON files. Since `.lang` files name their languages like
+//! `en_US`, languages are matched ignoring case, and the merged table uses
+//! lowercase language names.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use matryoshka::DataSource;
+use mcplatfm::platform::LangFormat;
+use mcplatfm::resource::mixed_case_lang;
+use mcplatfm::ResourceKind;
+
+use crate::langtable::{LanguageTable, LanguageTablePart, RcString, Result};
+use crate::workspace::FsTreeRoot;
+
+pub struct Layer {
+    name: String,
+    ds: Rc<DataSource>,
+    table: LanguageTable,
+}
+
+impl Layer {
+    /// Returns the name of the root this layer was loaded from.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn table(&self) -> &LanguageTable {
+        &self.table
+    }
+
+    /// Returns the value of `key` in `language`, matching the language
+    /// ignoring case.
+    pub fn get(&self, language: &str, key: &str) -> Option<&str> {
+        self.table.get(self.find_language(language)?, key)
+    }
+
+    fn find_language(&self, language: &str) -> Option<&RcString> {
+        self.table
+            .languages
+            .iter()
+            .find(|l| l.eq_ignore_ascii_case(language))
+    }
+
+    /// Returns the name this layer uses for `language`. Languages it doesn't
+    /// have yet are named the way the format of the layer expects.
+    fn language_name(&self, language: &str) -> RcString {
+        match self.find_language(language) {
+            Some(name) => name.clone(),
+            None => match self.table.format() {
+                LangFormat::Json => language.to_lowercase().into(),
+                LangFormat::Lang => mixed_case_lang(&language.to_lowercase(), '_').into(),
+            },
+        }
+    }
+}
+
+/// The value of a key in one layer.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LayerValue {
+    pub layer: usize,
+    pub value: String,
+    /// The layer whose value is used instead of this one, if any.
+    pub overridden_by: Option<usize>,
+}
+
+/// A key with different values in several layers.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Conflict {
+    pub language: RcString,
+    pub key: RcString,
+    /// The values of the layers that have one, from the bottom layer to the
+    /// top one, whose value is used.
+    pub values: Vec<LayerValue>,
+}
+
+pub struct LayeredLanguageTable {
+    namespace: String,
+    layers: Vec<Layer>,
+    merged: LanguageTable,
+}
+
+impl LayeredLanguageTable {
+    /// Loads the language files of `namespace` from each of `roots`. Roots
+    /// that aren't open are skipped.
+    pub fn load(roots: &[Rc<RefCell<FsTreeRoot>>], namespace: &str) -> Result<Self> {
+        let sources: Vec<_> = roots
+            .iter()
+            .filter_map(|root| {
+                let root = root.borrow();
+                let ds = root.data()?.ds().clone();
+                Some((root.name().to_string(), ds))
+            })
+            .collect();
+
+        Self::load_sources(sources, namespace)
+    }
+
+    /// Loads the language files of `namespace` from each of `sources`, where
+    /// each source is the name of the layer and its data source. Sources
+    /// without a language directory for `namespace` are empty layers, so
+    /// that values can be added to any of them.
+    pub fn load_sources<I, S>(sources: I, namespace: &str) -> Result<Self>
+    where
+        I: IntoIterator<Item = (S, Rc<DataSource>)>,
+        S: Into<String>,
+    {
+        let dir = ResourceKind::Lang.namespace_dir(namespace);
+        let mut layers = Vec::new();
+
+        for (name, ds) in sources {
+            let table = if ds.is_dir(&dir) {
+                LanguageTable::load(&ds, &dir)?
+            } else {
+                LanguageTable::new()
+            };
+
+            layers.push(Layer {
+                name: name.into(),
+                ds,
+                table,
+            });
+        }
+
+        let mut lt = LayeredLanguageTable {
+            namespace: namespace.to_string(),
+            layers,
+            merged: LanguageTable::new(),
+        };
+        lt.merge_layers();
+
+        Ok(lt)
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// Returns the merged table, with the values of the topmost layer that
+    /// has one for each key.
+    pub fn table(&self) -> &LanguageTable {
+        &self.merged
+    }
+
+    /// Returns the layer the merged value of `key` in `language` comes from.
+    pub fn origin(&self, language: &str, key: &str) -> Option<usize> {
+        self.layers
+            .iter()
+            .rposition(|l| l.get(language, key).is_some())
+    }
+
+    /// Returns the values of `key` in `language` in each layer that has one,
+    /// from the bottom layer to the top one.
+    pub fn values(&self, language: &str, key: &str) -> Vec<LayerValue> {
+        let origin = self.origin(language, key);
+
+        self.layers
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, l)| {
+                Some(LayerValue {
+                    layer: idx,
+                    value: l.get(language, key)?.to_string(),
+                    overridden_by: origin.filter(|&o| o != idx),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the keys that have different values in several layers.
+    /// Layers that override a value with the same one don't conflict.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        // the row of each merged key in each layer, which is the same for
+        // every language
+        let rows: Vec<Vec<Option<usize>>> = self
+            .layers
+            .iter()
+            .map(|l| {
+                self.merged
+                    .keys
+                    .iter()
+                    .map(|k| l.table.key_position(k))
+                    .collect()
+            })
+            .collect();
+        let mut conflicts = Vec::new();
+
+        for language in self.merged.languages.iter() {
+            let columns: Vec<Option<&LanguageTablePart>> = self
+                .layers
+                .iter()
+                .map(|l| l.table.get_part(l.find_language(language)?))
+                .collect();
+
+            let (columns, rows) = (&columns, &rows);
+
+            for (row, key) in self.merged.keys.iter().enumerate() {
+                let layer_values = || {
+                    (0..self.layers.len())
+                        .filter_map(move |idx| Some((idx, columns[idx]?.get(rows[idx][row]?)?)))
+                };
+
+                let mut values = layer_values();
+                let first = match values.next() {
+                    None => continue,
+                    Some((_, first)) => first,
+                };
+
+                if values.all(|(_, v)| v == first) {
+                    continue;
+                }
+
+                let values: Vec<_> = layer_values().collect();
+                let origin = values.last().map(|&(idx, _)| idx);
+
+                conflicts.push(Conflict {
+                    language: language.clone(),
+                    key: key.clone(),
+                    values: values
+                        .into_iter()
+                        .map(|(idx, value)| LayerValue {
+                            layer: idx,
+                            value: value.to_string(),
+                            overridden_by: origin.filter(|&o| o != idx),
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        conflicts
+    }
+
+    /// Sets the value of `key` in `language` in `layer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no layer with the index `layer`.
+    pub fn insert<L, K, V>(&mut self, layer: usize, language: L, key: K, value: V)
+    where
+        L: Into<RcString>,
+        K: Into<RcString>,
+        V: Into<String>,
+    {
+        let language = language.into();
+        let key = key.into();
+        let layer = &mut self.layers[layer];
+        let name = layer.language_name(&language);
+        layer.table.insert(name, key.clone(), value);
+        self.update(&language, key);
+    }
+
+    /// Removes the value of `key` in `language` from `layer`, so that the
+    /// value of the layer below it is used, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no layer with the index `layer`.
+    pub fn remove_value(&mut self, layer: usize, language: &str, key: &str) -> Option<String> {
+        let layer = &mut self.layers[layer];
+        let name = layer.find_language(language)?.clone();
+        let old = layer.table.remove_value(&name, key)?;
+        self.update(language, key.into());
+        Some(old)
+    }
+
+    /// Saves the values of `layer` to the root it was loaded from, creating
+    /// the language directory if it doesn't have one yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no layer with the index `layer`.
+    pub fn save(&self, layer: usize) -> Result<()> {
+        let layer = &self.layers[layer];
+        let dir = ResourceKind::Lang.namespace_dir(&self.namespace);
+
+        if !layer.ds.is_dir(&dir) {
+            layer.ds.create_dir_all(&dir)?;
+        }
+
+        layer.table.save(&layer.ds, dir)
+    }
+
+    fn merge_layers(&mut self) {
+        self.merged = LanguageTable::new();
+
+        for layer in self.layers.iter() {
+            for language in layer.table.languages.iter() {
+                self.merged.add_language(language.to_lowercase());
+            }
+
+            for key in layer.table.keys.iter() {
+                self.merged.add_key(key.clone());
+            }
+
+            for language in layer.table.languages.iter() {
+                let merged: RcString = language.to_lowercase().into();
+
+                for (key, value) in layer.table.entries(language) {
+                    self.merged.insert(merged.clone(), key.clone(), value);
+                }
+            }
+        }
+    }
+
+    /// Updates the merged value of `key` in `language` after it was changed
+    /// in a layer.
+    fn update(&mut self, language: &str, key: RcString) {
+        let language: RcString = language.to_lowercase().into();
+        let value = self
+            .layers
+            .iter()
+            .rev()
+            .find_map(|l| l.get(&language, &key))
+            .map(|s| s.to_string());
+
+        match value {
+            Some(value) => self.merged.insert(language, key, value),
+            None => {
+                self.merged.remove_value(&language, &key);
+            }
+        }
+    }
+}
+
+#[test]
+fn layered_tables() {
+    use std::fs;
+
+    let base = super::test_dir("layered-base");
+    let addon = super::test_dir("layered-addon");
+    let empty = super::test_dir("layered-empty");
+    fs::create_dir_all(base.join("assets/test/lang")).unwrap();
+    fs::create_dir_all(addon.join("assets/test/lang")).unwrap();
+    // an old resource pack below a mod's JSON files
+    fs::write(base.join("assets/test/lang/en_US.lang"), "a=A\nb=B\nc=C\n").unwrap();
+    fs::write(
+        addon.join("assets/test/lang/en_us.json"),
+        "{\"b\": \"B2\", \"c\": \"C\", \"d\": \"D\"}",
+    )
+    .unwrap();
+
+    let sources = vec![("base", &base), ("empty", &empty), ("addon", &addon)]
+        .into_iter()
+        .map(|(name, dir)| (name, Rc::new(DataSource::new_dir(dir).unwrap())));
+
+    let mut lt = LayeredLanguageTable::load_sources(sources, "test").unwrap();
+    assert_eq!(3, lt.layers().len());
+    assert_eq!("addon", lt.layers()[2].name());
+    assert_eq!(LangFormat::Lang, lt.layers()[0].table().format());
+    assert_eq!(1, lt.table().lang_count());
+    assert_eq!(4, lt.table().key_count());
+    assert_eq!(Some("A"), lt.table().get("en_us", "a"));
+    assert_eq!(Some("B2"), lt.table().get("en_us", "b"));
+    assert_eq!(Some(0), lt.origin("en_us", "a"));
+    assert_eq!(Some(2), lt.origin("en_us", "b"));
+
+    let values = lt.values("en_us", "b");
+    assert_eq!(Some(2), values[0].overridden_by);
+    assert_eq!(None, values[1].overridden_by);
+
+    // "c" is overridden with the same value
+    let conflicts = lt.conflicts();
+    assert_eq!(1, conflicts.len());
+    assert_eq!("b", &*conflicts[0].key);
+    assert_eq!(lt.values("en_us", "b"), conflicts[0].values);
+
+    lt.remove_value(2, "en_us", "b");
+    assert_eq!(Some("B"), lt.table().get("en_us", "b"));
+    assert!(lt.conflicts().is_empty());
+
+    lt.insert(2, "en_us", "a", "A2");
+    assert_eq!(Some("A2"), lt.table().get("en_us", "a"));
+    lt.save(2).unwrap();
+    assert!(
+        fs::read_to_string(addon.join("assets/test/lang/en_us.json"))
+            .unwrap()
+            .contains("\"a\": \"A2\"")
+    );
+
+    // the .lang layer keeps its file name
+    lt.insert(0, "en_us", "e", "E");
+    lt.save(0).unwrap();
+    assert_eq!(
+        "a=A\nb=B\nc=C\ne=E\n",
+        fs::read_to_string(base.join("assets/test/lang/en_US.lang")).unwrap()
+    );
+
+    // saving a layer that had no language files creates the directory
+    lt.insert(1, "de_de", "a", "Ä");
+    assert_eq!(Some(1), lt.origin("de_de", "a"));
+    lt.save(1).unwrap();
+    assert!(empty.join("assets/test/lang/de_de.json").is_file());
+}
@@ -0,0 +1,140 @@
+//! Merging translations from translation exchange formats, such as gettext
+//! PO files or XLIFF, back into a [`LanguageTable`].
+
+use crate::langtable::{LanguageTable, RcString};
+
+/// A translation read from an exchange file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TranslationUnit {
+    pub key: String,
+    /// The reference language value the translation was made for.
+    pub source: String,
+    /// The translation, or an empty string if there is none.
+    pub target: String,
+    /// Whether the translation is marked as needing review.
+    pub fuzzy: bool,
+    /// Whether the entry is marked as no longer used.
+    pub obsolete: bool,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct MergeOptions {
+    /// Also apply translations that are marked as needing review, or that
+    /// were made for a different reference language value than the current
+    /// one.
+    pub apply_fuzzy: bool,
+}
+
+/// What happened to each of the units when merging them into a table.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct MergeReport {
+    /// Keys whose value was added or changed.
+    pub updated: Vec<RcString>,
+    /// The number of units whose translation matched the table already.
+    pub unchanged: usize,
+    /// Units without a translation.
+    pub untranslated: Vec<RcString>,
+    /// Fuzzy units, which weren't applied unless
+    /// [`MergeOptions::apply_fuzzy`] is set.
+    pub fuzzy: Vec<RcString>,
+    /// Units marked as obsolete, which are never applied.
+    pub obsolete: Vec<RcString>,
+    /// Units for keys that aren't in the table, which are never applied.
+    pub unknown: Vec<RcString>,
+}
+
+impl LanguageTable {
+    /// Merges translations into `language`, comparing their source to the
+    /// values in `reference`. Only translations that differ from the value in
+    /// the table are applied; values are never removed.
+    pub fn merge<I>(
+        &mut self,
+        reference: &str,
+        language: &str,
+        units: I,
+        options: MergeOptions,
+    ) -> MergeReport
+    where
+        I: IntoIterator<Item = TranslationUnit>,
+    {
+        let mut report = MergeReport::default();
+
+        for unit in units {
+            let key = match self.keys.get(&unit.key) {
+                Some(key) => key.clone(),
+                None => {
+                    report.unknown.push(unit.key.into());
+                    continue;
+                }
+            };
+
+            // the source value changed since the file was exported
+            let outdated = self
+                .get(reference, &key)
+                .is_some_and(|current| current != unit.source);
+
+            if unit.obsolete {
+                report.obsolete.push(key);
+            } else if unit.target.is_empty() {
+                report.untranslated.push(key);
+            } else if (unit.fuzzy || outdated) && !options.apply_fuzzy {
+                report.fuzzy.push(key);
+            } else if self.get(language, &key) == Some(&*unit.target) {
+                report.unchanged += 1;
+            } else {
+                self.insert(language, key.clone(), unit.target);
+                report.updated.push(key);
+            }
+        }
+
+        report
+    }
+}
+
+#[test]
+fn merge_units() {
+    let unit = |key: &str, source: &str, target: &str| TranslationUnit {
+        key: key.to_string(),
+        source: source.to_string(),
+        target: target.to_string(),
+        fuzzy: false,
+        obsolete: false,
+    };
+
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "Stone");
+    lt.insert("en_us", "b", "Dirt");
+    lt.insert("en_us", "c", "Grass");
+    lt.insert("en_us", "d", "Sand");
+    lt.insert("de_de", "a", "Stein");
+
+    let units = vec![
+        unit("a", "Stone", "Stein"),
+        unit("b", "Dirt", "Erde"),
+        unit("c", "Old grass", "Gras"),
+        TranslationUnit {
+            fuzzy: true,
+            ..unit("d", "Sand", "Sand?")
+        },
+        unit("e", "New", "Neu"),
+        TranslationUnit {
+            obsolete: true,
+            ..unit("b", "Dirt", "Dreck")
+        },
+    ];
+
+    let report = lt.merge("en_us", "de_de", units.clone(), MergeOptions::default());
+    let names = |keys: &[RcString]| keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+    assert_eq!(vec!["b"], names(&report.updated));
+    assert_eq!(1, report.unchanged);
+    assert_eq!(vec!["c", "d"], names(&report.fuzzy));
+    assert_eq!(vec!["e"], names(&report.unknown));
+    assert_eq!(vec!["b"], names(&report.obsolete));
+    assert_eq!(Some("Erde"), lt.get("de_de", "b"));
+    assert_eq!(None, lt.get("de_de", "c"));
+
+    let options = MergeOptions { apply_fuzzy: true };
+    let report = lt.merge("en_us", "de_de", units, options);
+    assert_eq!(vec!["c", "d"], names(&report.updated));
+    assert_eq!(Some("Gras"), lt.get("de_de", "c"));
+}
@@ -0,0 +1,889 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::io::{Read, Write};
+use std::ops::Deref;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use matryoshka;
+use matryoshka::{DataSource, DirEntry, OpenOptions};
+use mcplatfm::platform::LangFormat;
+
+use crate::has_extension;
+use index::{retain_mask, Index};
+use json::JsonFile;
+use lang::LangFile;
+
+pub mod coverage;
+pub mod history;
+mod index;
+pub mod json;
+pub mod lang;
+pub mod layered;
+pub mod merge;
+pub mod po;
+pub mod query;
+pub mod spreadsheet;
+pub mod text;
+pub mod xliff;
+
+#[derive(Debug, Clone, Default)]
+pub struct LanguageTablePart {
+    /// The value of each key, by the position of the key in the table. Ends
+    /// after the last key with a value.
+    values: Vec<Option<String>>,
+    /// The file this part was loaded from. Used to keep the order and
+    /// formatting of the file when saving.
+    source: Option<SourceFile>,
+}
+
+impl LanguageTablePart {
+    fn get(&self, row: usize) -> Option<&str> {
+        self.values.get(row)?.as_deref()
+    }
+
+    fn set(&mut self, row: usize, value: Option<String>) -> Option<String> {
+        match self.values.get_mut(row) {
+            Some(slot) => std::mem::replace(slot, value),
+            None => {
+                if value.is_some() {
+                    self.values.resize(row + 1, None);
+                    self.values[row] = value;
+                }
+
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum SourceFile {
+    Json(JsonFile),
+    Lang(LangFile),
+}
+
+impl SourceFile {
+    fn entries(&self) -> Box<dyn Iterator<Item = (&str, &str)> + '_> {
+        match self {
+            SourceFile::Json(file) => Box::new(file.entries()),
+            SourceFile::Lang(file) => Box::new(file.entries()),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        match self {
+            SourceFile::Json(file) => file.get(key),
+            SourceFile::Lang(file) => file.get(key),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LanguageTable {
+    keys: Index,
+    languages: Index,
+    /// The values of each language, in the order of `languages`.
+    columns: Vec<LanguageTablePart>,
+    format: LangFormat,
+    /// Whether the table was converted from the other format since it was
+    /// loaded, in which case the old files are deleted when saving.
+    converted: bool,
+}
+
+impl Default for LanguageTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageTable {
+    pub fn new() -> Self {
+        LanguageTable {
+            keys: Index::default(),
+            languages: Index::default(),
+            columns: vec![],
+            format: LangFormat::Json,
+            converted: false,
+        }
+    }
+
+    /// Returns the format the files of this table are saved in.
+    pub fn format(&self) -> LangFormat {
+        self.format
+    }
+
+    /// Converts this table to `format`, which is used the next time it is
+    /// saved. Language names are made lowercase when converting to JSON,
+    /// since the game doesn't load JSON files with uppercase letters in their
+    /// names. Languages whose names only differ in case, such as `en_US` and
+    /// `en_us`, are merged; see [`LanguageTable::merge_case_duplicates`].
+    pub fn convert(&mut self, format: LangFormat) {
+        if format == self.format {
+            return;
+        }
+
+        self.format = format;
+        self.converted = true;
+
+        for part in self.columns.iter_mut() {
+            part.source = None;
+        }
+
+        if format == LangFormat::Json {
+            self.merge_case_duplicates();
+            self.languages.replace_all(|lang| {
+                let lower = lang.to_lowercase();
+                Some(lower.into()).filter(|l: &RcString| l != lang)
+            });
+        }
+    }
+
+    pub fn insert<L, K, V>(&mut self, language: L, key: K, value: V)
+    where
+        L: Into<RcString>,
+        K: Into<RcString>,
+        V: Into<String>,
+    {
+        let col = self.insert_language(language.into());
+        let (row, _) = self.keys.insert(key.into());
+        self.columns[col].set(row, Some(value.into()));
+    }
+
+    pub fn add_key<S: Into<RcString>>(&mut self, key: S) -> bool {
+        self.keys.insert(key.into()).1
+    }
+
+    pub fn add_language<S: Into<RcString>>(&mut self, lang: S) -> bool {
+        let count = self.languages.len();
+        self.insert_language(lang.into());
+        self.languages.len() > count
+    }
+
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Merges the columns of languages whose names are the same in lowercase.
+    /// The values of the language that is already named in lowercase, or
+    /// else of the first one, are kept, and keys it has no value for get the
+    /// value of the other languages.
+    fn merge_case_duplicates(&mut self) {
+        let lower: Vec<String> = self.languages.iter().map(|l| l.to_lowercase()).collect();
+        let mut primary: HashMap<&str, usize> = HashMap::new();
+
+        for (idx, lang) in self.languages.iter().enumerate() {
+            let name = lower[idx].as_str();
+
+            match primary.get(name) {
+                Some(&p) if lang[..] == *name && self.languages.get_at(p).unwrap()[..] != *name => {
+                    primary.insert(name, idx);
+                }
+                Some(_) => {}
+                None => {
+                    primary.insert(name, idx);
+                }
+            }
+        }
+
+        if primary.len() == self.languages.len() {
+            return;
+        }
+
+        let keep: Vec<bool> = lower
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| primary[name.as_str()] == idx)
+            .collect();
+
+        for (idx, name) in lower.iter().enumerate().filter(|&(idx, _)| !keep[idx]) {
+            let target = primary[name.as_str()];
+            let values = std::mem::take(&mut self.columns[idx].values);
+
+            for (row, value) in values.into_iter().enumerate() {
+                if value.is_some() && self.columns[target].get(row).is_none() {
+                    self.columns[target].set(row, value);
+                }
+            }
+        }
+
+        let mut kept = keep.iter();
+        self.languages.retain(|_| *kept.next().unwrap());
+        retain_mask(&mut self.columns, &keep);
+    }
+
+    pub fn lang_count(&self) -> usize {
+        self.languages.len()
+    }
+
+    pub fn get(&self, language: &str, key: &str) -> Option<&str> {
+        let col = self.languages.position(language)?;
+        let row = self.keys.position(key)?;
+        self.columns[col].get(row)
+    }
+
+    pub fn get_language_at(&self, idx: usize) -> Option<&RcString> {
+        self.languages.get_at(idx)
+    }
+
+    pub fn get_key_at(&self, idx: usize) -> Option<&RcString> {
+        self.keys.get_at(idx)
+    }
+
+    /// Returns the position of `key` in the table.
+    pub fn key_position(&self, key: &str) -> Option<usize> {
+        self.keys.position(key)
+    }
+
+    /// Returns the position of `language` in the table.
+    pub fn language_position(&self, language: &str) -> Option<usize> {
+        self.languages.position(language)
+    }
+
+    pub fn get_part(&self, lang: &str) -> Option<&LanguageTablePart> {
+        self.languages.position(lang).map(|col| &self.columns[col])
+    }
+
+    /// Returns the keys that have a value in `language` and their values, in
+    /// the order of the table.
+    pub fn entries(&self, language: &str) -> impl Iterator<Item = (&RcString, &str)> {
+        let values = match self.languages.position(language) {
+            Some(col) => &self.columns[col].values[..],
+            None => &[],
+        };
+
+        self.keys
+            .iter()
+            .zip(values.iter())
+            .filter_map(|(k, v)| Some((k, v.as_deref()?)))
+    }
+
+    pub fn contains_language(&self, lang: &str) -> bool {
+        self.languages.contains(lang)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    fn contains_language_ignore_case(&self, lang: &str) -> bool {
+        self.languages.iter().any(|s| s.eq_ignore_ascii_case(lang))
+    }
+
+    /// Removes the value of `key` in `language`, keeping the key and the
+    /// language in the table.
+    pub fn remove_value(&mut self, language: &str, key: &str) -> Option<String> {
+        let col = self.languages.position(language)?;
+        let row = self.keys.position(key)?;
+        self.columns[col].set(row, None)
+    }
+
+    /// Removes `key` and its values in every language. Returns whether the
+    /// key was in the table.
+    pub fn remove_key(&mut self, key: &str) -> bool {
+        self.remove_keys(std::iter::once(key)) > 0
+    }
+
+    /// Removes each of `keys` and their values in every language. Returns
+    /// how many of them were in the table.
+    pub fn remove_keys<I, S>(&mut self, keys: I) -> usize
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let keys: Vec<S> = keys.into_iter().collect();
+        let keys: HashSet<&str> = keys.iter().map(|k| k.as_ref()).collect();
+
+        let count = self.keys.len();
+        let keep = self.keys.retain(|k| !keys.contains(&**k));
+
+        if self.keys.len() < count {
+            for part in self.columns.iter_mut() {
+                retain_mask(&mut part.values, &keep);
+            }
+        }
+
+        count - self.keys.len()
+    }
+
+    /// Removes `language` and all of its values. Its file is deleted the next
+    /// time the table is saved. Returns whether the language was in the
+    /// table.
+    pub fn remove_language(&mut self, language: &str) -> bool {
+        self.remove_languages(std::iter::once(language)) > 0
+    }
+
+    /// Removes each of `languages` and all of their values. Returns how many
+    /// of them were in the table.
+    pub fn remove_languages<I, S>(&mut self, languages: I) -> usize
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let languages: Vec<S> = languages.into_iter().collect();
+        let languages: HashSet<&str> = languages.iter().map(|l| l.as_ref()).collect();
+
+        let count = self.languages.len();
+        let keep = self.languages.retain(|l| !languages.contains(&**l));
+        retain_mask(&mut self.columns, &keep);
+
+        count - self.languages.len()
+    }
+
+    /// Renames `from` to `to` in every language, keeping the position of the
+    /// key in the table and in the files it was loaded from.
+    pub fn rename_key(&mut self, from: &str, to: &str) -> Result<()> {
+        self.rename_keys(std::iter::once((from, to)))
+    }
+
+    /// Renames every key starting with `from` so that it starts with `to`
+    /// instead, such as `block.mymod.` to `block.othermod.`. Returns how many
+    /// keys were renamed.
+    pub fn rename_key_prefix(&mut self, from: &str, to: &str) -> Result<usize> {
+        let renames = self.prefix_renames(from, to);
+        let count = renames.len();
+
+        self.rename_keys(renames)?;
+        Ok(count)
+    }
+
+    /// Returns the keys starting with `from` together with what they would be
+    /// renamed to by [`LanguageTable::rename_key_prefix`].
+    pub(crate) fn prefix_renames(&self, from: &str, to: &str) -> Vec<(RcString, RcString)> {
+        self.keys
+            .iter()
+            .filter_map(|k| {
+                let renamed = format!("{}{}", to, k.strip_prefix(from)?);
+                Some((k.clone(), renamed.into()))
+            })
+            .collect()
+    }
+
+    /// Renames several keys at once. Keys can be swapped or renamed to keys
+    /// that are themselves renamed. Nothing is renamed if any of the keys
+    /// isn't in the table, or would be renamed to a key that is already in
+    /// the table.
+    pub fn rename_keys<I, F, T>(&mut self, renames: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (F, T)>,
+        F: Into<RcString>,
+        T: Into<RcString>,
+    {
+        let mut map: HashMap<RcString, RcString> = HashMap::new();
+
+        for (from, to) in renames {
+            let (from, to) = (from.into(), to.into());
+
+            if !self.keys.contains(&from) {
+                return Err(Error::KeyNotFound(from.to_string()));
+            }
+
+            if from != to {
+                map.insert(from, to);
+            }
+        }
+
+        let mut targets = HashSet::new();
+
+        for to in map.values() {
+            let taken = self.keys.contains(to) && !map.contains_key(to);
+
+            if taken || !targets.insert(to) {
+                return Err(Error::KeyExists(to.to_string()));
+            }
+        }
+
+        // the values stay at the position of their key
+        self.keys.replace_all(|k| map.get(k).cloned());
+
+        for part in self.columns.iter_mut() {
+            match &mut part.source {
+                Some(SourceFile::Json(file)) => file.rename_keys(&map),
+                Some(SourceFile::Lang(file)) => file.rename_keys(&map),
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.columns.clear();
+        self.keys.clear();
+        self.languages.clear();
+    }
+
+    /// Adds `language` if it isn't in the table yet, and returns its
+    /// position.
+    fn insert_language(&mut self, language: RcString) -> usize {
+        let (col, added) = self.languages.insert(language);
+
+        if added {
+            self.columns.push(LanguageTablePart::default());
+        }
+
+        col
+    }
+
+    /// Inserts `key` at `idx` without any values, moving the keys after it
+    /// back. Returns whether it was added.
+    fn insert_key_at(&mut self, idx: usize, key: RcString) -> bool {
+        let idx = idx.min(self.keys.len());
+
+        if !self.keys.insert_at(idx, key) {
+            return false;
+        }
+
+        for part in self.columns.iter_mut() {
+            if part.values.len() > idx {
+                part.values.insert(idx, None);
+            }
+        }
+
+        true
+    }
+
+    /// Inserts `language` at `idx` with the values in `part`, moving the
+    /// languages after it back. Returns whether it was added.
+    fn insert_language_at(
+        &mut self,
+        idx: usize,
+        language: RcString,
+        part: LanguageTablePart,
+    ) -> bool {
+        let idx = idx.min(self.languages.len());
+
+        if !self.languages.insert_at(idx, language) {
+            return false;
+        }
+
+        self.columns.insert(idx, part);
+        true
+    }
+
+    /// Saves the table to the directory `path`. Files keep the order and
+    /// formatting they were loaded with, and files whose contents haven't
+    /// changed aren't written.
+    pub fn save<P: AsRef<Path>>(&self, ds: &DataSource, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let ext = extension(self.format);
+
+        for x in ds.list_dir(path)? {
+            let lang = x.path().file_stem().and_then(|s| s.to_str());
+
+            // delete the files of languages that were removed, and those of
+            // languages that were converted from the other format
+            let removed =
+                has_extension(x.path(), ext) && lang.is_some_and(|s| !self.contains_language(s));
+            let converted = self.converted
+                && is_lang_file(&x)
+                && !has_extension(x.path(), ext)
+                && lang.is_some_and(|s| self.contains_language_ignore_case(s));
+
+            if x.info().is_file() && (removed || converted) {
+                ds.delete_file(x.path())?;
+            }
+        }
+
+        // new JSON files are formatted like the existing ones
+        let style = self
+            .columns
+            .iter()
+            .filter_map(|part| match part.source.as_ref()? {
+                SourceFile::Json(file) => Some(file.style().clone()),
+                SourceFile::Lang(_) => None,
+            })
+            .next()
+            .unwrap_or_default();
+
+        for (lang, part) in self.languages.iter().zip(self.columns.iter()) {
+            let values = self.entries(lang).map(|(k, v)| (&**k, v));
+            let source = part.source.as_ref();
+            let mut path = path.join(&**lang);
+            path.set_extension(ext);
+
+            let text = match (self.format, source) {
+                (LangFormat::Json, source) => {
+                    let mut file = match source {
+                        Some(SourceFile::Json(file)) => file.clone(),
+                        _ => JsonFile::new(style.clone()),
+                    };
+                    file.update(values);
+                    file.to_string()
+                }
+                (LangFormat::Lang, source) => {
+                    let mut file = match source {
+                        Some(SourceFile::Lang(file)) => file.clone(),
+                        _ => LangFile::new(),
+                    };
+                    file.update(values);
+                    file.to_string()
+                }
+            };
+
+            if ds.is_file(&path) {
+                let mut current = Vec::new();
+                ds.open(&path, OpenOptions::reading())?
+                    .read_to_end(&mut current)?;
+
+                if current == text.as_bytes() {
+                    continue;
+                }
+            }
+
+            ds.open(path, OpenOptions::writing(true))?
+                .write_all(text.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(ds: &DataSource, path: P) -> Result<Self> {
+        let mut lt = LanguageTable::new();
+
+        let mut dir = ds.list_dir(path)?;
+        lt.format = detect_format(&dir);
+
+        dir.sort_by(|a, b| match (a, b) {
+            (a, _) if is_reference(a) => Ordering::Less,
+            (_, b) if is_reference(b) => Ordering::Greater,
+            (a, b) => a.path().cmp(b.path()),
+        });
+
+        for entry in dir {
+            if entry.info().is_file() && has_extension(entry.path(), extension(lt.format)) {
+                let lang: RcString = entry.path().file_stem().unwrap().to_str().unwrap().into();
+
+                // Read entire file into string to increase speed (serde-rs/json#160)
+                let mut buf = String::new();
+                ds.open(entry.path(), OpenOptions::reading())?
+                    .read_to_string(&mut buf)?;
+
+                let source = match lt.format {
+                    LangFormat::Json => SourceFile::Json(JsonFile::parse(&buf)?),
+                    LangFormat::Lang => SourceFile::Lang(LangFile::parse(&buf)),
+                };
+
+                let col = lt.insert_language(lang);
+                let part = &mut lt.columns[col];
+
+                for (key, value) in source.entries() {
+                    let row = lt.keys.insert_str(key);
+                    part.set(row, Some(value.to_string()));
+                }
+
+                part.source = Some(source);
+            }
+        }
+
+        Ok(lt)
+    }
+}
+
+fn extension(format: LangFormat) -> &'static str {
+    match format {
+        LangFormat::Json => "json",
+        LangFormat::Lang => "lang",
+    }
+}
+
+fn is_lang_file(entry: &DirEntry) -> bool {
+    entry.info().is_file()
+        && (has_extension(entry.path(), "json") || has_extension(entry.path(), "lang"))
+}
+
+/// Returns whether `entry` is the file of the reference language, which is
+/// loaded first.
+fn is_reference(entry: &DirEntry) -> bool {
+    entry
+        .path()
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .is_some_and(|s| s.eq_ignore_ascii_case("en_us"))
+}
+
+/// Detects the format of a language directory from the files in it. If
+/// there are files of both formats, the JSON files are the ones the game
+/// loads.
+fn detect_format(dir: &[DirEntry]) -> LangFormat {
+    let files = || dir.iter().filter(|e| e.info().is_file());
+
+    if files().any(|e| has_extension(e.path(), "json")) {
+        LangFormat::Json
+    } else if files().any(|e| has_extension(e.path(), "lang")) {
+        LangFormat::Lang
+    } else {
+        LangFormat::Json
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("data source error: {0}")]
+    DataSource(#[from] matryoshka::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("key not found: {0}")]
+    KeyNotFound(String),
+    #[error("key already exists: {0}")]
+    KeyExists(String),
+    #[error("PO syntax error on line {0}: {1}")]
+    Po(usize, String),
+    #[error("XML error: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("invalid XLIFF file: {0}")]
+    Xliff(String),
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("invalid spreadsheet: {0}")]
+    Sheet(String),
+    #[error("invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct RcString(Rc<String>);
+
+impl RcString {
+    pub fn from(rc: Rc<String>) -> RcString {
+        RcString(rc)
+    }
+
+    pub fn into_inner(self) -> Rc<String> {
+        self.0
+    }
+}
+
+impl Borrow<str> for RcString {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for RcString {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> From<T> for RcString
+where
+    T: Into<String>,
+{
+    fn from(s: T) -> Self {
+        RcString(Rc::new(s.into()))
+    }
+}
+
+impl Serialize for RcString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RcString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(String::deserialize(deserializer)?.into())
+    }
+}
+
+#[cfg(test)]
+fn test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("mcrtlib-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn load_save_lang() {
+    use std::fs;
+
+    let dir = test_dir("lang");
+    fs::write(dir.join("en_us.lang"), "# comment\nb=B\na=A\n").unwrap();
+    fs::write(dir.join("de_DE.lang"), "a=\\u00C4\n").unwrap();
+    fs::write(dir.join("notes.txt"), "").unwrap();
+
+    let ds = DataSource::new_dir(&dir).unwrap();
+    let mut lt = LanguageTable::load(&ds, "/").unwrap();
+    assert_eq!(LangFormat::Lang, lt.format());
+    assert_eq!(Some("en_us"), lt.get_language_at(0).map(|s| &**s));
+    assert_eq!(Some("b"), lt.get_key_at(0).map(|s| &**s));
+    assert_eq!(Some("\u{C4}"), lt.get("de_DE", "a"));
+
+    lt.insert("en_us", "c", "C");
+    lt.save(&ds, "/").unwrap();
+    assert_eq!(
+        "# comment\nb=B\na=A\nc=C\n",
+        fs::read_to_string(dir.join("en_us.lang")).unwrap()
+    );
+    assert_eq!(
+        "a=\\u00C4\n",
+        fs::read_to_string(dir.join("de_DE.lang")).unwrap()
+    );
+
+    lt.convert(LangFormat::Json);
+    lt.save(&ds, "/").unwrap();
+    assert!(!dir.join("en_us.lang").exists());
+    assert!(!dir.join("de_DE.lang").exists());
+    assert!(dir.join("notes.txt").exists());
+
+    let lt = LanguageTable::load(&ds, "/").unwrap();
+    assert_eq!(LangFormat::Json, lt.format());
+    assert_eq!(Some("\u{C4}"), lt.get("de_de", "a"));
+    assert_eq!(Some("C"), lt.get("en_us", "c"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn convert_merges_languages() {
+    use std::fs;
+
+    let dir = test_dir("convert-merge");
+    fs::write(dir.join("en_US.lang"), "a=A\nb=B1\n").unwrap();
+    fs::write(dir.join("en_us.lang"), "b=B\n").unwrap();
+    fs::write(dir.join("de_DE.lang"), "a=\\u00C4\n").unwrap();
+
+    let ds = DataSource::new_dir(&dir).unwrap();
+    let mut lt = LanguageTable::load(&ds, "/").unwrap();
+    assert_eq!(3, lt.lang_count());
+
+    lt.convert(LangFormat::Json);
+    assert_eq!(2, lt.lang_count());
+    assert_eq!(Some("A"), lt.get("en_us", "a"));
+    assert_eq!(Some("B"), lt.get("en_us", "b"));
+    assert_eq!(Some("\u{C4}"), lt.get("de_de", "a"));
+
+    lt.save(&ds, "/").unwrap();
+    assert!(dir.join("en_us.json").exists());
+    assert!(!dir.join("en_US.lang").exists());
+    assert!(!dir.join("en_us.lang").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn load_save_json() {
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+
+    let dir = test_dir("json");
+    fs::write(
+        dir.join("en_us.json"),
+        "{\n    \"b\": \"B\",\n    \"a\": \"A\\u00e9\"\n}",
+    )
+    .unwrap();
+    fs::write(dir.join("de_de.json"), "{\n    \"a\": \"\\u00c4\"\n}").unwrap();
+
+    let ds = DataSource::new_dir(&dir).unwrap();
+    let mut lt = LanguageTable::load(&ds, "/").unwrap();
+    assert_eq!(Some("b"), lt.get_key_at(0).map(|s| &**s));
+    assert_eq!(Some("a"), lt.get_key_at(1).map(|s| &**s));
+
+    lt.insert("en_us", "a", "A2");
+    lt.insert("en_us", "c", "C");
+    lt.insert("fr_fr", "b", "B");
+
+    let old_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+    fs::File::options()
+        .write(true)
+        .open(dir.join("de_de.json"))
+        .unwrap()
+        .set_modified(old_time)
+        .unwrap();
+
+    lt.save(&ds, "/").unwrap();
+    assert_eq!(
+        "{\n    \"b\": \"B\",\n    \"a\": \"A2\",\n    \"c\": \"C\"\n}",
+        fs::read_to_string(dir.join("en_us.json")).unwrap()
+    );
+    assert_eq!(
+        "{\n    \"b\": \"B\"\n}",
+        fs::read_to_string(dir.join("fr_fr.json")).unwrap()
+    );
+
+    // unchanged files aren't written
+    let de = fs::metadata(dir.join("de_de.json")).unwrap();
+    assert_eq!(old_time, de.modified().unwrap());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn remove_rename_keys() {
+    use std::fs;
+
+    let dir = test_dir("rename");
+    fs::write(
+        dir.join("en_us.json"),
+        "{\"block.mymod.a\":\"A\",\"x\":\"X\",\"block.mymod.b\":\"B\"}",
+    )
+    .unwrap();
+    fs::write(dir.join("de_de.json"), "{\"x\":\"X\"}").unwrap();
+
+    let ds = DataSource::new_dir(&dir).unwrap();
+    let mut lt = LanguageTable::load(&ds, "/").unwrap();
+
+    assert_eq!(
+        2,
+        lt.rename_key_prefix("block.mymod.", "block.other.")
+            .unwrap()
+    );
+    assert!(matches!(
+        lt.rename_key("x", "block.other.a"),
+        Err(Error::KeyExists(_))
+    ));
+    assert!(matches!(
+        lt.rename_key("y", "z"),
+        Err(Error::KeyNotFound(_))
+    ));
+    assert_eq!(Some("x"), lt.get_key_at(1).map(|s| &**s));
+
+    // swapping keys
+    lt.rename_keys(vec![
+        ("block.other.a", "block.other.b"),
+        ("block.other.b", "block.other.a"),
+    ])
+    .unwrap();
+    assert_eq!(Some("B"), lt.get("en_us", "block.other.a"));
+
+    assert_eq!(Some("X".to_string()), lt.remove_value("de_de", "x"));
+    assert_eq!(None, lt.remove_value("de_de", "x"));
+    assert!(lt.remove_key("x"));
+    assert!(!lt.remove_key("x"));
+    assert_eq!(2, lt.key_count());
+    assert!(lt.remove_language("de_de"));
+    assert_eq!(1, lt.lang_count());
+
+    lt.save(&ds, "/").unwrap();
+    assert_eq!(
+        "{\"block.other.b\":\"A\",\"block.other.a\":\"B\"}",
+        fs::read_to_string(dir.join("en_us.json")).unwrap()
+    );
+    assert!(!dir.join("de_de.json").exists());
+
+    assert_eq!(1, lt.remove_keys(["block.other.a", "missing"]));
+    assert_eq!(1, lt.remove_languages(vec!["en_us".to_string()]));
+    assert_eq!(1, lt.key_count());
+    assert_eq!(0, lt.lang_count());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,326 @@
+//! gettext PO and POT files.
+//!
+//! Each key is written as an entry with the key as `msgctxt`, the value in
+//! the reference language as `msgid` and the translation as `msgstr`, so
+//! that tools like Poedit or Weblate show the reference language text as the
+//! source to translate.
+
+use std::io::{Read, Write};
+
+use mcplatfm::resource::mixed_case_lang;
+
+use crate::langtable::merge::{MergeOptions, MergeReport, TranslationUnit};
+use crate::langtable::{Error, LanguageTable, Result};
+
+impl LanguageTable {
+    /// Writes a PO template with the keys that have a value in `reference`.
+    pub fn export_pot<W: Write>(&self, pipe: W, reference: &str) -> Result<()> {
+        self.write_po(pipe, reference, None)
+    }
+
+    /// Writes the translations of `language` as a PO file. Keys without a
+    /// value in `language` get an empty `msgstr`.
+    pub fn export_po<W: Write>(&self, pipe: W, reference: &str, language: &str) -> Result<()> {
+        self.write_po(pipe, reference, Some(language))
+    }
+
+    /// Reads a PO file and merges its translations into `language`. The
+    /// `msgid`s are compared to the current values in `reference` to find
+    /// translations of outdated text.
+    pub fn import_po<R: Read>(
+        &mut self,
+        mut pipe: R,
+        reference: &str,
+        language: &str,
+        options: MergeOptions,
+    ) -> Result<MergeReport> {
+        let mut buf = String::new();
+        pipe.read_to_string(&mut buf)?;
+        let units = parse_po(&buf)?;
+        Ok(self.merge(reference, language, units, options))
+    }
+
+    fn write_po<W: Write>(
+        &self,
+        mut pipe: W,
+        reference: &str,
+        language: Option<&str>,
+    ) -> Result<()> {
+        let mut header = "Content-Type: text/plain; charset=UTF-8\n".to_string();
+
+        if let Some(language) = language {
+            header.push_str(&format!("Language: {}\n", mixed_case_lang(language, '_')));
+        }
+
+        write_field(&mut pipe, "msgid", "")?;
+        write_field(&mut pipe, "msgstr", &header)?;
+
+        for key in self.keys.iter() {
+            let source = match self.get(reference, key) {
+                Some(source) => source,
+                None => continue,
+            };
+            let target = language.and_then(|l| self.get(l, key)).unwrap_or("");
+
+            writeln!(pipe)?;
+            write_field(&mut pipe, "msgctxt", key)?;
+            write_field(&mut pipe, "msgid", source)?;
+            write_field(&mut pipe, "msgstr", target)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a field, splitting it into one string per line if it contains
+/// line breaks, like gettext does.
+fn write_field<W: Write>(pipe: &mut W, keyword: &str, value: &str) -> Result<()> {
+    let lines: Vec<&str> = value.split_inclusive('\n').collect();
+
+    if lines.len() > 1 {
+        writeln!(pipe, "{} \"\"", keyword)?;
+
+        for line in lines {
+            writeln!(pipe, "\"{}\"", escape(line))?;
+        }
+    } else {
+        writeln!(pipe, "{} \"{}\"", keyword, escape(value))?;
+    }
+
+    Ok(())
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Field {
+    Context,
+    Id,
+    /// `msgid_plural` and `msgstr[n]` for n > 0, which are ignored.
+    Ignored,
+    Str,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    context: Option<String>,
+    id: String,
+    target: String,
+    fuzzy: bool,
+    obsolete: bool,
+    has_target: bool,
+}
+
+impl Entry {
+    /// Returns the unit for this entry, or `None` for the header and entries
+    /// without a key.
+    fn into_unit(self) -> Option<TranslationUnit> {
+        Some(TranslationUnit {
+            key: self.context?,
+            source: self.id,
+            target: self.target,
+            fuzzy: self.fuzzy,
+            obsolete: self.obsolete,
+        })
+    }
+}
+
+/// Parses a PO file. Entries without a `msgctxt` aren't included, since the
+/// key can't be known for them.
+pub fn parse_po(s: &str) -> Result<Vec<TranslationUnit>> {
+    let s = s.strip_prefix('\u{FEFF}').unwrap_or(s);
+    let mut units = Vec::new();
+    let mut entry = Entry::default();
+    let mut field = None;
+
+    for (idx, line) in s.lines().enumerate() {
+        let line_no = idx + 1;
+        let mut line = line.trim();
+        let mut obsolete = false;
+
+        if let Some(rest) = line.strip_prefix("#~") {
+            line = rest.trim_start();
+            obsolete = true;
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        // comments come before the entry they belong to
+        if line.starts_with('#') {
+            if entry.has_target {
+                units.extend(std::mem::take(&mut entry).into_unit());
+            }
+
+            if let Some(flags) = line.strip_prefix("#,") {
+                entry.fuzzy |= flags.split(',').any(|f| f.trim() == "fuzzy");
+            }
+
+            continue;
+        }
+
+        let (keyword, rest) = match line.find(|c: char| c.is_whitespace()) {
+            _ if line.starts_with('"') => ("", line),
+            Some(idx) => (&line[..idx], line[idx..].trim_start()),
+            None => return Err(Error::Po(line_no, format!("unexpected '{}'", line))),
+        };
+        let value =
+            parse_string(rest).ok_or_else(|| Error::Po(line_no, "invalid string".to_string()))?;
+
+        let next = match keyword {
+            "" => match field {
+                Some(field) => field,
+                None => return Err(Error::Po(line_no, "string outside of a field".to_string())),
+            },
+            "msgctxt" => Field::Context,
+            "msgid" => Field::Id,
+            "msgid_plural" => Field::Ignored,
+            "msgstr" | "msgstr[0]" => Field::Str,
+            k if k.starts_with("msgstr[") => Field::Ignored,
+            k => return Err(Error::Po(line_no, format!("unknown keyword '{}'", k))),
+        };
+
+        // a msgctxt or msgid after a msgstr starts the next entry
+        if entry.has_target && (keyword == "msgctxt" || keyword == "msgid") {
+            units.extend(std::mem::take(&mut entry).into_unit());
+        }
+
+        entry.obsolete |= obsolete;
+
+        match next {
+            Field::Context => entry
+                .context
+                .get_or_insert_with(String::new)
+                .push_str(&value),
+            Field::Id => entry.id.push_str(&value),
+            Field::Str => {
+                entry.target.push_str(&value);
+                entry.has_target = true;
+            }
+            Field::Ignored => {}
+        }
+
+        field = Some(next);
+    }
+
+    units.extend(entry.into_unit());
+    Ok(units)
+}
+
+/// Parses a quoted string with C escapes, which must be the only thing in
+/// `s`.
+fn parse_string(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push(match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                c => c,
+            }),
+            '"' => return None,
+            c => out.push(c),
+        }
+    }
+
+    Some(out)
+}
+
+#[test]
+fn po_round_trip() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "Stone");
+    lt.insert("en_us", "b", "Two\nlines \"quoted\"");
+    lt.insert("en_us", "c", "Dirt");
+    lt.insert("de_de", "a", "Stein");
+    lt.insert("de_de", "only_de", "Nur");
+
+    let mut buf = Vec::new();
+    lt.export_po(&mut buf, "en_us", "de_de").unwrap();
+    let po = String::from_utf8(buf).unwrap();
+
+    assert!(po.contains("\"Language: de_DE\\n\""));
+    assert!(po.contains("msgctxt \"a\"\nmsgid \"Stone\"\nmsgstr \"Stein\"\n"));
+    assert!(po.contains("msgid \"\"\n\"Two\\n\"\n\"lines \\\"quoted\\\"\"\n"));
+    assert!(!po.contains("only_de"));
+
+    let units = parse_po(&po).unwrap();
+    assert_eq!(3, units.len());
+    assert_eq!("Two\nlines \"quoted\"", units[1].source);
+    assert_eq!("", units[1].target);
+
+    let mut buf = Vec::new();
+    lt.export_pot(&mut buf, "en_us").unwrap();
+    let pot = String::from_utf8(buf).unwrap();
+    assert!(pot.contains("msgctxt \"a\"\nmsgid \"Stone\"\nmsgstr \"\"\n"));
+    assert!(!pot.contains("Language:"));
+}
+
+#[test]
+fn po_import() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "Stone");
+    lt.insert("en_us", "b", "Dirt");
+    lt.insert("en_us", "c", "Sand");
+
+    let po = r#"
+msgid ""
+msgstr "Language: de_DE\n"
+
+msgctxt "a"
+msgid "Stone"
+msgstr "Stein"
+
+#, fuzzy
+msgctxt "b"
+msgid "Dirt"
+msgstr "Dreck"
+
+msgctxt "c"
+msgid "Sand"
+msgstr ""
+"S"
+"and"
+
+#~ msgctxt "old"
+#~ msgid "Old"
+#~ msgstr "Alt"
+"#;
+
+    let report = lt
+        .import_po(po.as_bytes(), "en_us", "de_de", MergeOptions::default())
+        .unwrap();
+    assert_eq!(2, report.updated.len());
+    assert_eq!(1, report.fuzzy.len());
+    assert_eq!(1, report.unknown.len());
+    assert_eq!(Some("Stein"), lt.get("de_de", "a"));
+    assert_eq!(None, lt.get("de_de", "b"));
+    assert_eq!(Some("Sand"), lt.get("de_de", "c"));
+
+    let units = parse_po(po).unwrap();
+    assert!(units[3].obsolete);
+    assert!(!units[2].fuzzy);
+
+    assert!(matches!(parse_po("msgid \"a"), Err(Error::Po(1, _))));
+    assert!(matches!(parse_po("\n\"a\""), Err(Error::Po(2, _))));
+}
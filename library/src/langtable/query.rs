@@ -0,0 +1,260 @@
+//! Searching and filtering the keys of a [`LanguageTable`].
+
+use regex::{Regex, RegexBuilder};
+
+use crate::langtable::{LanguageTable, Result};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Pattern {
+    /// Matches text containing the string.
+    Substring(String),
+    /// Matches text the regular expression matches part of.
+    Regex(String),
+    /// Matches text containing the characters of the string in the same
+    /// order, but not necessarily next to each other, so that `stnpck`
+    /// matches `Stone Pickaxe`.
+    Fuzzy(String),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Filter {
+    /// Keys without a value in the language.
+    MissingIn(String),
+    /// Keys whose value in any language is different from the one in the
+    /// file it was loaded from. Every value of a language that wasn't loaded
+    /// from a file counts as changed, which includes every language after
+    /// converting the table to another format.
+    Changed,
+    /// Keys starting with the string.
+    KeyPrefix(String),
+}
+
+/// Which keys of a table to find. Keys have to match the pattern, if there
+/// is one, and every filter.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Query {
+    pub pattern: Option<Pattern>,
+    pub case_sensitive: bool,
+    /// Whether to match the pattern against the keys.
+    pub in_keys: bool,
+    /// The languages whose values to match the pattern against.
+    pub in_languages: Vec<String>,
+    pub filters: Vec<Filter>,
+}
+
+enum Matcher {
+    Regex(Regex),
+    Fuzzy(Vec<char>, bool),
+}
+
+impl Matcher {
+    fn new(pattern: &Pattern, case_sensitive: bool) -> Result<Self> {
+        let regex = |s: &str| {
+            RegexBuilder::new(s)
+                .case_insensitive(!case_sensitive)
+                .build()
+        };
+
+        Ok(match pattern {
+            Pattern::Substring(s) => Matcher::Regex(regex(&regex::escape(s))?),
+            Pattern::Regex(s) => Matcher::Regex(regex(s)?),
+            Pattern::Fuzzy(s) => {
+                let chars = s
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(|c| fold_case(c, case_sensitive))
+                    .collect();
+                Matcher::Fuzzy(chars, case_sensitive)
+            }
+        })
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Regex(re) => re.is_match(text),
+            Matcher::Fuzzy(chars, case_sensitive) => {
+                let mut text = text.chars().map(|c| fold_case(c, *case_sensitive));
+                chars.iter().all(|c| text.any(|t| t == *c))
+            }
+        }
+    }
+}
+
+fn fold_case(c: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        c
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+impl LanguageTable {
+    /// Returns the positions of the keys matching `query`, in order.
+    pub fn query(&self, query: &Query) -> Result<Vec<usize>> {
+        self.query_in(query, 0..self.keys.len())
+    }
+
+    /// Returns the positions out of `rows` of the keys matching `query`. For
+    /// searching as the user types, pass the result of the last query if the
+    /// new one can only match fewer keys, such as when the substring that is
+    /// searched for got longer.
+    pub fn query_in<I>(&self, query: &Query, rows: I) -> Result<Vec<usize>>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let matcher = match &query.pattern {
+            Some(pattern) => Some(Matcher::new(pattern, query.case_sensitive)?),
+            None => None,
+        };
+
+        let columns: Vec<_> = query
+            .in_languages
+            .iter()
+            .filter_map(|l| self.languages.position(l))
+            .map(|col| &self.columns[col])
+            .collect();
+
+        let missing: Vec<_> = query
+            .filters
+            .iter()
+            .filter_map(|f| match f {
+                Filter::MissingIn(l) => Some(self.languages.position(l)),
+                _ => None,
+            })
+            .collect();
+
+        let changed = query.filters.contains(&Filter::Changed);
+
+        let is_match = |row: usize| {
+            let key = match self.keys.get_at(row) {
+                Some(key) => &**key,
+                None => return false,
+            };
+
+            for f in query.filters.iter() {
+                if let Filter::KeyPrefix(prefix) = f {
+                    if !key.starts_with(&**prefix) {
+                        return false;
+                    }
+                }
+            }
+
+            let missing_ok = missing.iter().all(|col| match col {
+                Some(col) => self.columns[*col].get(row).is_none(),
+                None => true,
+            });
+
+            if !missing_ok || (changed && !self.is_changed(row, key)) {
+                return false;
+            }
+
+            match &matcher {
+                None => true,
+                Some(m) => {
+                    (query.in_keys && m.is_match(key))
+                        || columns
+                            .iter()
+                            .any(|c| c.get(row).is_some_and(|v| m.is_match(v)))
+                }
+            }
+        };
+
+        Ok(rows.into_iter().filter(|&row| is_match(row)).collect())
+    }
+
+    /// Returns whether any value of `key`, which is at `row`, is different
+    /// from the one in the file it was loaded from.
+    fn is_changed(&self, row: usize, key: &str) -> bool {
+        self.columns
+            .iter()
+            .any(|part| part.get(row) != part.source.as_ref().and_then(|s| s.get(key)))
+    }
+}
+
+#[test]
+fn query_table() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "block.test.stone", "Stone");
+    lt.insert("en_us", "item.test.stone_pickaxe", "Stone Pickaxe");
+    lt.insert("en_us", "item.test.dirt", "Dirt (50%)");
+    lt.insert("de_de", "block.test.stone", "Stein");
+    lt.insert("de_de", "item.test.stone_pickaxe", "Steinspitzhacke");
+
+    let query = |pattern, in_keys, languages: &[&str], filters| {
+        let q = Query {
+            pattern,
+            case_sensitive: false,
+            in_keys,
+            in_languages: languages.iter().map(|s| s.to_string()).collect(),
+            filters,
+        };
+        lt.query(&q).unwrap()
+    };
+
+    let substring = |s: &str| Some(Pattern::Substring(s.to_string()));
+
+    assert_eq!(vec![0, 1, 2], query(None, false, &[], vec![]));
+    assert_eq!(
+        vec![0, 1],
+        query(substring("STONE"), false, &["en_us"], vec![])
+    );
+    assert_eq!(
+        vec![2],
+        query(substring("(50%)"), false, &["en_us"], vec![])
+    );
+    assert_eq!(vec![2], query(substring("dirt"), true, &[], vec![]));
+    assert_eq!(
+        vec![1],
+        query(substring("spitz"), false, &["de_de"], vec![])
+    );
+    assert!(query(substring("spitz"), true, &["en_us"], vec![]).is_empty());
+
+    let regex = Some(Pattern::Regex("^stone$".to_string()));
+    assert_eq!(vec![0], query(regex, false, &["en_us"], vec![]));
+
+    let fuzzy = Some(Pattern::Fuzzy("stn pck".to_string()));
+    assert_eq!(vec![1], query(fuzzy, false, &["en_us", "de_de"], vec![]));
+
+    let missing = vec![Filter::MissingIn("de_de".to_string())];
+    assert_eq!(vec![2], query(None, false, &[], missing));
+    let prefix = vec![Filter::KeyPrefix("item.".to_string())];
+    assert_eq!(vec![1], query(substring("e"), false, &["de_de"], prefix));
+
+    let q = Query {
+        pattern: Some(Pattern::Substring("stone".to_string())),
+        in_keys: true,
+        ..Query::default()
+    };
+    assert_eq!(vec![1], lt.query_in(&q, vec![1, 2]).unwrap());
+
+    let q = Query {
+        pattern: Some(Pattern::Regex("(".to_string())),
+        ..Query::default()
+    };
+    assert!(matches!(lt.query(&q), Err(super::Error::Regex(_))));
+}
+
+#[test]
+fn query_changed() {
+    use matryoshka::DataSource;
+    use std::fs;
+
+    let dir = super::test_dir("query");
+    fs::write(dir.join("en_us.json"), "{\"a\": \"A\", \"b\": \"B\"}").unwrap();
+
+    let ds = DataSource::new_dir(&dir).unwrap();
+    let mut lt = LanguageTable::load(&ds, "/").unwrap();
+    let q = Query {
+        filters: vec![Filter::Changed],
+        ..Query::default()
+    };
+    assert!(lt.query(&q).unwrap().is_empty());
+
+    lt.insert("en_us", "b", "B2");
+    lt.insert("en_us", "c", "C");
+    assert_eq!(vec![1, 2], lt.query(&q).unwrap());
+
+    lt.insert("en_us", "b", "B");
+    lt.insert("de_de", "a", "Ä");
+    assert_eq!(vec![0, 2], lt.query(&q).unwrap());
+}
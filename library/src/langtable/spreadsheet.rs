@@ -0,0 +1,304 @@
+//! CSV and TSV files, for editing a [`LanguageTable`] in a spreadsheet.
+//!
+//! The first row contains the languages, and each following row a key and
+//! its value in each language, in the same layout as the table is shown in.
+//! Cells of keys without a value in a language are empty, as are ones whose
+//! value is the empty string.
+
+use std::collections::HashSet;
+use std::io::{Read, Write};
+
+use csv::{ReaderBuilder, WriterBuilder};
+
+use crate::langtable::{Error, LanguageTable, RcString, Result};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SheetFormat {
+    Csv,
+    Tsv,
+}
+
+impl SheetFormat {
+    fn delimiter(self) -> u8 {
+        match self {
+            SheetFormat::Csv => b',',
+            SheetFormat::Tsv => b'\t',
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+/// A cell that is different in a spreadsheet than in the table.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CellChange {
+    pub language: RcString,
+    pub key: RcString,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl CellChange {
+    pub fn kind(&self) -> ChangeKind {
+        match (&self.old, &self.new) {
+            (None, _) => ChangeKind::Added,
+            (Some(_), Some(_)) => ChangeKind::Changed,
+            (Some(_), None) => ChangeKind::Removed,
+        }
+    }
+}
+
+/// The changes a spreadsheet would make to a table, so that they can be
+/// reviewed before they are applied with [`LanguageTable::apply_sheet`].
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct SheetChanges {
+    pub cells: Vec<CellChange>,
+}
+
+impl SheetChanges {
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn of_kind(&self, kind: ChangeKind) -> impl Iterator<Item = &CellChange> {
+        self.cells.iter().filter(move |c| c.kind() == kind)
+    }
+
+    pub fn added(&self) -> impl Iterator<Item = &CellChange> {
+        self.of_kind(ChangeKind::Added)
+    }
+
+    pub fn changed(&self) -> impl Iterator<Item = &CellChange> {
+        self.of_kind(ChangeKind::Changed)
+    }
+
+    pub fn removed(&self) -> impl Iterator<Item = &CellChange> {
+        self.of_kind(ChangeKind::Removed)
+    }
+}
+
+impl LanguageTable {
+    pub fn export_sheet<W: Write>(&self, pipe: W, format: SheetFormat) -> Result<()> {
+        let mut w = WriterBuilder::new()
+            .delimiter(format.delimiter())
+            .from_writer(pipe);
+
+        w.write_field("key")?;
+        w.write_record(self.languages.iter().map(|l| l.as_bytes()))?;
+
+        for key in self.keys.iter() {
+            w.write_field(key.as_bytes())?;
+            w.write_record(
+                self.languages
+                    .iter()
+                    .map(|l| self.get(l, key).unwrap_or("").as_bytes()),
+            )?;
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Reads a spreadsheet and returns how it differs from this table,
+    /// without changing the table.
+    ///
+    /// Only the cells in the spreadsheet are compared, so keys and languages
+    /// that aren't in it are left alone. Empty cells remove the value of
+    /// their key in their language, unless that value is the empty string.
+    /// Rows of keys and columns of languages that aren't in the table add
+    /// them.
+    ///
+    /// Returns an error if a language has more than one column or a key more
+    /// than one row, since it wouldn't be clear which cell to use.
+    pub fn read_sheet<R: Read>(&self, pipe: R, format: SheetFormat) -> Result<SheetChanges> {
+        let mut r = ReaderBuilder::new()
+            .delimiter(format.delimiter())
+            .flexible(true)
+            .from_reader(pipe);
+
+        let languages: Vec<RcString> = r
+            .headers()?
+            .iter()
+            .skip(1)
+            .map(|l| self.intern_language(l.trim()))
+            .collect();
+
+        if let Some(idx) = languages.iter().position(|l| l.is_empty()) {
+            return Err(Error::Sheet(format!("column {} has no language", idx + 2)));
+        }
+
+        let mut seen = HashSet::new();
+
+        for language in languages.iter() {
+            if !seen.insert(language) {
+                return Err(Error::Sheet(format!(
+                    "language '{}' has more than one column",
+                    &**language
+                )));
+            }
+        }
+
+        let mut changes = SheetChanges::default();
+        let mut seen = HashSet::new();
+
+        for record in r.records() {
+            let record = record?;
+            let key = match record.get(0) {
+                Some(key) if !key.is_empty() => self.intern_key(key),
+                _ => continue,
+            };
+
+            if !seen.insert(key.clone()) {
+                return Err(Error::Sheet(format!(
+                    "key '{}' has more than one row",
+                    &*key
+                )));
+            }
+
+            for (language, new) in languages.iter().zip(record.iter().skip(1)) {
+                let old = self.get(language, &key);
+                // an empty cell can't tell an empty value from a missing one
+                let new = Some(new).filter(|s| !s.is_empty() || old == Some(""));
+
+                if old != new {
+                    changes.cells.push(CellChange {
+                        language: language.clone(),
+                        key: key.clone(),
+                        old: old.map(|s| s.to_string()),
+                        new: new.map(|s| s.to_string()),
+                    });
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Applies changes read by [`LanguageTable::read_sheet`].
+    pub fn apply_sheet(&mut self, changes: &SheetChanges) {
+        for c in changes.cells.iter() {
+            match &c.new {
+                Some(value) => self.insert(c.language.clone(), c.key.clone(), value.clone()),
+                None => {
+                    self.remove_value(&c.language, &c.key);
+                }
+            }
+        }
+    }
+
+    /// Returns the table's string for `key` if it has one, so that strings
+    /// read from a file share their allocation with the table's.
+    fn intern_key(&self, key: &str) -> RcString {
+        self.keys.get(key).cloned().unwrap_or_else(|| key.into())
+    }
+
+    fn intern_language(&self, language: &str) -> RcString {
+        self.languages
+            .get(language)
+            .cloned()
+            .unwrap_or_else(|| language.into())
+    }
+}
+
+#[test]
+fn sheet_round_trip() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "Stone, \"smooth\"");
+    lt.insert("en_us", "b", "Two\nlines");
+    lt.insert("de_de", "a", "Stein");
+
+    let mut buf = Vec::new();
+    lt.export_sheet(&mut buf, SheetFormat::Csv).unwrap();
+    let csv = String::from_utf8(buf).unwrap();
+    assert_eq!(
+        "key,en_us,de_de\na,\"Stone, \"\"smooth\"\"\",Stein\nb,\"Two\nlines\",\n",
+        csv
+    );
+
+    let changes = lt.read_sheet(csv.as_bytes(), SheetFormat::Csv).unwrap();
+    assert!(changes.is_empty());
+
+    let mut buf = Vec::new();
+    lt.export_sheet(&mut buf, SheetFormat::Tsv).unwrap();
+    let tsv = String::from_utf8(buf).unwrap();
+    assert!(tsv.starts_with("key\ten_us\tde_de\n"));
+    assert!(lt
+        .read_sheet(tsv.as_bytes(), SheetFormat::Tsv)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn sheet_import() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "Stone");
+    lt.insert("en_us", "b", "Dirt");
+    lt.insert("de_de", "a", "Stein");
+    lt.insert("de_de", "b", "Erde");
+    lt.insert("de_de", "c", "Gras");
+
+    let csv = "key,de_de,fr_fr\na,Steine,Pierre\nb,,Terre\nd,Neu\n";
+    let changes = lt.read_sheet(csv.as_bytes(), SheetFormat::Csv).unwrap();
+
+    let cells = |kind| {
+        changes
+            .of_kind(kind)
+            .map(|c| format!("{}/{}", &*c.language, &*c.key))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec!["fr_fr/a", "fr_fr/b", "de_de/d"],
+        cells(ChangeKind::Added)
+    );
+    assert_eq!(vec!["de_de/a"], cells(ChangeKind::Changed));
+    assert_eq!(vec!["de_de/b"], cells(ChangeKind::Removed));
+
+    lt.apply_sheet(&changes);
+    assert_eq!(Some("Steine"), lt.get("de_de", "a"));
+    assert_eq!(Some("Pierre"), lt.get("fr_fr", "a"));
+    assert_eq!(None, lt.get("de_de", "b"));
+    assert_eq!(Some("Neu"), lt.get("de_de", "d"));
+    // not in the sheet
+    assert_eq!(Some("Gras"), lt.get("de_de", "c"));
+    assert_eq!(Some("Dirt"), lt.get("en_us", "b"));
+
+    assert!(matches!(
+        lt.read_sheet("key,,de_de\n".as_bytes(), SheetFormat::Csv),
+        Err(Error::Sheet(_))
+    ));
+    assert!(matches!(
+        lt.read_sheet("key,de_de,de_de\na,x,y\n".as_bytes(), SheetFormat::Csv),
+        Err(Error::Sheet(_))
+    ));
+    assert!(matches!(
+        lt.read_sheet("key,de_de\na,x\na,y\n".as_bytes(), SheetFormat::Csv),
+        Err(Error::Sheet(_))
+    ));
+}
+
+#[test]
+fn sheet_empty_values() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "");
+    lt.insert("en_us", "b", "Dirt");
+
+    let mut buf = Vec::new();
+    lt.export_sheet(&mut buf, SheetFormat::Csv).unwrap();
+    assert!(lt
+        .read_sheet(buf.as_slice(), SheetFormat::Csv)
+        .unwrap()
+        .is_empty());
+
+    let changes = lt
+        .read_sheet("key,en_us\na,\nb,\n".as_bytes(), SheetFormat::Csv)
+        .unwrap();
+    assert_eq!(1, changes.removed().count());
+    lt.apply_sheet(&changes);
+    assert_eq!(Some(""), lt.get("en_us", "a"));
+    assert_eq!(None, lt.get("en_us", "b"));
+}
@@ -0,0 +1,736 @@
+//! The structure of language values: `§` formatting codes, `%s` format
+//! specifiers and JSON text components.
+//!
+//! [`tokenize`] splits a value into these parts, [`validate`] checks a
+//! translation against the reference language, and [`preview`] renders a
+//! value into styled spans the way the game would show it.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use serde_json::Value;
+
+use crate::langtable::{LanguageTable, RcString};
+
+/// One of the 16 colors that can be selected with a formatting code.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ChatColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+
+impl ChatColor {
+    pub const ALL: [ChatColor; 16] = [
+        ChatColor::Black,
+        ChatColor::DarkBlue,
+        ChatColor::DarkGreen,
+        ChatColor::DarkAqua,
+        ChatColor::DarkRed,
+        ChatColor::DarkPurple,
+        ChatColor::Gold,
+        ChatColor::Gray,
+        ChatColor::DarkGray,
+        ChatColor::Blue,
+        ChatColor::Green,
+        ChatColor::Aqua,
+        ChatColor::Red,
+        ChatColor::LightPurple,
+        ChatColor::Yellow,
+        ChatColor::White,
+    ];
+
+    /// Returns the color for the formatting code `c`, `0` to `f`.
+    pub fn from_code(c: char) -> Option<Self> {
+        let idx = c.to_digit(16)?;
+        Some(ChatColor::ALL[idx as usize])
+    }
+
+    /// Returns the color with the name `name` in text components, such as
+    /// `dark_red`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        ChatColor::ALL.iter().copied().find(|c| c.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChatColor::Black => "black",
+            ChatColor::DarkBlue => "dark_blue",
+            ChatColor::DarkGreen => "dark_green",
+            ChatColor::DarkAqua => "dark_aqua",
+            ChatColor::DarkRed => "dark_red",
+            ChatColor::DarkPurple => "dark_purple",
+            ChatColor::Gold => "gold",
+            ChatColor::Gray => "gray",
+            ChatColor::DarkGray => "dark_gray",
+            ChatColor::Blue => "blue",
+            ChatColor::Green => "green",
+            ChatColor::Aqua => "aqua",
+            ChatColor::Red => "red",
+            ChatColor::LightPurple => "light_purple",
+            ChatColor::Yellow => "yellow",
+            ChatColor::White => "white",
+        }
+    }
+
+    /// Returns the color as `0xRRGGBB`.
+    pub fn rgb(self) -> u32 {
+        match self {
+            ChatColor::Black => 0x000000,
+            ChatColor::DarkBlue => 0x0000AA,
+            ChatColor::DarkGreen => 0x00AA00,
+            ChatColor::DarkAqua => 0x00AAAA,
+            ChatColor::DarkRed => 0xAA0000,
+            ChatColor::DarkPurple => 0xAA00AA,
+            ChatColor::Gold => 0xFFAA00,
+            ChatColor::Gray => 0xAAAAAA,
+            ChatColor::DarkGray => 0x555555,
+            ChatColor::Blue => 0x5555FF,
+            ChatColor::Green => 0x55FF55,
+            ChatColor::Aqua => 0x55FFFF,
+            ChatColor::Red => 0xFF5555,
+            ChatColor::LightPurple => 0xFF55FF,
+            ChatColor::Yellow => 0xFFFF55,
+            ChatColor::White => 0xFFFFFF,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Color {
+    Chat(ChatColor),
+    /// A color given as `#RRGGBB` in a text component.
+    Rgb(u32),
+}
+
+impl Color {
+    /// Parses a color name or `#RRGGBB`, as used in text components.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.strip_prefix('#') {
+            Some(hex) if hex.len() == 6 => u32::from_str_radix(hex, 16).ok().map(Color::Rgb),
+            Some(_) => None,
+            None => ChatColor::from_name(s).map(Color::Chat),
+        }
+    }
+
+    pub fn rgb(self) -> u32 {
+        match self {
+            Color::Chat(c) => c.rgb(),
+            Color::Rgb(rgb) => rgb,
+        }
+    }
+}
+
+/// A formatting code, such as `§c` or `§l`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum FormatCode {
+    Color(ChatColor),
+    Obfuscated,
+    Bold,
+    Strikethrough,
+    Underlined,
+    Italic,
+    Reset,
+}
+
+impl FormatCode {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_lowercase() {
+            'k' => Some(FormatCode::Obfuscated),
+            'l' => Some(FormatCode::Bold),
+            'm' => Some(FormatCode::Strikethrough),
+            'n' => Some(FormatCode::Underlined),
+            'o' => Some(FormatCode::Italic),
+            'r' => Some(FormatCode::Reset),
+            c => ChatColor::from_code(c).map(FormatCode::Color),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct Style {
+    /// The color of the text, or `None` for the default color of wherever
+    /// the text is shown.
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub strikethrough: bool,
+    pub obfuscated: bool,
+}
+
+impl Style {
+    /// Applies a formatting code. Like in the game, a color code also
+    /// resets the other formatting.
+    pub fn apply(&mut self, code: FormatCode) {
+        match code {
+            FormatCode::Color(c) => {
+                *self = Style {
+                    color: Some(Color::Chat(c)),
+                    ..Style::default()
+                }
+            }
+            FormatCode::Obfuscated => self.obfuscated = true,
+            FormatCode::Bold => self.bold = true,
+            FormatCode::Strikethrough => self.strikethrough = true,
+            FormatCode::Underlined => self.underlined = true,
+            FormatCode::Italic => self.italic = true,
+            FormatCode::Reset => *self = Style::default(),
+        }
+    }
+}
+
+/// A format specifier in a value, such as `%s` or `%2$d`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct FormatSpecifier {
+    /// The index of the argument this refers to, starting at 1. For
+    /// specifiers without an explicit index, this is the position among
+    /// those.
+    pub index: usize,
+    pub conversion: char,
+}
+
+/// A part of a value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Token<'a> {
+    Text(&'a str),
+    Code(FormatCode),
+    /// A `§` followed by a character that isn't a formatting code, or by
+    /// nothing at all, which the game doesn't show.
+    InvalidCode {
+        pos: usize,
+        ch: Option<char>,
+    },
+    /// A format specifier, or `None` for `%%` and `%n`, which don't refer to
+    /// an argument.
+    Specifier {
+        raw: &'a str,
+        spec: Option<FormatSpecifier>,
+    },
+}
+
+/// Splits a plain value into text, formatting codes and format specifiers,
+/// using the syntax of Java's `Formatter` for the latter. A `%` that doesn't
+/// start a valid specifier is text.
+pub fn tokenize(s: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut text_start = 0;
+    let mut next_index = 1;
+    let mut last_index = None;
+    let mut iter = s.char_indices().peekable();
+
+    while let Some((pos, c)) = iter.next() {
+        let (token, end) = match c {
+            '§' => match iter.peek().copied() {
+                Some((_, ch)) => {
+                    iter.next();
+                    let token = match FormatCode::from_char(ch) {
+                        Some(code) => Token::Code(code),
+                        None => Token::InvalidCode { pos, ch: Some(ch) },
+                    };
+                    (token, pos + '§'.len_utf8() + ch.len_utf8())
+                }
+                None => (Token::InvalidCode { pos, ch: None }, s.len()),
+            },
+            '%' => {
+                let raw = match parse_specifier(&s[pos + 1..]) {
+                    Some(raw) => raw,
+                    None => continue,
+                };
+                let end = pos + 1 + raw.len;
+
+                while iter.peek().is_some_and(|&(idx, _)| idx < end) {
+                    iter.next();
+                }
+
+                let index = match raw {
+                    RawSpecifier { arg: false, .. } => None,
+                    RawSpecifier {
+                        explicit: Some(idx),
+                        ..
+                    } => Some(idx),
+                    RawSpecifier { relative: true, .. } => last_index,
+                    _ => {
+                        next_index += 1;
+                        Some(next_index - 1)
+                    }
+                };
+
+                if index.is_some() {
+                    last_index = index;
+                }
+
+                let spec = index.map(|index| FormatSpecifier {
+                    index,
+                    conversion: raw.conversion.to_ascii_lowercase(),
+                });
+
+                let token = Token::Specifier {
+                    raw: &s[pos..end],
+                    spec,
+                };
+                (token, end)
+            }
+            _ => continue,
+        };
+
+        if text_start < pos {
+            tokens.push(Token::Text(&s[text_start..pos]));
+        }
+
+        tokens.push(token);
+        text_start = end;
+    }
+
+    if text_start < s.len() {
+        tokens.push(Token::Text(&s[text_start..]));
+    }
+
+    tokens
+}
+
+struct RawSpecifier {
+    /// The length of the specifier, without the `%`.
+    len: usize,
+    explicit: Option<usize>,
+    relative: bool,
+    conversion: char,
+    /// Whether this refers to an argument.
+    arg: bool,
+}
+
+/// Parses `[index$][flags][width][.precision]conversion`, the part of a
+/// format specifier after the `%`.
+fn parse_specifier(s: &str) -> Option<RawSpecifier> {
+    let mut rest = s;
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let mut explicit = None;
+    let mut relative = false;
+
+    if digits > 0 && rest[digits..].starts_with('$') {
+        explicit = rest[..digits].parse().ok();
+        rest = &rest[digits + 1..];
+    } else if let Some(r) = rest.strip_prefix('<') {
+        relative = true;
+        rest = r;
+    }
+
+    rest = rest.trim_start_matches(['-', '#', '+', ' ', '0', ',', '(']);
+    rest = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+
+    if let Some(r) = rest.strip_prefix('.') {
+        rest = r.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+
+    let conversion = rest
+        .chars()
+        .next()
+        .filter(|&c| c.is_ascii_alphabetic() || c == '%')?;
+    rest = &rest[1..];
+
+    if conversion == 't' || conversion == 'T' {
+        rest.chars().next().filter(char::is_ascii_alphabetic)?;
+        rest = &rest[1..];
+    }
+
+    Some(RawSpecifier {
+        len: s.len() - rest.len(),
+        explicit,
+        relative,
+        conversion,
+        arg: conversion != '%' && conversion != 'n',
+    })
+}
+
+/// Returns the format specifiers in `s` that refer to an argument, in the
+/// order they appear.
+pub fn format_specifiers(s: &str) -> Vec<FormatSpecifier> {
+    tokenize(s)
+        .into_iter()
+        .filter_map(|t| match t {
+            Token::Specifier { spec, .. } => spec,
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parses `s` as a JSON text component if it is a JSON object or array.
+pub fn parse_component(s: &str) -> Option<Value> {
+    let trimmed = s.trim_start();
+
+    if !trimmed.starts_with('{') && !trimmed.starts_with('[') {
+        return None;
+    }
+
+    serde_json::from_str(s).ok()
+}
+
+/// Returns the text in a value, with the text of all parts of it joined if it
+/// is a text component.
+fn plain_text(s: &str) -> String {
+    fn collect(component: &Value, out: &mut String) {
+        match component {
+            Value::String(s) => out.push_str(s),
+            Value::Array(parts) => parts.iter().for_each(|p| collect(p, out)),
+            Value::Object(obj) => {
+                if let Some(Value::String(text)) = obj.get("text") {
+                    out.push_str(text);
+                }
+
+                if let Some(extra) = obj.get("extra") {
+                    collect(extra, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match parse_component(s) {
+        Some(component) => {
+            let mut out = String::new();
+            collect(&component, &mut out);
+            out
+        }
+        None => s.to_string(),
+    }
+}
+
+/// A problem with a translation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Problem {
+    /// The translation doesn't use the same format arguments as the value in
+    /// the reference language.
+    PlaceholderMismatch {
+        expected: Vec<FormatSpecifier>,
+        actual: Vec<FormatSpecifier>,
+    },
+    /// A `§` at byte offset `pos` that isn't followed by a formatting code.
+    InvalidFormatCode { pos: usize, ch: Option<char> },
+    /// The value in the reference language is a text component, but the
+    /// translation isn't.
+    InvalidComponent,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let list = |specs: &[FormatSpecifier]| {
+            specs
+                .iter()
+                .map(|s| format!("%{}${}", s.index, s.conversion))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match self {
+            Problem::PlaceholderMismatch { expected, actual } => write!(
+                f,
+                "placeholders don't match: expected [{}], found [{}]",
+                list(expected),
+                list(actual)
+            ),
+            Problem::InvalidFormatCode { pos, ch: Some(ch) } => {
+                write!(f, "invalid formatting code '{}' at {}", ch, pos)
+            }
+            Problem::InvalidFormatCode { pos, ch: None } => {
+                write!(f, "formatting code at {} is missing its character", pos)
+            }
+            Problem::InvalidComponent => f.write_str("not a valid JSON text component"),
+        }
+    }
+}
+
+/// Checks the translation `value` of the reference language value
+/// `reference`.
+pub fn validate(reference: &str, value: &str) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    if parse_component(reference).is_some() && parse_component(value).is_none() {
+        problems.push(Problem::InvalidComponent);
+    }
+
+    let mut expected = format_specifiers(&plain_text(reference));
+    let mut actual = format_specifiers(&plain_text(value));
+    expected.sort();
+    actual.sort();
+
+    if expected != actual {
+        problems.push(Problem::PlaceholderMismatch { expected, actual });
+    }
+
+    for token in tokenize(value) {
+        if let Token::InvalidCode { pos, ch } = token {
+            problems.push(Problem::InvalidFormatCode { pos, ch });
+        }
+    }
+
+    problems
+}
+
+impl LanguageTable {
+    /// Checks every value in `language` against the value of the same key in
+    /// `reference`.
+    pub fn validate(&self, reference: &str, language: &str) -> Vec<(RcString, Problem)> {
+        let mut problems = Vec::new();
+
+        for key in self.keys.iter() {
+            if let (Some(expected), Some(actual)) =
+                (self.get(reference, key), self.get(language, key))
+            {
+                problems.extend(
+                    validate(expected, actual)
+                        .into_iter()
+                        .map(|p| (key.clone(), p)),
+                );
+            }
+        }
+
+        problems
+    }
+}
+
+/// A piece of text with a single style.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+/// Renders a value into styled spans, the way the game would show it.
+/// Format specifiers are shown as they are written, and text components
+/// that refer to other keys with `translate` show the key.
+pub fn preview(value: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+
+    match parse_component(value) {
+        Some(component) => preview_component(&component, Style::default(), &mut spans),
+        None => preview_text(value, Style::default(), &mut spans),
+    }
+
+    spans
+}
+
+fn preview_text(s: &str, mut style: Style, spans: &mut Vec<Span>) {
+    for token in tokenize(s) {
+        match token {
+            Token::Text(text) | Token::Specifier { raw: text, .. } => push_span(spans, text, style),
+            Token::Code(code) => style.apply(code),
+            Token::InvalidCode { .. } => {}
+        }
+    }
+}
+
+fn preview_component(component: &Value, parent: Style, spans: &mut Vec<Span>) {
+    match component {
+        Value::String(s) => preview_text(s, parent, spans),
+        Value::Array(parts) => {
+            // the first part is the parent of the others
+            if let Some((first, rest)) = parts.split_first() {
+                let style = component_style(first, parent);
+                preview_component(first, parent, spans);
+
+                for part in rest {
+                    preview_component(part, style, spans);
+                }
+            }
+        }
+        Value::Object(obj) => {
+            let style = component_style(component, parent);
+
+            if let Some(Value::String(text)) = obj.get("text") {
+                preview_text(text, style, spans);
+            } else if let Some(Value::String(key)) = obj.get("translate") {
+                push_span(spans, key, style);
+            }
+
+            if let Some(Value::Array(extra)) = obj.get("extra") {
+                for part in extra {
+                    preview_component(part, style, spans);
+                }
+            }
+        }
+        Value::Number(n) => push_span(spans, &n.to_string(), parent),
+        Value::Bool(b) => push_span(spans, &b.to_string(), parent),
+        Value::Null => {}
+    }
+}
+
+/// Returns the style of a component, which inherits what it doesn't set from
+/// its parent.
+fn component_style(component: &Value, parent: Style) -> Style {
+    let obj = match component {
+        Value::Object(obj) => obj,
+        _ => return parent,
+    };
+    let flag =
+        |name: &str, inherited: bool| obj.get(name).and_then(Value::as_bool).unwrap_or(inherited);
+
+    Style {
+        color: obj
+            .get("color")
+            .and_then(Value::as_str)
+            .and_then(Color::parse)
+            .or(parent.color),
+        bold: flag("bold", parent.bold),
+        italic: flag("italic", parent.italic),
+        underlined: flag("underlined", parent.underlined),
+        strikethrough: flag("strikethrough", parent.strikethrough),
+        obfuscated: flag("obfuscated", parent.obfuscated),
+    }
+}
+
+/// Adds text to the spans, extending the last one if it has the same style.
+fn push_span(spans: &mut Vec<Span>, text: &str, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => spans.push(Span {
+            text: text.to_string(),
+            style,
+        }),
+    }
+}
+
+#[test]
+fn format_specifier_parsing() {
+    let spec = |index, conversion| FormatSpecifier { index, conversion };
+
+    assert_eq!(
+        vec![spec(1, 's'), spec(2, 'd'), spec(1, 's')],
+        format_specifiers("%s has %d items, %1$s")
+    );
+    assert_eq!(
+        vec![spec(2, 's'), spec(1, 's')],
+        format_specifiers("%2$s %s")
+    );
+    assert_eq!(
+        vec![spec(1, 'f'), spec(1, 'f')],
+        format_specifiers("%-8.2f%<F 100%% %n")
+    );
+    assert_eq!(
+        Vec::<FormatSpecifier>::new(),
+        format_specifiers("off by 50%")
+    );
+}
+
+#[test]
+fn tokenize_value() {
+    assert_eq!(
+        vec![
+            Token::Code(FormatCode::Color(ChatColor::Red)),
+            Token::Text("Hi "),
+            Token::Specifier {
+                raw: "%1$s",
+                spec: Some(FormatSpecifier {
+                    index: 1,
+                    conversion: 's'
+                })
+            },
+            Token::Text(" 5%. "),
+            Token::Specifier {
+                raw: "%%",
+                spec: None
+            },
+            Token::InvalidCode {
+                pos: 17,
+                ch: Some('z')
+            },
+            Token::Code(FormatCode::Bold),
+            Token::InvalidCode { pos: 23, ch: None },
+        ],
+        tokenize("§cHi %1$s 5%. %%§z§l§")
+    );
+}
+
+#[test]
+fn validate_value() {
+    assert_eq!(
+        Vec::<Problem>::new(),
+        validate("%s by %s", "%2$s von §a%1$s")
+    );
+    assert!(matches!(
+        validate("%s by %s", "%s von %s %s")[..],
+        [Problem::PlaceholderMismatch { .. }]
+    ));
+    assert_eq!(
+        vec![Problem::InvalidFormatCode {
+            pos: 1,
+            ch: Some('x')
+        }],
+        validate("a", "a§x")
+    );
+    assert_eq!(
+        vec![Problem::InvalidComponent],
+        validate(r#"{"text":"%s"}"#, "{\"text\":%s")
+    );
+    assert_eq!(
+        Vec::<Problem>::new(),
+        validate(r#"{"text":"%s"}"#, r#"["",{"text":"§l%s"}]"#)
+    );
+
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "%s");
+    lt.insert("de_de", "a", "kein");
+    assert_eq!(1, lt.validate("en_us", "de_de").len());
+}
+
+#[test]
+fn preview_value() {
+    let red = Some(Color::Chat(ChatColor::Red));
+
+    assert_eq!(
+        vec![
+            Span {
+                text: "A ".to_string(),
+                style: Style::default()
+            },
+            Span {
+                text: "red %s".to_string(),
+                style: Style {
+                    color: red,
+                    ..Style::default()
+                }
+            },
+            Span {
+                text: " bold".to_string(),
+                style: Style {
+                    color: red,
+                    bold: true,
+                    ..Style::default()
+                }
+            },
+            Span {
+                text: " plain".to_string(),
+                style: Style::default()
+            },
+        ],
+        preview("A §cred %s§l bold§r plain")
+    );
+
+    // a color code resets bold
+    assert!(!preview("§l§cx")[0].style.bold);
+
+    let spans = preview(
+        r##"{"text":"A","color":"#102030","extra":[{"text":"B","bold":true},"C",{"translate":"block.stone"}]}"##,
+    );
+    let texts: Vec<_> = spans.iter().map(|s| &*s.text).collect();
+    assert_eq!(vec!["A", "B", "Cblock.stone"], texts);
+    assert_eq!(Some(Color::Rgb(0x102030)), spans[1].style.color);
+    assert!(spans[1].style.bold);
+    assert!(!spans[2].style.bold);
+}
@@ -0,0 +1,331 @@
+//! XLIFF 1.2 and 2.0 files.
+//!
+//! Each key is written as a unit with the key as its ID, the value in the
+//! reference language as the source and the translation as the target.
+
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+use mcplatfm::resource::mixed_case_lang;
+use roxmltree::{Document, Node};
+
+use crate::langtable::merge::{MergeOptions, MergeReport, TranslationUnit};
+use crate::langtable::{Error, LanguageTable, Result};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum XliffVersion {
+    V1_2,
+    V2_0,
+}
+
+impl LanguageTable {
+    /// Writes the translations of `language` as an XLIFF file, with the keys
+    /// that have a value in `reference`. Keys without a value in `language`
+    /// are written without a target.
+    pub fn export_xliff<W: Write>(
+        &self,
+        mut pipe: W,
+        reference: &str,
+        language: &str,
+        version: XliffVersion,
+    ) -> Result<()> {
+        let src = mixed_case_lang(reference, '-');
+        let trg = mixed_case_lang(language, '-');
+
+        writeln!(pipe, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+
+        match version {
+            XliffVersion::V1_2 => {
+                writeln!(
+                    pipe,
+                    r#"<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">"#
+                )?;
+                writeln!(
+                    pipe,
+                    r#"  <file original="{}" source-language="{}" target-language="{}" datatype="plaintext">"#,
+                    escape(language),
+                    src,
+                    trg
+                )?;
+                writeln!(pipe, "    <body>")?;
+            }
+            XliffVersion::V2_0 => {
+                writeln!(
+                    pipe,
+                    r#"<xliff version="2.0" xmlns="urn:oasis:names:tc:xliff:document:2.0" srcLang="{}" trgLang="{}">"#,
+                    src, trg
+                )?;
+                writeln!(pipe, r#"  <file id="{}">"#, escape(language))?;
+            }
+        }
+
+        for key in self.keys.iter() {
+            let source = match self.get(reference, key) {
+                Some(source) => escape(source),
+                None => continue,
+            };
+            let target = self.get(language, key).map(escape);
+
+            match version {
+                XliffVersion::V1_2 => {
+                    writeln!(
+                        pipe,
+                        r#"      <trans-unit id="{}" xml:space="preserve">"#,
+                        escape(key)
+                    )?;
+                    writeln!(pipe, "        <source>{}</source>", source)?;
+
+                    if let Some(target) = target {
+                        writeln!(
+                            pipe,
+                            r#"        <target state="translated">{}</target>"#,
+                            target
+                        )?;
+                    }
+
+                    writeln!(pipe, "      </trans-unit>")?;
+                }
+                XliffVersion::V2_0 => {
+                    let state = if target.is_some() {
+                        "translated"
+                    } else {
+                        "initial"
+                    };
+
+                    writeln!(
+                        pipe,
+                        r#"    <unit id="{}" xml:space="preserve">"#,
+                        escape(key)
+                    )?;
+                    writeln!(pipe, r#"      <segment state="{}">"#, state)?;
+                    writeln!(pipe, "        <source>{}</source>", source)?;
+
+                    if let Some(target) = target {
+                        writeln!(pipe, "        <target>{}</target>", target)?;
+                    }
+
+                    writeln!(pipe, "      </segment>")?;
+                    writeln!(pipe, "    </unit>")?;
+                }
+            }
+        }
+
+        if version == XliffVersion::V1_2 {
+            writeln!(pipe, "    </body>")?;
+        }
+
+        writeln!(pipe, "  </file>")?;
+        writeln!(pipe, "</xliff>")?;
+
+        Ok(())
+    }
+
+    /// Reads an XLIFF 1.2 or 2.0 file and merges its translations into
+    /// `language`.
+    pub fn import_xliff<R: Read>(
+        &mut self,
+        mut pipe: R,
+        reference: &str,
+        language: &str,
+        options: MergeOptions,
+    ) -> Result<MergeReport> {
+        let mut buf = String::new();
+        pipe.read_to_string(&mut buf)?;
+        let units = parse_xliff(&buf)?;
+        Ok(self.merge(reference, language, units, options))
+    }
+}
+
+/// Parses an XLIFF 1.2 or 2.0 file.
+///
+/// In XLIFF 1.2, targets with a state of `new` or one starting with `needs-`
+/// are fuzzy. In XLIFF 2.0, targets of segments in the `initial` state are,
+/// which is also the state of segments that don't specify one.
+/// Units are read by their `resname` if they have one, and by their ID
+/// otherwise.
+pub fn parse_xliff(s: &str) -> Result<Vec<TranslationUnit>> {
+    let doc = Document::parse(s)?;
+    let root = doc.root_element();
+
+    if root.tag_name().name() != "xliff" {
+        return Err(Error::Xliff("root element isn't <xliff>".to_string()));
+    }
+
+    let version = match root.attribute("version") {
+        Some(v) if v.starts_with("1.") => XliffVersion::V1_2,
+        Some(v) if v.starts_with("2.") => XliffVersion::V2_0,
+        Some(v) => return Err(Error::Xliff(format!("unsupported version '{}'", v))),
+        None => return Err(Error::Xliff("missing version".to_string())),
+    };
+
+    let unit_tag = match version {
+        XliffVersion::V1_2 => "trans-unit",
+        XliffVersion::V2_0 => "unit",
+    };
+
+    let mut units = Vec::new();
+
+    for node in root.descendants().filter(|n| n.has_tag_name(unit_tag)) {
+        let key = match node.attribute("resname").or_else(|| node.attribute("id")) {
+            Some(key) => key.to_string(),
+            None => return Err(Error::Xliff(format!("<{}> without an ID", unit_tag))),
+        };
+
+        let (source, target, fuzzy) = match version {
+            XliffVersion::V1_2 => {
+                let target = child(node, "target");
+                let state = target.and_then(|t| t.attribute("state")).unwrap_or("");
+                let fuzzy = state == "new" || state.starts_with("needs-");
+                (
+                    child(node, "source").map(text).unwrap_or_default(),
+                    target.map(text).unwrap_or_default(),
+                    fuzzy,
+                )
+            }
+            XliffVersion::V2_0 => {
+                // a unit can be split into several segments
+                let mut source = String::new();
+                let mut target = String::new();
+                let mut fuzzy = false;
+
+                for part in node.children().filter(Node::is_element) {
+                    match part.tag_name().name() {
+                        "segment" | "ignorable" => {
+                            // ignorables have no state
+                            if part.has_tag_name("segment") {
+                                fuzzy |= part.attribute("state").unwrap_or("initial") == "initial";
+                            }
+                            source.push_str(&child(part, "source").map(text).unwrap_or_default());
+                            target.push_str(&child(part, "target").map(text).unwrap_or_default());
+                        }
+                        _ => {}
+                    }
+                }
+
+                (source, target, fuzzy)
+            }
+        };
+
+        units.push(TranslationUnit {
+            key,
+            source,
+            target,
+            fuzzy,
+            obsolete: false,
+        });
+    }
+
+    Ok(units)
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+/// Returns all text in `node`, including the text in inline elements.
+fn text(node: Node) -> String {
+    node.descendants()
+        .filter(Node::is_text)
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+fn escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(['&', '<', '>', '"']) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut out = String::with_capacity(s.len() + 8);
+
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+
+    Cow::Owned(out)
+}
+
+#[test]
+fn xliff_round_trip() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "Stone & <b>");
+    lt.insert("en_us", "b", " Two\nlines ");
+    lt.insert("de_de", "a", "Stein & <b>");
+
+    for &version in [XliffVersion::V1_2, XliffVersion::V2_0].iter() {
+        let mut buf = Vec::new();
+        lt.export_xliff(&mut buf, "en_us", "de_de", version)
+            .unwrap();
+        let xliff = String::from_utf8(buf).unwrap();
+        assert!(xliff.contains("Stein &amp; &lt;b&gt;"));
+        assert!(xliff.contains("\"de-DE\""));
+
+        let units = parse_xliff(&xliff).unwrap();
+        assert_eq!(2, units.len());
+        assert_eq!("Stone & <b>", units[0].source);
+        assert_eq!("Stein & <b>", units[0].target);
+        assert_eq!(" Two\nlines ", units[1].source);
+        assert_eq!("", units[1].target);
+        assert_eq!(version == XliffVersion::V2_0, units[1].fuzzy);
+    }
+}
+
+#[test]
+fn xliff_import() {
+    let mut lt = LanguageTable::new();
+    lt.insert("en_us", "a", "Stone");
+    lt.insert("en_us", "b", "Dirt");
+
+    let xliff = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file original="de_de" source-language="en-US" target-language="de-DE" datatype="plaintext">
+    <body>
+      <trans-unit id="1" resname="a">
+        <source>Stone</source>
+        <target state="final">St<g id="x">ei</g>n</target>
+      </trans-unit>
+      <trans-unit id="b">
+        <source>Dirt</source>
+        <target state="needs-review-translation">Dreck</target>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>"#;
+
+    let report = lt
+        .import_xliff(xliff.as_bytes(), "en_us", "de_de", MergeOptions::default())
+        .unwrap();
+    assert_eq!(1, report.updated.len());
+    assert_eq!(1, report.fuzzy.len());
+    assert_eq!(Some("Stein"), lt.get("de_de", "a"));
+    assert_eq!(None, lt.get("de_de", "b"));
+
+    let xliff = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="2.0" xmlns="urn:oasis:names:tc:xliff:document:2.0" srcLang="en-US" trgLang="de-DE">
+  <file id="de_de">
+    <unit id="a">
+      <segment state="reviewed"><source>Stone</source><target>Stein</target></segment>
+      <ignorable><source> </source><target> </target></ignorable>
+    </unit>
+    <unit id="b">
+      <segment><source>Dirt</source><target>Dreck</target></segment>
+    </unit>
+  </file>
+</xliff>"#;
+
+    let units = parse_xliff(xliff).unwrap();
+    assert_eq!("Stein ", units[0].target);
+    assert!(!units[0].fuzzy);
+    assert!(units[1].fuzzy);
+
+    assert!(matches!(parse_xliff("<xliff>"), Err(Error::Xml(_))));
+    assert!(matches!(
+        parse_xliff(r#"<xliff version="3.0"/>"#),
+        Err(Error::Xliff(_))
+    ));
+}
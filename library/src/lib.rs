@@ -1,4 +1,4 @@
-#![feature(str_split_once)]
+#![forbid(unstable_features)]
 
 use std::ffi::OsStr;
 use std::path::Path;
@@ -22,7 +22,7 @@ pub fn get_file_type<P: AsRef<Path>>(ds: &DataSource, path: P) -> Option<FileTyp
     // shitty detection for now
     let path = path.as_ref();
     if ds.is_file(path)
-        && has_extension(path, "json")
+        && (has_extension(path, "json") || has_extension(path, "lang"))
         && path.parent().and_then(|p| get_file_type(ds, p)) == Some(FileType::Language)
     {
         Some(FileType::LanguagePart)
@@ -36,11 +36,11 @@ pub fn get_file_type<P: AsRef<Path>>(ds: &DataSource, path: P) -> Option<FileTyp
 fn has_extension<P: AsRef<Path>, S: AsRef<OsStr>>(path: P, ext: S) -> bool {
     path.as_ref()
         .extension()
-        .map_or(false, |s| s == ext.as_ref())
+        .is_some_and(|s| s == ext.as_ref())
 }
 
 fn has_file_name<P: AsRef<Path>, S: AsRef<OsStr>>(path: P, name: S) -> bool {
     path.as_ref()
         .file_name()
-        .map_or(false, |s| s == name.as_ref())
+        .is_some_and(|s| s == name.as_ref())
 }
@@ -92,7 +92,7 @@ impl FsTree {
         {
             let mut ref_mut = root.borrow_mut();
             ref_mut.open()?;
-            ref_mut.root().borrow_mut().root = Rc::downgrade(&root);
+            ref_mut.root().borrow_mut().root = Rc::downgrade(root);
             let root = ref_mut.root().clone();
             drop(ref_mut);
             self.refresh(&root);
@@ -147,11 +147,11 @@ impl FsTree {
         }
     }
 
-    pub fn dispatcher(&self) -> Ref<TreeChangeDispatcher> {
+    pub fn dispatcher(&self) -> Ref<'_, TreeChangeDispatcher> {
         self.dispatcher.borrow()
     }
 
-    pub fn dispatcher_mut(&self) -> RefMut<TreeChangeDispatcher> {
+    pub fn dispatcher_mut(&self) -> RefMut<'_, TreeChangeDispatcher> {
         self.dispatcher.borrow_mut()
     }
 
@@ -257,13 +257,13 @@ impl FsTree {
                                 drop(ch);
 
                                 drop(e);
-                                d.pre_remove(&path_buf, i, i);
+                                d.pre_remove(path_buf, i, i);
                                 e = entry.borrow_mut();
 
                                 e.children.remove(i);
 
                                 drop(e);
-                                d.post_remove(&path_buf);
+                                d.post_remove(path_buf);
                                 e = entry.borrow_mut();
                             }
                             Ordering::Equal => {
@@ -281,7 +281,7 @@ impl FsTree {
                         let root = e.root.clone();
 
                         drop(e);
-                        d.pre_insert(&path_buf, i, i);
+                        d.pre_insert(path_buf, i, i);
                         e = entry.borrow_mut();
 
                         e.children.insert(
@@ -290,7 +290,7 @@ impl FsTree {
                         );
 
                         drop(e);
-                        d.post_insert(&path_buf);
+                        d.post_insert(path_buf);
                         e = entry.borrow_mut();
                     }
                 }
@@ -299,13 +299,13 @@ impl FsTree {
 
                 if len > 0 {
                     drop(e);
-                    d.pre_remove(&path_buf, 0, len - 1);
+                    d.pre_remove(path_buf, 0, len - 1);
                     e = entry.borrow_mut();
 
                     e.children.clear();
 
                     drop(e);
-                    d.post_remove(&path_buf);
+                    d.post_remove(path_buf);
                     e = entry.borrow_mut();
                 }
             }
@@ -336,7 +336,7 @@ impl<'de> BinDeserialize<'de> for FsTree {
         self.reset();
 
         self.roots = try_iter(VecLikeIter::new(deserializer)?, |iter| {
-            iter.map(|el| Rc::new(el)).collect()
+            iter.map(Rc::new).collect()
         })?;
 
         Ok(())
@@ -350,27 +350,16 @@ impl BinSerialize for FsTree {
     }
 }
 
-#[derive(Debug, BinSerialize)]
+#[derive(Debug, BinSerialize, BinDeserialize)]
 pub struct FsTreeRoot {
     name: String,
     ds_proto: DataSourceProto,
     #[binserde(skip)]
     data: Option<OpenFsTreeRoot>,
-    #[binserde(skip)]
+    #[binserde(skip, default = "FsTreeEntry::new_top_level_rc")]
     root: Rc<RefCell<FsTreeEntry>>,
 }
 
-impl<'de> BinDeserialize<'de> for FsTreeRoot {
-    fn deserialize<D: BinDeserializer<'de>>(mut deserializer: D) -> binserde::Result<Self> {
-        Ok(FsTreeRoot {
-            name: String::deserialize(&mut deserializer)?,
-            ds_proto: DataSourceProto::deserialize(&mut deserializer)?,
-            data: None,
-            root: Rc::new(RefCell::new(Default::default())),
-        })
-    }
-}
-
 #[derive(Debug, BinSerialize, BinDeserialize)]
 pub enum DataSourceProto {
     Dir(PathBuf),
@@ -397,7 +386,7 @@ impl FsTreeRoot {
             name: name.into(),
             ds_proto,
             data: None,
-            root: Rc::new(RefCell::new(FsTreeEntry::new_top_level())),
+            root: FsTreeEntry::new_top_level_rc(),
         }));
 
         fst.borrow_mut().root().borrow_mut().root = Rc::downgrade(&fst);
@@ -476,6 +465,10 @@ impl FsTreeEntry {
         }
     }
 
+    fn new_top_level_rc() -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(FsTreeEntry::new_top_level()))
+    }
+
     fn new<P: Into<PathBuf>>(
         path: P,
         parent: Rc<RefCell<FsTreeEntry>>,
@@ -492,7 +485,7 @@ impl FsTreeEntry {
         }
     }
 
-    pub fn display_name(&self) -> Cow<str> {
+    pub fn display_name(&self) -> Cow<'_, str> {
         if self.is_top_level {
             match self.root.upgrade() {
                 None => {
@@ -537,8 +530,3 @@ impl FsTreeEntry {
     }
 }
 
-impl Default for FsTreeEntry {
-    fn default() -> Self {
-        FsTreeEntry::new_top_level()
-    }
-}
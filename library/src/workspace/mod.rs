@@ -9,7 +9,7 @@ use binserde::{BinDeserialize, BinSerialize};
 use fstree::FsTree;
 pub use fstree::{DataSourceProto, FsTreeEntry, FsTreeRoot};
 
-
+#[cfg(feature = "cpp")]
 use crate::ffi;
 #[cfg(feature = "cpp")]
 use crate::ffi::TreeChangeSubscriber as CppTreeChangeSubscriber;
@@ -27,6 +27,12 @@ pub struct Workspace {
     gd: GameData,
 }
 
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Workspace {
     pub fn new() -> Self {
         Workspace {
@@ -37,36 +43,57 @@ impl Workspace {
 
     pub fn add_dir<P: Into<PathBuf>>(&mut self, path: P) -> matryoshka::Result<()> {
         self.fst.add_dir(path)?;
-        self.update_refs();
+        self.refresh();
         Ok(())
     }
 
     pub fn add_zip<P: Into<PathBuf>>(&mut self, path: P) -> matryoshka::Result<()> {
         self.fst.add_zip(path)?;
-        self.update_refs();
+        self.refresh();
         Ok(())
     }
 
-    pub fn update_refs(&mut self) {
+    /// Collects the references in the file tree and creates the game objects
+    /// they need.
+    ///
+    /// Fails if the stored game objects can't be decoded. They are kept as
+    /// they were in that case, and saving the workspace fails with the same
+    /// error.
+    pub fn update_refs(&mut self) -> binserde::Result<()> {
         self.gd.collect_usages(self.fst.roots());
-        self.gd.create_dummies();
+        self.gd.create_dummies()?;
+
+        // create_dummies decoded both registries, so these can't fail
+        if let Ok(blocks) = self.gd.blocks() {
+            let ids = self.gd.ids();
+            let mut blocks: Vec<_> = blocks.ids().filter_map(|id| ids.resolve(id)).collect();
+            blocks.sort();
+            print!("Blocks: ");
+            blocks.iter().for_each(|id| print!("{} ", id));
+            println!();
+        }
 
-        let mut blocks: Vec<_> = self.gd.blocks().ids().collect();
-        blocks.sort();
-        print!("Blocks: ");
-        blocks.iter().for_each(|id| print!("{} ", id));
-        println!();
+        if let Ok(items) = self.gd.items() {
+            let ids = self.gd.ids();
+            let mut items: Vec<_> = items.ids().filter_map(|id| ids.resolve(id)).collect();
+            items.sort();
+            print!("Items: ");
+            items.iter().for_each(|id| print!("{} ", id));
+            println!();
+        }
 
-        let mut items: Vec<_> = self.gd.items().ids().collect();
-        items.sort();
-        print!("Items: ");
-        items.iter().for_each(|id| print!("{} ", id));
-        println!();
+        Ok(())
+    }
+
+    fn refresh(&mut self) {
+        if let Err(e) = self.update_refs() {
+            eprintln!("warning: could not update references: {}", e);
+        }
     }
 
     pub fn detach(&mut self, root: &Rc<RefCell<FsTreeRoot>>) {
         self.fst.detach(root);
-        self.update_refs();
+        self.refresh();
     }
 
     pub fn roots(&self) -> &[Rc<RefCell<FsTreeRoot>>] {
@@ -81,26 +108,26 @@ impl Workspace {
         &self.gd
     }
 
-    pub fn fst_dispatcher(&self) -> Ref<TreeChangeDispatcher> {
+    pub fn fst_dispatcher(&self) -> Ref<'_, TreeChangeDispatcher> {
         self.fst.dispatcher()
     }
 
-    pub fn fst_dispatcher_mut(&self) -> RefMut<TreeChangeDispatcher> {
+    pub fn fst_dispatcher_mut(&self) -> RefMut<'_, TreeChangeDispatcher> {
         self.fst.dispatcher_mut()
     }
 
-    pub fn gd_dispatcher(&self) -> Ref<TreeChangeDispatcher> {
+    pub fn gd_dispatcher(&self) -> Ref<'_, TreeChangeDispatcher> {
         self.gd.dispatcher()
     }
 
-    pub fn gd_dispatcher_mut(&self) -> RefMut<TreeChangeDispatcher> {
+    pub fn gd_dispatcher_mut(&self) -> RefMut<'_, TreeChangeDispatcher> {
         self.gd.dispatcher_mut()
     }
 
     pub fn reset(&mut self) {
         self.gd.reset();
         self.fst.reset();
-        self.update_refs();
+        self.refresh();
     }
 }
 
@@ -1,26 +1,35 @@
 
 use std::io;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, Write};
 use std::num::TryFromIntError;
 
 use byteorder::{ReadBytesExt, WriteBytesExt, BE, LE};
 
-use binserde::Mode;
+use binserde::{Limits, Mode};
 
 use crate::workspace::{Error, Workspace};
 
 pub const MAGIC: u16 = 0x3B1C;
-pub const VERSION: u16 = 1;
-pub const MIN_VERSION: u16 = 1;
+/// Version 2 stores the game data as lazily decoded values. Version 1 files
+/// can't be read anymore, since the layout of game objects changed since.
+pub const VERSION: u16 = 2;
+pub const MIN_VERSION: u16 = 2;
+
+/// Workspace files may come from anywhere, so don't let them make us allocate
+/// or recurse without bound.
+pub const LIMITS: Limits = Limits::untrusted();
 
 impl Workspace {
-    pub fn read_from<R: Read>(pipe: R) -> Result<Self> {
+    /// Reads a workspace from `pipe`. The game data is only decoded once it
+    /// is first used, so `pipe` is kept open for as long as the workspace
+    /// is, and the file it reads from must not be changed in the meantime.
+    pub fn read_from<R: Read + Seek + 'static>(pipe: R) -> Result<Self> {
         let mut ws = Workspace::new();
         ws.read_from_in_place(pipe)?;
         Ok(ws)
     }
 
-    pub fn read_from_in_place<R: Read>(&mut self, mut pipe: R) -> Result<()> {
+    pub fn read_from_in_place<R: Read + Seek + 'static>(&mut self, mut pipe: R) -> Result<()> {
         let magic = pipe.read_u16::<BE>()?;
         if magic != MAGIC {
             return Err(Error::MagicError(magic));
@@ -33,7 +42,7 @@ impl Workspace {
 
         self.reset();
 
-        binserde::deserialize_in_place(self, pipe, Mode::dedup())?;
+        binserde::lazy::deserialize_in_place_from(self, pipe, Mode::dedup().with_limits(LIMITS))?;
 
         Ok(())
     }
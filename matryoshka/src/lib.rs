@@ -1,3 +1,5 @@
+#![forbid(unstable_features)]
+
 use std::ffi::OsStr;
 use std::io::Cursor;
 use std::path::{Component, Path, PathBuf};
@@ -37,11 +39,10 @@ impl DataSource {
                     Err(Error::PermissionDenied)
                 } else {
                     let path = path.as_ref();
-                    let result: Result<Vec<u8>, Error> =
-                        ds.open(path).map_err(|e| e.into()).map_err(|e| match e {
-                            Error::NotFound if opts.create => Error::ReadOnly(path.to_path_buf()),
-                            x => x,
-                        });
+                    let result: Result<Vec<u8>, Error> = ds.open(path).map_err(|e| match e {
+                        Error::NotFound if opts.create => Error::ReadOnly(path.to_path_buf()),
+                        x => x,
+                    });
                     Ok(ResFile::ZipEntry(Cursor::new(result?)))
                 }
             }
@@ -219,14 +220,14 @@ impl OpenOptions {
     }
 }
 
-impl Into<fs::OpenOptions> for OpenOptions {
-    fn into(self) -> fs::OpenOptions {
+impl From<OpenOptions> for fs::OpenOptions {
+    fn from(opts: OpenOptions) -> Self {
         let mut options = fs::OpenOptions::new();
-        options.read(self.read);
-        options.write(self.write);
-        options.create(self.create);
-        options.append(self.write && self.append);
-        options.truncate(self.write && !self.append);
+        options.read(opts.read);
+        options.write(opts.write);
+        options.create(opts.create);
+        options.append(opts.write && opts.append);
+        options.truncate(opts.write && !opts.append);
         options
     }
 }
@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io;
-use std::io::{Cursor, ErrorKind, IoSlice, IoSliceMut, Read, Seek, SeekFrom, Write};
+use std::io::{Cursor, IoSlice, IoSliceMut, Read, Seek, SeekFrom, Write};
 
 #[derive(Debug)]
 pub enum ResFile {
@@ -35,21 +35,21 @@ impl Write for ResFile {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         match self {
             ResFile::File(inner) => inner.write(buf),
-            ResFile::ZipEntry(_) => Err(io::Error::new(ErrorKind::Other, "unsupported write")),
+            ResFile::ZipEntry(_) => Err(io::Error::other("unsupported write")),
         }
     }
 
     fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
         match self {
             ResFile::File(inner) => inner.write_vectored(bufs),
-            ResFile::ZipEntry(_) => Err(io::Error::new(ErrorKind::Other, "unsupported write")),
+            ResFile::ZipEntry(_) => Err(io::Error::other("unsupported write")),
         }
     }
 
     fn flush(&mut self) -> io::Result<()> {
         match self {
             ResFile::File(inner) => inner.flush(),
-            ResFile::ZipEntry(_) => Err(io::Error::new(ErrorKind::Other, "unsupported write")),
+            ResFile::ZipEntry(_) => Err(io::Error::other("unsupported write")),
         }
     }
 }
@@ -62,15 +62,6 @@ impl Seek for ResFile {
         }
     }
 
-    #[cfg(feature = "seek_convenience")]
-    fn stream_len(&mut self) -> io::Result<u64> {
-        match self {
-            ResFile::File(inner) => inner.stream_len(),
-            ResFile::ZipEntry(inner) => inner.stream_len(),
-        }
-    }
-
-    #[cfg(feature = "seek_convenience")]
     fn stream_position(&mut self) -> io::Result<u64> {
         match self {
             ResFile::File(inner) => inner.stream_position(),
@@ -7,7 +7,7 @@ use std::path::{Component, Path, PathBuf};
 
 use zip::ZipArchive;
 
-use crate::{normalize_path, DirEntry, FileInfo, Result, Error};
+use crate::{normalize_path, DirEntry, Error, FileInfo, Result};
 
 #[derive(Debug)]
 pub struct DataSource {
@@ -109,9 +109,7 @@ impl DataSource {
 
                     if cd
                         .children
-                        .binary_search_by(|a| {
-                            (OsStr::new(&a.path.file_name().unwrap())).cmp(&file_name)
-                        })
+                        .binary_search_by(|a| a.path.file_name().unwrap().cmp(file_name))
                         .is_ok()
                     {
                         Ok(FileInfo {
@@ -122,7 +120,7 @@ impl DataSource {
                         })
                     } else if cd
                         .files
-                        .binary_search_by(|a| (OsStr::new(&a)).cmp(&file_name))
+                        .binary_search_by(|a| OsStr::new(a).cmp(file_name))
                         .is_ok()
                     {
                         Ok(FileInfo {
@@ -206,7 +204,7 @@ impl DirTree {
             return;
         }
 
-        if let Err(idx) = self.files.binary_search_by(|a| (&**a).cmp(file)) {
+        if let Err(idx) = self.files.binary_search_by(|a| (**a).cmp(file)) {
             self.files.insert(idx, file.to_string());
         }
     }
@@ -219,7 +217,7 @@ impl DirTree {
     }
 
     fn subdir_or_create(&mut self, dir: &str) -> &mut DirTree {
-        if let Ok(idx) = self.files.binary_search_by(|a| (&**a).cmp(dir)) {
+        if let Ok(idx) = self.files.binary_search_by(|a| (**a).cmp(dir)) {
             self.files.remove(idx);
         }
 
@@ -245,8 +243,6 @@ impl DirTree {
                 Component::RootDir => true,
                 _ => false,
             })
-            .fold(Some(self), |acc, a| {
-                acc.and_then(|acc| acc.subdir(a.as_os_str().to_str().unwrap()))
-            })
+            .try_fold(self, |acc, a| acc.subdir(a.as_os_str().to_str().unwrap()))
     }
 }
@@ -4,8 +4,10 @@ use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
+use std::str::FromStr;
 
 use binserde::{BinDeserialize, BinSerialize, BinSerializer};
+use thiserror::Error;
 
 #[derive(Debug, Clone, Eq, BinDeserialize)]
 pub struct Identifier {
@@ -13,10 +15,52 @@ pub struct Identifier {
 }
 
 impl Identifier {
+    /// Parses `s` as an identifier, checking that it only contains characters
+    /// the game allows. `s` may omit the namespace, in which case it is
+    /// `minecraft`.
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        Ident::new(s).validate()?;
+        Ok(Identifier::from(s.to_string()))
+    }
+
+    /// Like [`Identifier::parse`], but always returns an identifier, together
+    /// with the characters that are not allowed in it. Only an empty path
+    /// isn't reported.
+    pub fn parse_lenient(s: &str) -> (Self, Vec<InvalidChar>) {
+        let invalid = Ident::new(s).invalid_chars().collect();
+        (Identifier::from(s.to_string()), invalid)
+    }
+
+    /// Creates an identifier from its components without validating them.
+    /// Use [`Identifier::try_from_components`] for untrusted input.
     pub fn from_components(namespace: &str, path: &str) -> Self {
         Identifier::from(format!("{}:{}", namespace, path))
     }
 
+    /// Creates an identifier from its components, checking that they only
+    /// contain characters the game allows.
+    pub fn try_from_components(namespace: &str, path: &str) -> Result<Self, ParseError> {
+        let id = Identifier::from_components(namespace, path);
+
+        // validate the components separately so that a ':' in the namespace
+        // is reported as invalid instead of moving the separator
+        if namespace.is_empty() {
+            return Err(ParseError::EmptyNamespace);
+        }
+
+        let invalid: Vec<_> = invalid_chars(namespace, 0, false)
+            .chain(invalid_chars(path, namespace.len() + 1, true))
+            .collect();
+
+        if !invalid.is_empty() {
+            Err(ParseError::InvalidChars(invalid))
+        } else if path.is_empty() {
+            Err(ParseError::EmptyPath)
+        } else {
+            Ok(id)
+        }
+    }
+
     /// Sets the namespace of this `Identifier`, or removes it if `namespace` is
     /// `None`.
     pub fn set_namespace(&mut self, namespace: Option<&str>) {
@@ -59,6 +103,14 @@ impl Identifier {
     }
 }
 
+impl FromStr for Identifier {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Identifier::parse(s)
+    }
+}
+
 impl From<String> for Identifier {
     fn from(s: String) -> Self {
         Identifier { inner: s }
@@ -111,7 +163,7 @@ impl Ord for Identifier {
 
 impl PartialOrd for Identifier {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.as_ident().partial_cmp(other.as_ident())
+        Some(self.cmp(other))
     }
 }
 
@@ -135,6 +187,8 @@ impl Ident {
         unsafe { &*(s.as_ref() as *const str as *const Ident) }
     }
 
+    /// Returns the namespace of this identifier, which is `minecraft` if it
+    /// doesn't have one. Use [`Ident::namespace_raw`] to tell these apart.
     pub fn namespace(&self) -> &str {
         self.namespace_raw().unwrap_or("minecraft")
     }
@@ -162,6 +216,36 @@ impl Ident {
         Identifier::from(self.inner.to_string())
     }
 
+    /// Checks that this identifier is one the game would accept: the
+    /// namespace may only contain `[a-z0-9_.-]`, the path additionally `/`,
+    /// and neither may be empty.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        let invalid: Vec<_> = self.invalid_chars().collect();
+
+        if !invalid.is_empty() {
+            Err(ParseError::InvalidChars(invalid))
+        } else if self.namespace_raw() == Some("") {
+            Err(ParseError::EmptyNamespace)
+        } else if self.path().is_empty() {
+            Err(ParseError::EmptyPath)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    fn invalid_chars(&self) -> impl Iterator<Item = InvalidChar> + '_ {
+        let (namespace, path_start) = match self.get_seperator() {
+            None => ("", 0),
+            Some(idx) => (&self.inner[..idx], idx + 1),
+        };
+
+        invalid_chars(namespace, 0, false).chain(invalid_chars(self.path(), path_start, true))
+    }
+
     fn get_seperator(&self) -> Option<usize> {
         self.inner.find(':')
     }
@@ -192,7 +276,7 @@ impl Ord for Ident {
     fn cmp(&self, other: &Self) -> Ordering {
         match self.namespace().cmp(other.namespace()) {
             Ordering::Equal => self.path().cmp(other.path()),
-            x @ _ => x,
+            x => x,
         }
     }
 }
@@ -214,3 +298,105 @@ impl BinSerialize for Ident {
         self.trim().as_str().serialize(serializer)
     }
 }
+
+/// A character that is not allowed in an identifier, and its byte offset in
+/// the identifier.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct InvalidChar {
+    pub pos: usize,
+    pub ch: char,
+}
+
+impl Display for InvalidChar {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:?} at {}", self.ch, self.pos)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum ParseError {
+    #[error("empty namespace")]
+    EmptyNamespace,
+    #[error("empty path")]
+    EmptyPath,
+    #[error("invalid characters in identifier: {}", list(.0))]
+    InvalidChars(Vec<InvalidChar>),
+}
+
+fn list(chars: &[InvalidChar]) -> String {
+    chars
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub(crate) fn is_valid_char(ch: char, is_path: bool) -> bool {
+    matches!(ch, 'a'..='z' | '0'..='9' | '_' | '.' | '-') || (is_path && ch == '/')
+}
+
+pub(crate) fn invalid_chars_by<F>(
+    s: &str,
+    offset: usize,
+    is_valid: F,
+) -> impl Iterator<Item = InvalidChar> + '_
+where
+    F: Fn(char) -> bool + 'static,
+{
+    s.char_indices()
+        .filter(move |&(_, ch)| !is_valid(ch))
+        .map(move |(pos, ch)| InvalidChar {
+            pos: offset + pos,
+            ch,
+        })
+}
+
+fn invalid_chars(s: &str, offset: usize, is_path: bool) -> impl Iterator<Item = InvalidChar> + '_ {
+    invalid_chars_by(s, offset, move |ch| is_valid_char(ch, is_path))
+}
+
+#[test]
+fn parse_identifier() {
+    let id = Identifier::parse("minecraft:stone").unwrap();
+    assert_eq!(Some("minecraft"), id.namespace_raw());
+    assert_eq!("stone", id.path());
+
+    let id: Identifier = "textures/block/dirt.png".parse().unwrap();
+    assert_eq!(None, id.namespace_raw());
+    assert_eq!("minecraft", id.namespace());
+
+    assert_eq!(Err(ParseError::EmptyPath), Identifier::parse("a:"));
+    assert_eq!(Err(ParseError::EmptyNamespace), Identifier::parse(":a"));
+    assert_eq!(
+        Err(ParseError::InvalidChars(vec![
+            InvalidChar { pos: 0, ch: 'F' },
+            InvalidChar { pos: 3, ch: ' ' },
+            InvalidChar { pos: 9, ch: ':' },
+        ])),
+        Identifier::parse("Foo bar:x:y")
+    );
+    assert_eq!(
+        Err(ParseError::InvalidChars(vec![InvalidChar {
+            pos: 1,
+            ch: '/'
+        }])),
+        Identifier::parse("a/b:c")
+    );
+
+    let (id, invalid) = Identifier::parse_lenient("Stone");
+    assert_eq!("Stone", id.as_str());
+    assert_eq!(vec![InvalidChar { pos: 0, ch: 'S' }], invalid);
+    assert_eq!(
+        "invalid characters in identifier: 'S' at 0",
+        Identifier::parse("Stone").unwrap_err().to_string()
+    );
+
+    assert!(Identifier::try_from_components("mymod", "ore/copper").is_ok());
+    assert_eq!(
+        Err(ParseError::InvalidChars(vec![InvalidChar {
+            pos: 1,
+            ch: ':'
+        }])),
+        Identifier::try_from_components("a:b", "c")
+    );
+}
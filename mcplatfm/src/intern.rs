@@ -0,0 +1,182 @@
+//! Interned identifiers.
+//!
+//! An [`Interner`] stores each distinct identifier only once and hands out
+//! [`IdentId`] handles for them. Comparing two handles for equality and
+//! hashing one only looks at a number, and creating a handle for an
+//! identifier that was already seen doesn't allocate.
+//!
+//! Identifiers are owned by the interner and freed together with it, so an
+//! interner should be scoped to the data its handles are used in, such as a
+//! workspace. A handle is only meaningful to the interner that created it.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use binserde::{BinDeserialize, BinDeserializer, BinSerialize, BinSerializer};
+
+use crate::{Ident, Identifier};
+
+/// A handle to an identifier in an [`Interner`].
+///
+/// Identifiers with and without the `minecraft` namespace written out are
+/// the same identifier, and get the same handle.
+///
+/// Handles are serialized as their position in the interner, which is
+/// serialized separately and writes the identifiers through the
+/// deduplication table like strings. Deserializing a handle doesn't check
+/// that the position exists; [`Interner::resolve`] returning `None` is the
+/// only indication of a handle that doesn't belong to the interner.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct IdentId(u32);
+
+/// A table of identifiers, each stored once and referred to by an
+/// [`IdentId`].
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    idents: Vec<Rc<Identifier>>,
+    ids: HashMap<Key, IdentId>,
+}
+
+/// A key of the lookup map in an [`Interner`], sharing the identifier with
+/// the interner's list so that it is only allocated once.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct Key(Rc<Identifier>);
+
+impl Borrow<Ident> for Key {
+    fn borrow(&self) -> &Ident {
+        self.0.as_ident()
+    }
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// Returns the handle for `id`, adding it to the table if it isn't in
+    /// there yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table already holds `u32::MAX` identifiers.
+    pub fn intern(&mut self, id: &Ident) -> IdentId {
+        if let Some(handle) = self.get(id) {
+            return handle;
+        }
+
+        let handle = IdentId(u32::try_from(self.idents.len()).expect("too many identifiers"));
+        let id = Rc::new(id.to_identifier());
+        self.idents.push(id.clone());
+        self.ids.insert(Key(id), handle);
+        handle
+    }
+
+    /// Returns the handle for `id` if it has been interned before.
+    pub fn get(&self, id: &Ident) -> Option<IdentId> {
+        self.ids.get(id).copied()
+    }
+
+    /// Returns the identifier `id` refers to, or `None` if it doesn't come
+    /// from this interner.
+    pub fn resolve(&self, id: IdentId) -> Option<&Ident> {
+        self.idents.get(id.0 as usize).map(|id| id.as_ident())
+    }
+
+    pub fn len(&self) -> usize {
+        self.idents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.idents.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.idents.clear();
+        self.ids.clear();
+    }
+}
+
+impl BinSerialize for IdentId {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> binserde::Result<()> {
+        (self.0 as usize).serialize(serializer)
+    }
+}
+
+impl<'de> BinDeserialize<'de> for IdentId {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> binserde::Result<Self> {
+        Ok(IdentId(u32::try_from(usize::deserialize(deserializer)?)?))
+    }
+}
+
+impl BinSerialize for Interner {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> binserde::Result<()> {
+        let idents: Vec<&Identifier> = self.idents.iter().map(|id| &**id).collect();
+        idents.serialize(serializer)
+    }
+}
+
+impl<'de> BinDeserialize<'de> for Interner {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> binserde::Result<Self> {
+        let idents = Vec::<Identifier>::deserialize(deserializer)?;
+        let mut interner = Interner::new();
+
+        for id in idents {
+            if let Err(e) = id.validate() {
+                return Err(binserde::Error::custom(format!(
+                    "invalid identifier '{}': {}",
+                    id, e
+                )));
+            }
+
+            let len = interner.len();
+            interner.intern(&id);
+
+            if interner.len() == len {
+                return Err(binserde::Error::custom(format!(
+                    "duplicate identifier '{}'",
+                    id
+                )));
+            }
+        }
+
+        Ok(interner)
+    }
+}
+
+#[test]
+fn intern_ident() {
+    use binserde::Mode;
+
+    let mut interner = Interner::new();
+    let stone = interner.intern(Ident::new("stone"));
+    let stone2 = interner.intern(&Identifier::from_components("minecraft", "stone"));
+    let dirt = interner.intern(Ident::new("minecraft:dirt"));
+
+    assert_eq!(stone, stone2);
+    assert_ne!(stone, dirt);
+    assert_eq!(2, interner.len());
+    assert_eq!(Some("stone"), interner.resolve(stone).map(|id| id.as_str()));
+    assert_eq!(Some(stone), interner.get(Ident::new("minecraft:stone")));
+    assert_eq!(None, interner.get(Ident::new("intern_test:never_seen")));
+    assert_eq!(None, Interner::new().resolve(stone));
+
+    let ids = vec![stone, dirt, stone, stone];
+    let buf = binserde::serialize_with(&(&interner, &ids), Mode::dedup()).unwrap();
+    let (read, read_ids): (Interner, Vec<IdentId>) =
+        binserde::deserialize_with(&buf, Mode::dedup()).unwrap();
+    assert_eq!(ids, read_ids);
+    assert_eq!(Some(stone), read.get(Ident::new("stone")));
+
+    let invalid = vec![Identifier::from("Stone".to_string())];
+    let buf = binserde::serialize(&invalid).unwrap();
+    assert!(binserde::deserialize::<Interner>(&buf).is_err());
+
+    let duplicate = vec![
+        Identifier::from("stone".to_string()),
+        Identifier::from("minecraft:stone".to_string()),
+    ];
+    let buf = binserde::serialize(&duplicate).unwrap();
+    assert!(binserde::deserialize::<Interner>(&buf).is_err());
+}
@@ -1,3 +1,15 @@
-pub use ident::{Ident, Identifier};
+#![forbid(unstable_features)]
+
+pub use ident::{Ident, Identifier, InvalidChar, ParseError};
+pub use intern::{IdentId, Interner};
+pub use pattern::IdentPattern;
+pub use platform::{GameVersion, Loader, Platform};
+pub use resource::{PackType, ResourceKind};
+pub use tag::{IdentOrTag, TagParseError, TagRef};
 
 pub mod ident;
+pub mod intern;
+pub mod pattern;
+pub mod platform;
+pub mod resource;
+pub mod tag;
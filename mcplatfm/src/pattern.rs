@@ -0,0 +1,161 @@
+//! Glob patterns matching identifiers, such as `mymod:*` or `*:*_ore`.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use binserde::{BinDeserialize, BinDeserializer, BinSerialize, BinSerializer};
+
+use crate::ident::{invalid_chars_by, is_valid_char, ParseError};
+use crate::Ident;
+
+/// A pattern matching identifiers.
+///
+/// The namespace and path are matched separately, so a wildcard never
+/// matches the `:` between them. In either, `*` matches any number of
+/// characters, including `/`, and `?` matches a single character. Like for
+/// identifiers, a pattern without a namespace only matches in the
+/// `minecraft` namespace: use `*:path` to match a path in any namespace.
+///
+/// This is serialized as its string form.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct IdentPattern {
+    namespace: String,
+    path: String,
+}
+
+impl IdentPattern {
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        let (namespace, path, path_start) = match s.find(':') {
+            None => ("minecraft", s, 0),
+            Some(idx) => (&s[..idx], &s[idx + 1..], idx + 1),
+        };
+
+        let invalid: Vec<_> = invalid_chars_by(namespace, 0, |ch| is_pattern_char(ch, false))
+            .chain(invalid_chars_by(path, path_start, |ch| {
+                is_pattern_char(ch, true)
+            }))
+            .collect();
+
+        if !invalid.is_empty() {
+            Err(ParseError::InvalidChars(invalid))
+        } else if namespace.is_empty() {
+            Err(ParseError::EmptyNamespace)
+        } else if path.is_empty() {
+            Err(ParseError::EmptyPath)
+        } else {
+            Ok(IdentPattern {
+                namespace: namespace.to_string(),
+                path: path.to_string(),
+            })
+        }
+    }
+
+    /// Returns whether this pattern doesn't contain any wildcards, and so
+    /// only matches a single identifier.
+    pub fn is_literal(&self) -> bool {
+        !self.namespace.contains(['*', '?']) && !self.path.contains(['*', '?'])
+    }
+
+    pub fn matches(&self, id: &Ident) -> bool {
+        glob_matches(&self.namespace, id.namespace()) && glob_matches(&self.path, id.path())
+    }
+}
+
+impl FromStr for IdentPattern {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IdentPattern::parse(s)
+    }
+}
+
+impl Display for IdentPattern {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.path)
+    }
+}
+
+impl BinSerialize for IdentPattern {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> binserde::Result<()> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl<'de> BinDeserialize<'de> for IdentPattern {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> binserde::Result<Self> {
+        let s = String::deserialize(deserializer)?;
+        IdentPattern::parse(&s).map_err(binserde::Error::custom)
+    }
+}
+
+fn is_pattern_char(ch: char, is_path: bool) -> bool {
+    ch == '*' || ch == '?' || is_valid_char(ch, is_path)
+}
+
+/// Matches `s` against the glob `pattern`, backtracking to the last `*` on a
+/// mismatch.
+fn glob_matches(pattern: &str, s: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let s: Vec<char> = s.chars().collect();
+
+    let mut p = 0;
+    let mut i = 0;
+    // the position of the last '*' in the pattern, and where in s it started
+    // matching
+    let mut star = None;
+
+    while i < s.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, i));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == s[i] => {
+                p += 1;
+                i += 1;
+            }
+            _ => match star {
+                Some((sp, si)) => {
+                    // let the '*' match one more character
+                    star = Some((sp, si + 1));
+                    p = sp + 1;
+                    i = si + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[test]
+fn ident_pattern() {
+    let any_ore: IdentPattern = "*:*_ore".parse().unwrap();
+    assert!(any_ore.matches(Ident::new("iron_ore")));
+    assert!(any_ore.matches(Ident::new("mymod:copper_ore")));
+    assert!(!any_ore.matches(Ident::new("mymod:ore_block")));
+    assert!(!any_ore.is_literal());
+
+    let mymod: IdentPattern = "mymod:*".parse().unwrap();
+    assert!(mymod.matches(Ident::new("mymod:block/gear")));
+    assert!(!mymod.matches(Ident::new("gear")));
+
+    let stone: IdentPattern = "stone".parse().unwrap();
+    assert!(stone.is_literal());
+    assert!(stone.matches(Ident::new("minecraft:stone")));
+    assert!(!stone.matches(Ident::new("mymod:stone")));
+    assert_eq!("minecraft:stone", stone.to_string());
+
+    let single: IdentPattern = "a?c*".parse().unwrap();
+    assert!(single.matches(Ident::new("abc")));
+    assert!(single.matches(Ident::new("abcdef")));
+    assert!(!single.matches(Ident::new("ac")));
+
+    assert!(IdentPattern::parse("My*").is_err());
+    assert_eq!(Err(ParseError::EmptyPath), IdentPattern::parse("mymod:"));
+
+    let buf = binserde::serialize(&any_ore).unwrap();
+    assert_eq!(any_ore, binserde::deserialize(&buf).unwrap());
+}
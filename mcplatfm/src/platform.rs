@@ -0,0 +1,454 @@
+//! Game versions, mod loaders, and how the layout of packs differs between
+//! them.
+//!
+//! [`ResourceKind`] describes the layout used from 1.13 to 1.20, which is also
+//! the form identifiers are given in. A [`Platform`] translates between that
+//! and the layout of a specific game version: for example, the texture
+//! `minecraft:block/stone` is at `assets/minecraft/textures/blocks/stone.png`
+//! in 1.12, and the block tag `minecraft:blocks/logs` at
+//! `data/minecraft/tags/block/logs.json` in 1.21.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::resource;
+use crate::{Ident, Identifier, PackType, ResourceKind};
+
+/// A release version of the game, such as 1.16.5.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct GameVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl GameVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        GameVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl Display for GameVersion {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+
+        if self.patch != 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for GameVersion {
+    type Err = VersionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || VersionParseError(s.to_string());
+        let mut parts = s.split('.').map(|p| p.parse::<u32>().map_err(|_| err()));
+
+        let major = parts.next().ok_or_else(err)??;
+        let minor = parts.next().ok_or_else(err)??;
+        let patch = parts.next().transpose()?.unwrap_or(0);
+
+        if parts.next().is_some() {
+            return Err(err());
+        }
+
+        Ok(GameVersion::new(major, minor, patch))
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+#[error("invalid game version `{0}`")]
+pub struct VersionParseError(String);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Loader {
+    Vanilla,
+    Forge,
+    Fabric,
+}
+
+impl Display for Loader {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let s = match self {
+            Loader::Vanilla => "Vanilla",
+            Loader::Forge => "Forge",
+            Loader::Fabric => "Fabric",
+        };
+
+        f.write_str(s)
+    }
+}
+
+/// A game version together with the mod loader running on it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Platform {
+    version: GameVersion,
+    loader: Loader,
+}
+
+const fn v(minor: u32, patch: u32) -> GameVersion {
+    GameVersion::new(1, minor, patch)
+}
+
+/// The `pack_format` of resource packs, by the first version using it.
+const RESOURCE_PACK_FORMATS: &[(GameVersion, u32)] = &[
+    (v(6, 1), 1),
+    (v(9, 0), 2),
+    (v(11, 0), 3),
+    (v(13, 0), 4),
+    (v(15, 0), 5),
+    (v(16, 2), 6),
+    (v(17, 0), 7),
+    (v(18, 0), 8),
+    (v(19, 0), 9),
+    (v(19, 3), 12),
+    (v(19, 4), 13),
+    (v(20, 0), 15),
+    (v(20, 2), 18),
+    (v(20, 3), 22),
+    (v(20, 5), 32),
+    (v(21, 0), 34),
+];
+
+/// The `pack_format` of data packs, by the first version using it.
+const DATA_PACK_FORMATS: &[(GameVersion, u32)] = &[
+    (v(13, 0), 4),
+    (v(15, 0), 5),
+    (v(16, 2), 6),
+    (v(17, 0), 7),
+    (v(18, 0), 8),
+    (v(18, 2), 9),
+    (v(19, 0), 10),
+    (v(19, 4), 12),
+    (v(20, 0), 15),
+    (v(20, 2), 18),
+    (v(20, 3), 26),
+    (v(20, 5), 41),
+    (v(21, 0), 48),
+];
+
+/// A directory that has a different name in some versions.
+struct Rename {
+    /// The first version using `actual`, if it is a newer name.
+    since: Option<GameVersion>,
+    /// The last version using `actual` plus one, if it is an older name.
+    until: Option<GameVersion>,
+    /// The directory in the layout of [`ResourceKind`], relative to the
+    /// namespace directory.
+    canonical: &'static str,
+    actual: &'static str,
+}
+
+impl Rename {
+    const fn before(version: GameVersion, canonical: &'static str, actual: &'static str) -> Self {
+        Rename {
+            since: None,
+            until: Some(version),
+            canonical,
+            actual,
+        }
+    }
+
+    const fn since(version: GameVersion, canonical: &'static str, actual: &'static str) -> Self {
+        Rename {
+            since: Some(version),
+            until: None,
+            canonical,
+            actual,
+        }
+    }
+
+    fn applies_to(&self, version: GameVersion) -> bool {
+        self.since.is_none_or(|v| version >= v) && self.until.is_none_or(|v| version < v)
+    }
+}
+
+const RENAMES: &[Rename] = &[
+    Rename::before(v(13, 0), "textures/block", "textures/blocks"),
+    Rename::before(v(13, 0), "textures/item", "textures/items"),
+    Rename::since(v(21, 0), "recipes", "recipe"),
+    Rename::since(v(21, 0), "loot_tables", "loot_table"),
+    Rename::since(v(21, 0), "advancements", "advancement"),
+    Rename::since(v(21, 0), "tags/blocks", "tags/block"),
+    Rename::since(v(21, 0), "tags/items", "tags/item"),
+    Rename::since(v(21, 0), "tags/fluids", "tags/fluid"),
+    Rename::since(v(21, 0), "tags/entity_types", "tags/entity_type"),
+    Rename::since(v(21, 0), "tags/game_events", "tags/game_event"),
+    Rename::since(v(21, 0), "tags/functions", "tags/function"),
+];
+
+/// The format of language files.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum LangFormat {
+    /// `key=value` lines in `.lang` files.
+    Lang,
+    /// A JSON object in `.json` files.
+    Json,
+}
+
+impl Platform {
+    pub fn new(version: GameVersion, loader: Loader) -> Self {
+        Platform { version, loader }
+    }
+
+    pub fn version(&self) -> GameVersion {
+        self.version
+    }
+
+    pub fn loader(&self) -> Loader {
+        self.loader
+    }
+
+    /// Returns the `pack_format` of packs of `pack_type` for this version, or
+    /// `None` if it doesn't support them.
+    pub fn pack_format(&self, pack_type: PackType) -> Option<u32> {
+        let table = match pack_type {
+            PackType::Assets => RESOURCE_PACK_FORMATS,
+            PackType::Data => DATA_PACK_FORMATS,
+        };
+
+        table
+            .iter()
+            .rev()
+            .find(|(since, _)| self.version >= *since)
+            .map(|&(_, format)| format)
+    }
+
+    /// Returns whether this version loads resources of `kind` from packs.
+    pub fn supports(&self, kind: ResourceKind) -> bool {
+        self.pack_format(kind.pack_type()).is_some()
+    }
+
+    pub fn lang_format(&self) -> LangFormat {
+        if self.version < v(13, 0) {
+            LangFormat::Lang
+        } else {
+            LangFormat::Json
+        }
+    }
+
+    /// Returns the file extension of resources of `kind`, without the leading
+    /// dot.
+    pub fn extension(&self, kind: ResourceKind) -> &'static str {
+        match (kind, self.lang_format()) {
+            (ResourceKind::Lang, LangFormat::Lang) => "lang",
+            _ => kind.extension(),
+        }
+    }
+
+    /// Returns the namespace shared tags between mods are in on this
+    /// platform, such as `forge:ingots/copper`, or `None` if there is no
+    /// such convention.
+    pub fn common_tag_namespace(&self) -> Option<&'static str> {
+        if !self.supports(ResourceKind::Tag) {
+            return None;
+        }
+
+        match self.loader {
+            Loader::Vanilla => None,
+            Loader::Forge => Some("forge"),
+            Loader::Fabric => Some("c"),
+        }
+    }
+
+    /// Returns the directory containing all resources of `kind` in
+    /// `namespace`, like [`ResourceKind::namespace_dir`].
+    pub fn namespace_dir(&self, kind: ResourceKind, namespace: &str) -> PathBuf {
+        let mut path = PathBuf::from(kind.pack_type().dir());
+        path.push(namespace);
+        path.push(&*self.actual_dir(kind.dir()));
+        path
+    }
+
+    /// Returns the path of the resource `id` of `kind` inside a pack for this
+    /// platform, or `None` if the platform doesn't support `kind`.
+    pub fn resource_path(&self, kind: ResourceKind, id: &Ident) -> Option<PathBuf> {
+        if !self.supports(kind) {
+            return None;
+        }
+
+        let name = match kind {
+            ResourceKind::Lang if self.version < v(11, 0) => {
+                Cow::Owned(resource::mixed_case_lang(id.path(), '_'))
+            }
+            _ => Cow::Borrowed(id.path()),
+        };
+
+        let rel = format!("{}/{}.{}", kind.dir(), name, self.extension(kind));
+        let mut path = PathBuf::from(kind.pack_type().dir());
+        path.push(id.namespace());
+        path.push(&*self.actual_dir(&rel));
+        Some(path)
+    }
+
+    /// Parses the path of a file inside a pack for this platform back into
+    /// its kind and identifier, like [`resource::parse_path`]. Returns `None`
+    /// if the path isn't laid out the way this platform expects.
+    pub fn parse_path<P: AsRef<Path>>(&self, path: P) -> Option<(ResourceKind, Identifier)> {
+        let mut parts = Vec::new();
+
+        for c in path.as_ref().components() {
+            match c {
+                Component::RootDir | Component::CurDir => {}
+                Component::Normal(s) => parts.push(s.to_str()?),
+                Component::Prefix(_) | Component::ParentDir => return None,
+            }
+        }
+
+        let (pack_type, namespace, rest) = match parts.as_slice() {
+            [pack_type, namespace, rest @ ..] => (pack_type, namespace, rest.join("/")),
+            _ => return None,
+        };
+
+        let mut rel = self.canonical_dir(&rest).into_owned();
+
+        if self.lang_format() == LangFormat::Lang && rel.starts_with("lang/") {
+            let stem = rel.strip_suffix(".lang")?;
+            rel = format!("{}.json", stem.to_lowercase());
+        }
+
+        let (kind, id) = resource::parse_path(format!("{}/{}/{}", pack_type, namespace, rel))?;
+
+        // reject paths using a name from a different version, which the
+        // renames above leave alone
+        let path: PathBuf = parts.iter().collect();
+
+        if self.resource_path(kind, &id)? == path {
+            Some((kind, id))
+        } else {
+            None
+        }
+    }
+
+    fn actual_dir<'a>(&self, rel: &'a str) -> Cow<'a, str> {
+        self.rename(rel, |r| (r.canonical, r.actual))
+    }
+
+    fn canonical_dir<'a>(&self, rel: &'a str) -> Cow<'a, str> {
+        self.rename(rel, |r| (r.actual, r.canonical))
+    }
+
+    fn rename<'a, F>(&self, rel: &'a str, op: F) -> Cow<'a, str>
+    where
+        F: Fn(&Rename) -> (&'static str, &'static str),
+    {
+        for r in RENAMES.iter().filter(|r| r.applies_to(self.version)) {
+            let (from, to) = op(r);
+
+            if let Some(rest) = rel.strip_prefix(from) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    return Cow::Owned(format!("{}{}", to, rest));
+                }
+            }
+        }
+
+        Cow::Borrowed(rel)
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.loader, self.version)
+    }
+}
+
+#[test]
+fn platform_layout() {
+    let old = Platform::new("1.12.2".parse().unwrap(), Loader::Forge);
+    let mid = Platform::new("1.16.5".parse().unwrap(), Loader::Fabric);
+    let new = Platform::new("1.21".parse().unwrap(), Loader::Vanilla);
+
+    assert_eq!(Some(3), old.pack_format(PackType::Assets));
+    assert_eq!(None, old.pack_format(PackType::Data));
+    assert_eq!(Some(6), mid.pack_format(PackType::Data));
+    assert_eq!(Some(48), new.pack_format(PackType::Data));
+    assert_eq!("1.21", new.version().to_string());
+
+    let stone = Identifier::parse("block/stone").unwrap();
+    let logs = Identifier::parse("blocks/logs").unwrap();
+    let en_us = Identifier::parse("en_us").unwrap();
+
+    let cases = [
+        (
+            old,
+            ResourceKind::Texture,
+            &stone,
+            "assets/minecraft/textures/blocks/stone.png",
+        ),
+        (
+            mid,
+            ResourceKind::Texture,
+            &stone,
+            "assets/minecraft/textures/block/stone.png",
+        ),
+        (
+            old,
+            ResourceKind::Lang,
+            &en_us,
+            "assets/minecraft/lang/en_us.lang",
+        ),
+        (
+            mid,
+            ResourceKind::Lang,
+            &en_us,
+            "assets/minecraft/lang/en_us.json",
+        ),
+        (
+            mid,
+            ResourceKind::Tag,
+            &logs,
+            "data/minecraft/tags/blocks/logs.json",
+        ),
+        (
+            new,
+            ResourceKind::Tag,
+            &logs,
+            "data/minecraft/tags/block/logs.json",
+        ),
+    ];
+
+    for &(platform, kind, id, path) in cases.iter() {
+        assert_eq!(
+            Some(Path::new(path).to_path_buf()),
+            platform.resource_path(kind, id)
+        );
+        assert_eq!(Some((kind, id.clone())), platform.parse_path(path));
+    }
+
+    let ancient = Platform::new(GameVersion::new(1, 10, 0), Loader::Vanilla);
+    assert_eq!(
+        Some(Path::new("assets/minecraft/lang/en_US.lang").to_path_buf()),
+        ancient.resource_path(ResourceKind::Lang, &en_us)
+    );
+    assert_eq!(
+        Some((ResourceKind::Lang, en_us)),
+        ancient.parse_path("/assets/minecraft/lang/en_US.lang")
+    );
+
+    assert_eq!(None, old.resource_path(ResourceKind::Recipe, &stone));
+    assert_eq!(
+        None,
+        mid.parse_path("assets/minecraft/textures/blocks/stone.png")
+            .filter(|(_, id)| *id == stone)
+    );
+    assert_eq!(None, new.parse_path("data/minecraft/recipes/a.json"));
+
+    assert_eq!(
+        Some("forge"),
+        Platform::new(v(16, 5), Loader::Forge).common_tag_namespace()
+    );
+    assert_eq!(None, old.common_tag_namespace());
+    assert!("1.x".parse::<GameVersion>().is_err());
+}
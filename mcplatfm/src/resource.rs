@@ -0,0 +1,260 @@
+//! Mapping identifiers to the files they refer to inside a resource or data
+//! pack, and back.
+//!
+//! A resource is located by its identifier and its [`ResourceKind`]: the
+//! texture `minecraft:block/stone` is at
+//! `assets/minecraft/textures/block/stone.png`, and the recipe `mymod:gear` at
+//! `data/mymod/recipes/gear.json`. Paths are always relative to the pack
+//! root.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::path::{Component, Path, PathBuf};
+
+use crate::{Ident, Identifier};
+
+/// The top level directory of a pack.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum PackType {
+    /// Resource pack contents, in `assets`.
+    Assets,
+    /// Data pack contents, in `data`.
+    Data,
+}
+
+impl PackType {
+    pub fn dir(self) -> &'static str {
+        match self {
+            PackType::Assets => "assets",
+            PackType::Data => "data",
+        }
+    }
+
+    pub fn from_dir(dir: &str) -> Option<Self> {
+        match dir {
+            "assets" => Some(PackType::Assets),
+            "data" => Some(PackType::Data),
+            _ => None,
+        }
+    }
+}
+
+impl Display for PackType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.dir())
+    }
+}
+
+/// The kind of file an identifier refers to.
+///
+/// The path of a tag's identifier starts with the registry it belongs to, so
+/// the block tag `minecraft:logs` is the resource `minecraft:blocks/logs`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum ResourceKind {
+    Texture,
+    Model,
+    BlockState,
+    Lang,
+    Sound,
+    Recipe,
+    LootTable,
+    Tag,
+    Advancement,
+}
+
+impl ResourceKind {
+    pub const ALL: [ResourceKind; 9] = [
+        ResourceKind::Texture,
+        ResourceKind::Model,
+        ResourceKind::BlockState,
+        ResourceKind::Lang,
+        ResourceKind::Sound,
+        ResourceKind::Recipe,
+        ResourceKind::LootTable,
+        ResourceKind::Tag,
+        ResourceKind::Advancement,
+    ];
+
+    pub fn pack_type(self) -> PackType {
+        match self {
+            ResourceKind::Texture
+            | ResourceKind::Model
+            | ResourceKind::BlockState
+            | ResourceKind::Lang
+            | ResourceKind::Sound => PackType::Assets,
+            ResourceKind::Recipe
+            | ResourceKind::LootTable
+            | ResourceKind::Tag
+            | ResourceKind::Advancement => PackType::Data,
+        }
+    }
+
+    /// Returns the name of the directory resources of this kind are in,
+    /// inside of the namespace directory.
+    pub fn dir(self) -> &'static str {
+        match self {
+            ResourceKind::Texture => "textures",
+            ResourceKind::Model => "models",
+            ResourceKind::BlockState => "blockstates",
+            ResourceKind::Lang => "lang",
+            ResourceKind::Sound => "sounds",
+            ResourceKind::Recipe => "recipes",
+            ResourceKind::LootTable => "loot_tables",
+            ResourceKind::Tag => "tags",
+            ResourceKind::Advancement => "advancements",
+        }
+    }
+
+    /// Returns the file extension of resources of this kind, without the
+    /// leading dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ResourceKind::Texture => "png",
+            ResourceKind::Sound => "ogg",
+            _ => "json",
+        }
+    }
+
+    /// Returns the directory containing all resources of this kind in
+    /// `namespace`, for example `assets/minecraft/lang`.
+    pub fn namespace_dir(self, namespace: &str) -> PathBuf {
+        [self.pack_type().dir(), namespace, self.dir()]
+            .iter()
+            .collect()
+    }
+
+    /// Returns the path of the resource `id` of this kind.
+    pub fn path(self, id: &Ident) -> PathBuf {
+        let mut path = self.namespace_dir(id.namespace());
+        path.push(format!("{}.{}", id.path(), self.extension()));
+        path
+    }
+
+    fn from_dirs(pack_type: PackType, dir: &str) -> Option<Self> {
+        ResourceKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.pack_type() == pack_type && kind.dir() == dir)
+    }
+}
+
+impl Display for ResourceKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let s = match self {
+            ResourceKind::Texture => "texture",
+            ResourceKind::Model => "model",
+            ResourceKind::BlockState => "block state",
+            ResourceKind::Lang => "language file",
+            ResourceKind::Sound => "sound",
+            ResourceKind::Recipe => "recipe",
+            ResourceKind::LootTable => "loot table",
+            ResourceKind::Tag => "tag",
+            ResourceKind::Advancement => "advancement",
+        };
+
+        f.write_str(s)
+    }
+}
+
+/// Parses the path of a file inside a pack back into its kind and identifier.
+/// A leading `/` is ignored, since data sources list their entries with one.
+///
+/// Returns `None` if the path isn't one of a known resource kind. The
+/// identifier isn't validated, use [`Ident::validate`] to check it.
+pub fn parse_path<P: AsRef<Path>>(path: P) -> Option<(ResourceKind, Identifier)> {
+    let mut parts = Vec::new();
+
+    for c in path.as_ref().components() {
+        match c {
+            Component::RootDir | Component::CurDir => {}
+            Component::Normal(s) => parts.push(s.to_str()?),
+            Component::Prefix(_) | Component::ParentDir => return None,
+        }
+    }
+
+    match parts.as_slice() {
+        [pack_type, namespace, dir, rest @ ..] if !rest.is_empty() => {
+            let kind = ResourceKind::from_dirs(PackType::from_dir(pack_type)?, dir)?;
+            let path = rest.join("/");
+            let path = path.strip_suffix(&format!(".{}", kind.extension()))?;
+
+            Some((kind, Identifier::from_components(namespace, path)))
+        }
+        _ => None,
+    }
+}
+
+/// Converts a language name like `de_de` to one with an upper case region,
+/// separated from the language by `separator`: `de_DE` for `_`, the form
+/// used by language files before 1.11 and by gettext, or the language tag
+/// `de-DE` for `-`.
+pub fn mixed_case_lang(name: &str, separator: char) -> String {
+    match name.split_once('_') {
+        Some((lang, rest)) => {
+            let rest = rest.to_uppercase().replace('_', &separator.to_string());
+            format!("{}{}{}", lang, separator, rest)
+        }
+        None => name.to_string(),
+    }
+}
+
+impl Ident {
+    /// Returns the path of the resource of `kind` this identifier refers to.
+    /// See [`ResourceKind::path`].
+    pub fn resource_path(&self, kind: ResourceKind) -> PathBuf {
+        kind.path(self)
+    }
+}
+
+#[test]
+fn resource_paths() {
+    let cases = [
+        (
+            ResourceKind::Texture,
+            "block/stone",
+            "assets/minecraft/textures/block/stone.png",
+        ),
+        (
+            ResourceKind::BlockState,
+            "mymod:gear",
+            "assets/mymod/blockstates/gear.json",
+        ),
+        (
+            ResourceKind::Lang,
+            "en_us",
+            "assets/minecraft/lang/en_us.json",
+        ),
+        (
+            ResourceKind::Sound,
+            "mymod:entity/cow/moo",
+            "assets/mymod/sounds/entity/cow/moo.ogg",
+        ),
+        (
+            ResourceKind::LootTable,
+            "blocks/dirt",
+            "data/minecraft/loot_tables/blocks/dirt.json",
+        ),
+        (
+            ResourceKind::Tag,
+            "mymod:items/gears",
+            "data/mymod/tags/items/gears.json",
+        ),
+    ];
+
+    for &(kind, id, path) in cases.iter() {
+        let id = Identifier::parse(id).unwrap();
+        assert_eq!(Path::new(path), id.resource_path(kind));
+        assert_eq!(Some((kind, id.clone())), parse_path(path));
+        assert_eq!(Some((kind, id)), parse_path(Path::new("/").join(path)));
+    }
+
+    assert_eq!(None, parse_path("assets/minecraft/recipes/a.json"));
+    assert_eq!(None, parse_path("assets/minecraft/textures/a.png.mcmeta"));
+    assert_eq!(None, parse_path("assets/minecraft/textures"));
+    assert_eq!(None, parse_path("pack.mcmeta"));
+
+    assert_eq!("en_US", mixed_case_lang("en_us", '_'));
+    assert_eq!("de-DE", mixed_case_lang("de_de", '-'));
+    assert_eq!("qya-AA-X", mixed_case_lang("qya_aa_x", '-'));
+    assert_eq!("lol", mixed_case_lang("lol", '-'));
+}
@@ -0,0 +1,198 @@
+//! References to tags, written as `#namespace:path`, as found in recipes,
+//! tags and commands wherever either a single object or a tag of them is
+//! accepted.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use binserde::{BinDeserialize, BinDeserializer, BinSerialize};
+use thiserror::Error;
+
+use crate::ident::{InvalidChar, ParseError};
+use crate::{Ident, Identifier};
+
+/// A reference to a tag, such as `#minecraft:logs`.
+///
+/// This is serialized as the tag's identifier, without the `#`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, BinSerialize, BinDeserialize)]
+pub struct TagRef {
+    #[binserde(deserialize_with = "deserialize_valid")]
+    id: Identifier,
+}
+
+impl TagRef {
+    pub fn new(id: Identifier) -> Self {
+        TagRef { id }
+    }
+
+    /// Parses `s` as a tag reference, which is a `#` followed by a valid
+    /// identifier.
+    pub fn parse(s: &str) -> Result<Self, TagParseError> {
+        let id = s.strip_prefix('#').ok_or(TagParseError::NotATag)?;
+
+        match Identifier::parse(id) {
+            Ok(id) => Ok(TagRef::new(id)),
+            // report positions in s, not in the identifier
+            Err(ParseError::InvalidChars(chars)) => Err(ParseError::InvalidChars(
+                chars
+                    .into_iter()
+                    .map(|c| InvalidChar {
+                        pos: c.pos + 1,
+                        ..c
+                    })
+                    .collect(),
+            )
+            .into()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the identifier of the referenced tag.
+    pub fn id(&self) -> &Ident {
+        &self.id
+    }
+
+    pub fn into_id(self) -> Identifier {
+        self.id
+    }
+}
+
+impl FromStr for TagRef {
+    type Err = TagParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TagRef::parse(s)
+    }
+}
+
+impl Display for TagRef {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "#{}", self.id)
+    }
+}
+
+/// Either a single identifier or a tag reference, such as `minecraft:oak_log`
+/// or `#minecraft:logs`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, BinSerialize, BinDeserialize)]
+pub enum IdentOrTag {
+    Ident(#[binserde(deserialize_with = "deserialize_valid")] Identifier),
+    Tag(TagRef),
+}
+
+impl IdentOrTag {
+    /// Parses `s` as a tag reference if it starts with `#`, and as an
+    /// identifier otherwise.
+    pub fn parse(s: &str) -> Result<Self, TagParseError> {
+        if s.starts_with('#') {
+            TagRef::parse(s).map(IdentOrTag::Tag)
+        } else {
+            Ok(IdentOrTag::Ident(Identifier::parse(s)?))
+        }
+    }
+
+    pub fn is_tag(&self) -> bool {
+        matches!(self, IdentOrTag::Tag(_))
+    }
+
+    /// Returns the identifier, or the identifier of the referenced tag.
+    pub fn id(&self) -> &Ident {
+        match self {
+            IdentOrTag::Ident(id) => id,
+            IdentOrTag::Tag(tag) => tag.id(),
+        }
+    }
+}
+
+impl FromStr for IdentOrTag {
+    type Err = TagParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IdentOrTag::parse(s)
+    }
+}
+
+impl Display for IdentOrTag {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            IdentOrTag::Ident(id) => id.fmt(f),
+            IdentOrTag::Tag(tag) => tag.fmt(f),
+        }
+    }
+}
+
+impl From<Identifier> for IdentOrTag {
+    fn from(id: Identifier) -> Self {
+        IdentOrTag::Ident(id)
+    }
+}
+
+impl From<TagRef> for IdentOrTag {
+    fn from(tag: TagRef) -> Self {
+        IdentOrTag::Tag(tag)
+    }
+}
+
+/// Why a string couldn't be parsed as a [`TagRef`] or [`IdentOrTag`].
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum TagParseError {
+    #[error("tag reference doesn't start with '#'")]
+    NotATag,
+    #[error("{0}")]
+    InvalidIdent(#[from] ParseError),
+}
+
+/// Reads an identifier, rejecting one that [`Identifier::parse`] wouldn't
+/// accept.
+fn deserialize_valid<'de, D: BinDeserializer<'de>>(
+    deserializer: D,
+) -> binserde::Result<Identifier> {
+    let id = Identifier::deserialize(deserializer)?;
+
+    match id.validate() {
+        Ok(()) => Ok(id),
+        Err(e) => Err(binserde::Error::custom(format!(
+            "invalid identifier '{}': {}",
+            id, e
+        ))),
+    }
+}
+
+#[test]
+fn parse_tag_ref() {
+    let tag: TagRef = "#logs".parse().unwrap();
+    assert_eq!("minecraft:logs", tag.id().to_string());
+    assert_eq!("#minecraft:logs", tag.to_string());
+
+    assert_eq!(Err(TagParseError::NotATag), TagRef::parse("logs"));
+    assert_eq!(
+        Err(TagParseError::InvalidIdent(ParseError::InvalidChars(vec![
+            InvalidChar { pos: 1, ch: 'L' }
+        ]))),
+        TagRef::parse("#Logs")
+    );
+
+    let item: IdentOrTag = "mymod:gear".parse().unwrap();
+    let tag: IdentOrTag = "#c:gears".parse().unwrap();
+    assert!(!item.is_tag());
+    assert!(tag.is_tag());
+    assert_eq!("mymod:gear", item.to_string());
+    assert_eq!("#c:gears", tag.to_string());
+
+    let values = vec![item, tag];
+    let buf = binserde::serialize(&values).unwrap();
+    assert_eq!(
+        values,
+        binserde::deserialize::<Vec<IdentOrTag>>(&buf).unwrap()
+    );
+
+    // both are checked when reading them back
+    let invalid = vec![
+        IdentOrTag::Ident(Identifier::from("Gear".to_string())),
+        IdentOrTag::Tag(TagRef::new(Identifier::from("c:".to_string()))),
+    ];
+    for value in invalid {
+        let buf = binserde::serialize(&value).unwrap();
+        assert!(binserde::deserialize::<IdentOrTag>(&buf).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+#![forbid(unstable_features)]
+
+extern crate proc_macro;
+
+use darling::ast::Data;
+use darling::util::Ignored;
+use darling::{FromDeriveInput, FromField};
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{GenericArgument, Generics, Ident, PathArguments, Type};
+
+#[derive(FromDeriveInput)]
+#[darling(attributes(nbt), supports(struct_named))]
+struct NbtOpts {
+    ident: Ident,
+    generics: Generics,
+    data: Data<Ignored, NbtField>,
+}
+
+#[derive(FromField)]
+#[darling(attributes(nbt))]
+struct NbtField {
+    ident: Option<Ident>,
+    ty: Type,
+    #[darling(default)]
+    rename: Option<String>,
+    #[darling(default)]
+    skip: bool,
+    #[darling(default)]
+    default: bool,
+    #[darling(default)]
+    array: bool,
+}
+
+impl NbtField {
+    fn ident(&self) -> &Ident {
+        self.ident.as_ref().unwrap()
+    }
+
+    fn key(&self) -> String {
+        self.rename
+            .clone()
+            .unwrap_or_else(|| self.ident().to_string())
+    }
+
+    /// Whether the field's type is an `Option`, which is written only if it
+    /// is `Some`.
+    fn is_option(&self) -> bool {
+        option_inner(&self.ty).is_some()
+    }
+
+    fn validate(&self) {
+        let name = self.ident();
+
+        if self.skip && (self.default || self.array || self.rename.is_some()) {
+            panic!(
+                "field `{}`: skip can't be combined with other attributes",
+                name
+            );
+        }
+
+        if self.is_option() && (self.default || self.array) {
+            panic!(
+                "field `{}`: default and array can't be used on Option fields",
+                name
+            );
+        }
+    }
+
+    /// The path of the function converting a tag to this field's value.
+    fn read_fn(&self) -> TokenStream2 {
+        if self.array {
+            quote!(::nbt::convert::NbtArray::from_array_tag)
+        } else {
+            quote!(::nbt::FromNbt::from_nbt)
+        }
+    }
+
+    /// The expression converting `value` (a reference to this field's value)
+    /// to a tag.
+    fn to_expr(&self, value: TokenStream2) -> TokenStream2 {
+        if self.array {
+            quote!(::nbt::convert::NbtArray::to_array_tag(#value))
+        } else {
+            quote!(::nbt::ToNbt::to_nbt(#value))
+        }
+    }
+}
+
+/// Returns `T` if `ty` is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(p) if p.qself.is_none() => &p.path,
+        _ => return None,
+    };
+
+    let last = path.segments.last()?;
+
+    if last.ident != "Option" {
+        return None;
+    }
+
+    match &last.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse(input: TokenStream) -> NbtOpts {
+    let ast = syn::parse(input).expect("failed to parse token stream");
+    let opts: NbtOpts = FromDeriveInput::from_derive_input(&ast).unwrap();
+
+    for field in fields(&opts) {
+        field.validate();
+    }
+
+    opts
+}
+
+fn fields(opts: &NbtOpts) -> &[NbtField] {
+    match &opts.data {
+        Data::Struct(fields) => &fields.fields,
+        Data::Enum(_) => unreachable!(),
+    }
+}
+
+/// Returns the generics of the impl, with `bound` added to every type
+/// parameter.
+fn bounded_generics(opts: &NbtOpts, bound: TokenStream2) -> Generics {
+    let mut generics = opts.generics.clone();
+
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse2(bound.clone()).unwrap());
+    }
+
+    generics
+}
+
+#[proc_macro_derive(ToNbt, attributes(nbt))]
+pub fn to_nbt_derive(input: TokenStream) -> TokenStream {
+    let opts = parse(input);
+    let name = &opts.ident;
+    let generics = bounded_generics(&opts, quote!(::nbt::ToNbt));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let stmts = fields(&opts).iter().filter(|f| !f.skip).map(|f| {
+        let ident = f.ident();
+        let key = f.key();
+
+        if f.is_option() {
+            let expr = f.to_expr(quote!(value));
+            quote! {
+                if let ::std::option::Option::Some(value) = &self.#ident {
+                    compound.insert(#key.to_string(), #expr);
+                }
+            }
+        } else {
+            let expr = f.to_expr(quote!(&self.#ident));
+            quote!(compound.insert(#key.to_string(), #expr);)
+        }
+    });
+
+    let gen = quote! {
+        impl #impl_generics ::nbt::ToNbt for #name #ty_generics #where_clause {
+            fn to_nbt(&self) -> ::nbt::Tag {
+                let mut compound = ::nbt::Compound::new();
+                #( #stmts )*
+                ::nbt::Tag::Compound(compound)
+            }
+        }
+    };
+
+    gen.into()
+}
+
+#[proc_macro_derive(FromNbt, attributes(nbt))]
+pub fn from_nbt_derive(input: TokenStream) -> TokenStream {
+    let opts = parse(input);
+    let name = &opts.ident;
+    let generics = bounded_generics(&opts, quote!(::nbt::FromNbt));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let inits = fields(&opts).iter().map(|f| {
+        let ident = f.ident();
+        let key = f.key();
+        let read_fn = f.read_fn();
+
+        let expr = if f.skip {
+            quote!(::std::default::Default::default())
+        } else if f.is_option() {
+            quote! {
+                match compound.get(#key) {
+                    ::std::option::Option::Some(tag) => ::std::option::Option::Some(#read_fn(tag)?),
+                    ::std::option::Option::None => ::std::option::Option::None,
+                }
+            }
+        } else if f.default {
+            quote! {
+                match compound.get(#key) {
+                    ::std::option::Option::Some(tag) => #read_fn(tag)?,
+                    ::std::option::Option::None => ::std::default::Default::default(),
+                }
+            }
+        } else {
+            quote! {
+                match compound.get(#key) {
+                    ::std::option::Option::Some(tag) => #read_fn(tag)?,
+                    ::std::option::Option::None => {
+                        return ::std::result::Result::Err(::nbt::Error::MissingKey(#key.to_string()))
+                    }
+                }
+            }
+        };
+
+        quote!(#ident: #expr)
+    });
+
+    let gen = quote! {
+        impl #impl_generics ::nbt::FromNbt for #name #ty_generics #where_clause {
+            fn from_nbt(tag: &::nbt::Tag) -> ::nbt::Result<Self> {
+                let compound = ::nbt::convert::expect_compound(tag)?;
+
+                ::std::result::Result::Ok(#name {
+                    #( #inits, )*
+                })
+            }
+        }
+    };
+
+    gen.into()
+}
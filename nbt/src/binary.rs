@@ -0,0 +1,249 @@
+//! The binary NBT format, implemented on top of binserde.
+//!
+//! [`Tag`] and [`NamedTag`] implement [`BinSerialize`] and [`BinDeserialize`].
+//! NBT always uses big-endian fixed size numbers and its own string encoding,
+//! so these implementations change the mode of the (de)serializer they're
+//! given accordingly. The [`Limits`](binserde::Limits) of the mode still
+//! apply when reading.
+//!
+//! A [`Tag`] is written as its type ID followed by its payload, which is the
+//! nameless form Minecraft uses over the network. A [`NamedTag`] has its name
+//! in between, which is the form used in files.
+
+use std::convert::TryFrom;
+use std::io;
+use std::io::{Read, Write};
+
+use binserde::{
+    BinDeserialize, BinDeserializer, BinSerialize, BinSerializer, Endianness, Error, Mode, Result,
+};
+
+use crate::mutf8;
+use crate::tag::{Compound, NamedTag, Tag, TagType};
+
+/// How many elements to allocate room for up front when reading a list or
+/// array, so that a bogus length can't make us allocate a huge buffer.
+const PREALLOC: usize = 1024;
+
+fn nbt_mode(mode: &mut Mode) {
+    mode.endianness = Endianness::Big;
+    mode.fixed_size_use_varint = false;
+    mode.use_dedup = false;
+}
+
+impl BinSerialize for Tag {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        let mut serializer = serializer.change_mode(nbt_mode);
+        self.tag_type().id().serialize(&mut serializer)?;
+        write_payload(self, &mut serializer)
+    }
+}
+
+impl<'de> BinDeserialize<'de> for Tag {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        let mut deserializer = deserializer.change_mode(nbt_mode);
+        let ty = read_type(&mut deserializer)?;
+        read_payload(ty, &mut deserializer)
+    }
+}
+
+impl BinSerialize for NamedTag {
+    fn serialize<S: BinSerializer>(&self, serializer: S) -> Result<()> {
+        let mut serializer = serializer.change_mode(nbt_mode);
+        self.tag.tag_type().id().serialize(&mut serializer)?;
+        write_string(&self.name, &mut serializer)?;
+        write_payload(&self.tag, &mut serializer)
+    }
+}
+
+impl<'de> BinDeserialize<'de> for NamedTag {
+    fn deserialize<D: BinDeserializer<'de>>(deserializer: D) -> Result<Self> {
+        let mut deserializer = deserializer.change_mode(nbt_mode);
+        let ty = read_type(&mut deserializer)?;
+        let name = read_string(&mut deserializer)?;
+        let tag = read_payload(ty, &mut deserializer)?;
+        Ok(NamedTag { name, tag })
+    }
+}
+
+fn write_payload<S: BinSerializer>(tag: &Tag, serializer: &mut S) -> Result<()> {
+    match tag {
+        Tag::Byte(v) => v.serialize(serializer),
+        Tag::Short(v) => v.serialize(serializer),
+        Tag::Int(v) => v.serialize(serializer),
+        Tag::Long(v) => v.serialize(serializer),
+        Tag::Float(v) => v.serialize(serializer),
+        Tag::Double(v) => v.serialize(serializer),
+        Tag::ByteArray(v) => {
+            write_len(v.len(), serializer)?;
+            let bytes: Vec<u8> = v.iter().map(|&b| b as u8).collect();
+            serializer.pipe().write_all(&bytes)?;
+            Ok(())
+        }
+        Tag::String(v) => write_string(v, serializer),
+        Tag::List(v) => {
+            let ty = tag
+                .list_type()
+                .ok_or_else(|| Error::custom("list contains elements of different types"))?;
+            ty.id().serialize(&mut *serializer)?;
+            write_len(v.len(), serializer)?;
+
+            for el in v {
+                write_payload(el, serializer)?;
+            }
+
+            Ok(())
+        }
+        Tag::Compound(v) => {
+            for (name, el) in v {
+                el.tag_type().id().serialize(&mut *serializer)?;
+                write_string(name, serializer)?;
+                write_payload(el, serializer)?;
+            }
+
+            TagType::End.id().serialize(serializer)
+        }
+        Tag::IntArray(v) => write_array(v, serializer),
+        Tag::LongArray(v) => write_array(v, serializer),
+    }
+}
+
+fn write_array<S, T>(array: &[T], serializer: &mut S) -> Result<()>
+where
+    S: BinSerializer,
+    T: BinSerialize,
+{
+    write_len(array.len(), serializer)?;
+
+    for el in array {
+        el.serialize(&mut *serializer)?;
+    }
+
+    Ok(())
+}
+
+fn write_len<S: BinSerializer>(len: usize, serializer: &mut S) -> Result<()> {
+    i32::try_from(len)?.serialize(serializer)
+}
+
+fn write_string<S: BinSerializer>(s: &str, serializer: &mut S) -> Result<()> {
+    let bytes = mutf8::encode(s);
+    u16::try_from(bytes.len())?.serialize(&mut *serializer)?;
+    serializer.pipe().write_all(&bytes)?;
+    Ok(())
+}
+
+fn read_type<'de, D: BinDeserializer<'de>>(deserializer: &mut D) -> Result<TagType> {
+    let id = u8::deserialize(deserializer)?;
+    TagType::from_id(id).ok_or_else(|| Error::custom(format!("invalid tag type {}", id)))
+}
+
+fn read_payload<'de, D: BinDeserializer<'de>>(ty: TagType, deserializer: &mut D) -> Result<Tag> {
+    let tag = match ty {
+        TagType::End => return Err(Error::custom("unexpected end tag")),
+        TagType::Byte => Tag::Byte(i8::deserialize(deserializer)?),
+        TagType::Short => Tag::Short(i16::deserialize(deserializer)?),
+        TagType::Int => Tag::Int(i32::deserialize(deserializer)?),
+        TagType::Long => Tag::Long(i64::deserialize(deserializer)?),
+        TagType::Float => Tag::Float(f32::deserialize(deserializer)?),
+        TagType::Double => Tag::Double(f64::deserialize(deserializer)?),
+        TagType::ByteArray => {
+            let len = read_len(deserializer)?;
+            let mut buf = Vec::with_capacity(len.min(PREALLOC));
+            (&mut *deserializer.pipe())
+                .take(len as u64)
+                .read_to_end(&mut buf)?;
+
+            if buf.len() != len {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+
+            Tag::ByteArray(buf.into_iter().map(|b| b as i8).collect())
+        }
+        TagType::String => Tag::String(read_string(deserializer)?),
+        TagType::List => deserializer.nested(|deserializer| {
+            let ty = read_type(deserializer)?;
+            let len = read_len(deserializer)?;
+
+            if ty == TagType::End && len > 0 {
+                return Err(Error::custom("non-empty list of end tags"));
+            }
+
+            let mut list = Vec::with_capacity(len.min(PREALLOC));
+
+            for _ in 0..len {
+                list.push(read_payload(ty, deserializer)?);
+            }
+
+            Ok(Tag::List(list))
+        })?,
+        TagType::Compound => deserializer.nested(|deserializer| {
+            let mut compound = Compound::new();
+
+            loop {
+                let ty = read_type(deserializer)?;
+
+                if ty == TagType::End {
+                    break;
+                }
+
+                let name = read_string(deserializer)?;
+                let el = read_payload(ty, deserializer)?;
+                compound.insert(name, el);
+            }
+
+            Ok(Tag::Compound(compound))
+        })?,
+        TagType::IntArray => Tag::IntArray(read_array(deserializer)?),
+        TagType::LongArray => Tag::LongArray(read_array(deserializer)?),
+    };
+
+    Ok(tag)
+}
+
+fn read_array<'de, D, T>(deserializer: &mut D) -> Result<Vec<T>>
+where
+    D: BinDeserializer<'de>,
+    T: BinDeserialize<'de>,
+{
+    let len = read_len(deserializer)?;
+    let mut array = Vec::with_capacity(len.min(PREALLOC));
+
+    for _ in 0..len {
+        array.push(T::deserialize(&mut *deserializer)?);
+    }
+
+    Ok(array)
+}
+
+fn read_len<'de, D: BinDeserializer<'de>>(deserializer: &mut D) -> Result<usize> {
+    let len = i32::deserialize(&mut *deserializer)?;
+    let len =
+        usize::try_from(len).map_err(|_| Error::custom(format!("negative length {}", len)))?;
+
+    if let Some(max_len) = deserializer.mode().limits.max_len {
+        if len > max_len {
+            return Err(Error::CollectionTooLong(len));
+        }
+    }
+
+    Ok(len)
+}
+
+fn read_string<'de, D: BinDeserializer<'de>>(deserializer: &mut D) -> Result<String> {
+    let len = u16::deserialize(&mut *deserializer)? as usize;
+
+    if let Some(max_str_bytes) = deserializer.mode().limits.max_str_bytes {
+        if len > max_str_bytes {
+            return Err(Error::StrTooLong(len));
+        }
+    }
+
+    let mut buf = vec![0; len];
+    deserializer.pipe().read_exact(&mut buf)?;
+
+    match mutf8::decode(&buf) {
+        Some(s) => Ok(s.into_owned()),
+        None => Err(Error::custom("invalid modified UTF-8 string")),
+    }
+}
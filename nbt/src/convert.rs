@@ -0,0 +1,313 @@
+//! Conversion between Rust values and tags.
+//!
+//! [`ToNbt`] and [`FromNbt`] can be derived for structs with named fields,
+//! which are converted to and from a compound with a key for each field. The
+//! derive supports these field attributes:
+//!
+//! - `#[nbt(rename = "Name")]` uses `Name` as the key instead of the field's
+//!   name.
+//! - `#[nbt(skip)]` leaves the field out of the compound, and fills it with
+//!   `Default::default()` when reading.
+//! - `#[nbt(default)]` uses `Default::default()` if the key is missing instead
+//!   of failing.
+//! - `#[nbt(array)]` converts a `Vec<i8>`, `Vec<i32>` or `Vec<i64>` to a byte,
+//!   int or long array instead of a list.
+//!
+//! Fields of type `Option<T>` are left out of the compound if they are `None`,
+//! and are `None` if the key is missing.
+//!
+//! ```
+//! use nbt::{FromNbt, Tag, ToNbt};
+//!
+//! #[derive(ToNbt, FromNbt, Debug, PartialEq)]
+//! struct Item {
+//!     id: String,
+//!     #[nbt(rename = "Count")]
+//!     count: i8,
+//!     tag: Option<Tag>,
+//! }
+//!
+//! let item = Item { id: "minecraft:stone".to_string(), count: 3, tag: None };
+//! let tag = item.to_nbt();
+//!
+//! assert_eq!(r#"{Count:3b,id:"minecraft:stone"}"#, tag.to_string());
+//! assert_eq!(item, Item::from_nbt(&tag).unwrap());
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+use crate::tag::{Compound, Tag, TagType};
+use crate::{Error, Result};
+
+pub trait ToNbt {
+    fn to_nbt(&self) -> Tag;
+}
+
+pub trait FromNbt: Sized {
+    fn from_nbt(tag: &Tag) -> Result<Self>;
+}
+
+/// Conversion of vectors to array tags, used for fields with
+/// `#[nbt(array)]`.
+pub trait NbtArray: Sized {
+    fn to_array_tag(&self) -> Tag;
+
+    fn from_array_tag(tag: &Tag) -> Result<Self>;
+}
+
+/// Returns the contents of `tag` if it is a compound.
+pub fn expect_compound(tag: &Tag) -> Result<&Compound> {
+    tag.as_compound()
+        .ok_or_else(|| mismatch(TagType::Compound, tag))
+}
+
+fn mismatch(expected: TagType, found: &Tag) -> Error {
+    Error::TypeMismatch {
+        expected,
+        found: found.tag_type(),
+    }
+}
+
+macro_rules! impl_tag_type {
+    ($($type:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl ToNbt for $type {
+                fn to_nbt(&self) -> Tag {
+                    Tag::$variant(self.clone())
+                }
+            }
+
+            impl FromNbt for $type {
+                fn from_nbt(tag: &Tag) -> Result<Self> {
+                    match tag {
+                        Tag::$variant(v) => Ok(v.clone()),
+                        _ => Err(mismatch(TagType::$variant, tag)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_tag_type! {
+    i8 => Byte,
+    i16 => Short,
+    i32 => Int,
+    i64 => Long,
+    f32 => Float,
+    f64 => Double,
+    String => String,
+}
+
+macro_rules! impl_array {
+    ($($type:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl NbtArray for Vec<$type> {
+                fn to_array_tag(&self) -> Tag {
+                    Tag::$variant(self.clone())
+                }
+
+                fn from_array_tag(tag: &Tag) -> Result<Self> {
+                    match tag {
+                        Tag::$variant(v) => Ok(v.clone()),
+                        _ => Err(mismatch(TagType::$variant, tag)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_array! {
+    i8 => ByteArray,
+    i32 => IntArray,
+    i64 => LongArray,
+}
+
+impl ToNbt for bool {
+    fn to_nbt(&self) -> Tag {
+        Tag::Byte(*self as i8)
+    }
+}
+
+impl FromNbt for bool {
+    fn from_nbt(tag: &Tag) -> Result<Self> {
+        match tag {
+            Tag::Byte(v) => Ok(*v != 0),
+            _ => Err(mismatch(TagType::Byte, tag)),
+        }
+    }
+}
+
+impl ToNbt for str {
+    fn to_nbt(&self) -> Tag {
+        Tag::String(self.to_string())
+    }
+}
+
+impl ToNbt for Tag {
+    fn to_nbt(&self) -> Tag {
+        self.clone()
+    }
+}
+
+impl FromNbt for Tag {
+    fn from_nbt(tag: &Tag) -> Result<Self> {
+        Ok(tag.clone())
+    }
+}
+
+impl<T> ToNbt for &T
+where
+    T: ToNbt + ?Sized,
+{
+    fn to_nbt(&self) -> Tag {
+        (*self).to_nbt()
+    }
+}
+
+impl<T> ToNbt for Box<T>
+where
+    T: ToNbt + ?Sized,
+{
+    fn to_nbt(&self) -> Tag {
+        (**self).to_nbt()
+    }
+}
+
+impl<T> FromNbt for Box<T>
+where
+    T: FromNbt,
+{
+    fn from_nbt(tag: &Tag) -> Result<Self> {
+        T::from_nbt(tag).map(Box::new)
+    }
+}
+
+impl<T> ToNbt for Vec<T>
+where
+    T: ToNbt,
+{
+    fn to_nbt(&self) -> Tag {
+        Tag::List(self.iter().map(ToNbt::to_nbt).collect())
+    }
+}
+
+impl<T> FromNbt for Vec<T>
+where
+    T: FromNbt,
+{
+    fn from_nbt(tag: &Tag) -> Result<Self> {
+        match tag {
+            Tag::List(v) => v.iter().map(T::from_nbt).collect(),
+            _ => Err(mismatch(TagType::List, tag)),
+        }
+    }
+}
+
+impl<T> ToNbt for BTreeMap<String, T>
+where
+    T: ToNbt,
+{
+    fn to_nbt(&self) -> Tag {
+        Tag::Compound(self.iter().map(|(k, v)| (k.clone(), v.to_nbt())).collect())
+    }
+}
+
+impl<T> FromNbt for BTreeMap<String, T>
+where
+    T: FromNbt,
+{
+    fn from_nbt(tag: &Tag) -> Result<Self> {
+        expect_compound(tag)?
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), T::from_nbt(v)?)))
+            .collect()
+    }
+}
+
+impl<T, S> ToNbt for HashMap<String, T, S>
+where
+    T: ToNbt,
+{
+    fn to_nbt(&self) -> Tag {
+        Tag::Compound(self.iter().map(|(k, v)| (k.clone(), v.to_nbt())).collect())
+    }
+}
+
+impl<T, S> FromNbt for HashMap<String, T, S>
+where
+    T: FromNbt,
+    S: BuildHasher + Default,
+{
+    fn from_nbt(tag: &Tag) -> Result<Self> {
+        expect_compound(tag)?
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), T::from_nbt(v)?)))
+            .collect()
+    }
+}
+
+#[test]
+fn derive_nbt() {
+    use crate::{FromNbt, ToNbt};
+
+    #[derive(ToNbt, FromNbt, Debug, PartialEq)]
+    struct Entity {
+        #[nbt(rename = "Pos")]
+        pos: Vec<f64>,
+        #[nbt(array, rename = "UUID")]
+        uuid: Vec<i32>,
+        #[nbt(default)]
+        tags: Vec<String>,
+        custom_name: Option<String>,
+        #[nbt(skip)]
+        cached: u32,
+        on_ground: bool,
+    }
+
+    let entity = Entity {
+        pos: vec![1.0, 2.5, 3.0],
+        uuid: vec![1, 2, 3, 4],
+        tags: vec![],
+        custom_name: None,
+        cached: 5,
+        on_ground: true,
+    };
+
+    let tag = entity.to_nbt();
+    assert_eq!(
+        "{Pos:[1.0d,2.5d,3.0d],UUID:[I;1,2,3,4],on_ground:1b,tags:[]}",
+        tag.to_string()
+    );
+
+    let read = Entity::from_nbt(&tag).unwrap();
+    assert_eq!(0, read.cached);
+    assert_eq!(
+        Entity {
+            cached: 0,
+            ..entity
+        },
+        read
+    );
+
+    let tag: Tag = "{Pos:[],UUID:[I;],on_ground:0b,custom_name:\"x\"}"
+        .parse()
+        .unwrap();
+    let read = Entity::from_nbt(&tag).unwrap();
+    assert_eq!(Some("x".to_string()), read.custom_name);
+    assert!(read.tags.is_empty());
+
+    assert!(matches!(
+        Entity::from_nbt(&"{Pos:[],UUID:[I;]}".parse().unwrap()),
+        Err(Error::MissingKey(key)) if key == "on_ground"
+    ));
+    assert!(matches!(
+        Entity::from_nbt(&"{Pos:[],UUID:[1,2],on_ground:0b}".parse().unwrap()),
+        Err(Error::TypeMismatch {
+            expected: TagType::IntArray,
+            found: TagType::List
+        })
+    ));
+}
@@ -0,0 +1,199 @@
+//! # nbt
+//!
+//! Reading and writing Minecraft's NBT (named binary tag) format, which is
+//! used for structure files, `level.dat`, player data and more.
+//!
+//! A tag tree is represented by [`Tag`], with [`NamedTag`] for the named root
+//! tag found in files. Files can be read and written with [`read`] and
+//! [`write`], uncompressed or compressed with gzip or zlib. The binary format
+//! is implemented using binserde (see the [`binary`] module), so tags can also
+//! be embedded in other binserde data.
+//!
+//! The text format (SNBT) used in commands and data packs is supported
+//! through the [`Display`](std::fmt::Display) and
+//! [`FromStr`](std::str::FromStr) implementations of [`Tag`]; see the
+//! [`snbt`] module.
+//!
+//! ## Usage
+//!
+//! ```
+//! use nbt::{Compression, NamedTag, Tag};
+//!
+//! let tag: Tag = "{DataVersion:2586,size:[I;1,2,3]}".parse().unwrap();
+//! let root = NamedTag::new("", tag);
+//!
+//! let mut buf = Vec::new();
+//! nbt::write(&mut buf, &root, Compression::Gzip).unwrap();
+//!
+//! let read = nbt::read(&*buf).unwrap();
+//!
+//! assert_eq!(root, read);
+//! assert_eq!(Some(2586), read.tag.as_compound().unwrap()["DataVersion"].as_i64());
+//! ```
+//!
+//! Structs can be converted to and from compound tags by deriving [`ToNbt`]
+//! and [`FromNbt`]; see the [`convert`] module.
+
+#![forbid(unstable_features)]
+
+extern crate self as nbt;
+
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use thiserror::Error;
+
+use binserde::{Limits, Mode};
+pub use convert::{FromNbt, ToNbt};
+pub use nbt_derive::{FromNbt, ToNbt};
+pub use tag::{Compound, NamedTag, Tag, TagType};
+
+pub mod binary;
+pub mod convert;
+pub mod mutf8;
+pub mod snbt;
+mod tag;
+
+/// The limits used by [`read`]. These are the same as
+/// [`Limits::untrusted`], except for the nesting depth, which is raised to
+/// Minecraft's limit of 512.
+pub const DEFAULT_LIMITS: Limits = Limits {
+    max_depth: Some(512),
+    ..Limits::untrusted()
+};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zlib,
+}
+
+impl Compression {
+    /// Guesses the compression of an NBT file from its first bytes.
+    pub fn detect(header: &[u8]) -> Self {
+        match header {
+            [0x1F, 0x8B, ..] => Compression::Gzip,
+            [a @ 0x78, b, ..] if (*a as u16 * 256 + *b as u16).is_multiple_of(31) => {
+                Compression::Zlib
+            }
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Reads an NBT file from `pipe`, detecting its compression and applying
+/// [`DEFAULT_LIMITS`].
+pub fn read<R: Read>(pipe: R) -> Result<NamedTag> {
+    let mut pipe = BufReader::new(pipe);
+    let compression = Compression::detect(pipe.fill_buf()?);
+    read_with(pipe, compression, DEFAULT_LIMITS)
+}
+
+pub fn read_with<R: Read>(pipe: R, compression: Compression, limits: Limits) -> Result<NamedTag> {
+    let mode = Mode::default().with_limits(limits);
+
+    let tag = match compression {
+        Compression::None => binserde::deserialize_with_from(pipe, mode)?,
+        Compression::Gzip => binserde::deserialize_with_from(GzDecoder::new(pipe), mode)?,
+        Compression::Zlib => binserde::deserialize_with_from(ZlibDecoder::new(pipe), mode)?,
+    };
+
+    Ok(tag)
+}
+
+pub fn write<W: Write>(pipe: W, tag: &NamedTag, compression: Compression) -> Result<()> {
+    let mode = Mode::default();
+    let level = flate2::Compression::default();
+
+    match compression {
+        Compression::None => binserde::serialize_with_into(pipe, tag, mode)?,
+        Compression::Gzip => {
+            let mut pipe = GzEncoder::new(pipe, level);
+            binserde::serialize_with_into(&mut pipe, tag, mode)?;
+            pipe.finish()?;
+        }
+        Compression::Zlib => {
+            let mut pipe = ZlibEncoder::new(pipe, level);
+            binserde::serialize_with_into(&mut pipe, tag, mode)?;
+            pipe.finish()?;
+        }
+    }
+
+    Ok(())
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    BinSerde(#[from] binserde::Error),
+    #[error("syntax error at position {pos}: {msg}")]
+    Syntax { pos: usize, msg: String },
+    #[error("expected {expected}, found {found}")]
+    TypeMismatch { expected: TagType, found: TagType },
+    #[error("missing key `{0}`")]
+    MissingKey(String),
+}
+
+#[test]
+fn binary_round_trip() {
+    let mut tag: Tag = r#"{
+        list: [[1, 2], []],
+        compounds: [{a: 1b}, {}],
+        empty: [],
+        bytes: [B; 1b, -1b],
+        ints: [I; 1, -1],
+        longs: [L; 1L],
+        nums: {s: 1s, l: 2L, f: 0.5f, d: 0.25d}
+    }"#
+    .parse()
+    .unwrap();
+    let name = Tag::String("a\0\u{10000}".to_string());
+    tag.as_compound_mut()
+        .unwrap()
+        .insert("name".to_string(), name);
+    let root = NamedTag::new("root", tag);
+
+    for &compression in [Compression::None, Compression::Gzip, Compression::Zlib].iter() {
+        let mut buf = Vec::new();
+        write(&mut buf, &root, compression).unwrap();
+        assert_eq!(compression, Compression::detect(&buf));
+        assert_eq!(root, read(&*buf).unwrap());
+    }
+
+    let buf = binserde::serialize(&NamedTag::new("hi", Tag::Short(0x1234))).unwrap();
+    assert_eq!(&[2, 0, 2, b'h', b'i', 0x12, 0x34], &*buf);
+
+    // nameless tags, as sent over the network
+    let buf = binserde::serialize(&Tag::List(vec![Tag::Int(1)])).unwrap();
+    assert_eq!(&[9, 3, 0, 0, 0, 1, 0, 0, 0, 1], &*buf);
+    assert_eq!(
+        Tag::List(vec![Tag::Int(1)]),
+        binserde::deserialize(&buf).unwrap()
+    );
+}
+
+#[test]
+fn binary_invalid() {
+    let mixed = NamedTag::new("", Tag::List(vec![Tag::Int(1), Tag::Byte(1)]));
+    assert!(write(Vec::new(), &mixed, Compression::None).is_err());
+
+    // invalid tag type, negative length, unterminated compound
+    assert!(read(&[13, 0, 0][..]).is_err());
+    assert!(read(&[7, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF][..]).is_err());
+    assert!(read(&[10, 0, 0, 1, 0, 1, b'a', 5][..]).is_err());
+
+    let limits = Limits::none().with_max_depth(2);
+    let nested: Tag = "[[[1]]]".parse().unwrap();
+    let buf = binserde::serialize(&NamedTag::new("", nested)).unwrap();
+    assert!(matches!(
+        read_with(&*buf, Compression::None, limits),
+        Err(Error::BinSerde(binserde::Error::TooDeep(2)))
+    ));
+}
@@ -0,0 +1,94 @@
+//! Java's modified UTF-8, which NBT uses for strings.
+//!
+//! It differs from UTF-8 in two ways: the nul character is written as the two
+//! bytes `C0 80` instead of a single zero byte, and characters outside of the
+//! basic multilingual plane are split into a UTF-16 surrogate pair, each half
+//! of which is written as three bytes. Most strings don't contain either of
+//! these, in which case no conversion is needed.
+
+use std::borrow::Cow;
+
+pub fn encode(s: &str) -> Cow<'_, [u8]> {
+    // 4 byte sequences start with 0xF0 or higher
+    if s.bytes().all(|b| b != 0 && b < 0xF0) {
+        return Cow::Borrowed(s.as_bytes());
+    }
+
+    let mut buf = Vec::with_capacity(s.len() + 2);
+
+    for c in s.chars() {
+        if c == '\0' {
+            buf.extend_from_slice(&[0xC0, 0x80]);
+        } else if (c as u32) < 0x10000 {
+            buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        } else {
+            for &unit in c.encode_utf16(&mut [0; 2]).iter() {
+                buf.push(0xE0 | (unit >> 12) as u8);
+                buf.push(0x80 | (unit >> 6 & 0x3F) as u8);
+                buf.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+
+    Cow::Owned(buf)
+}
+
+/// Decodes `bytes`, returning `None` if it isn't valid modified UTF-8 or
+/// contains unpaired surrogates.
+pub fn decode(bytes: &[u8]) -> Option<Cow<'_, str>> {
+    // Java accepts plain zero bytes as well, so anything that is valid UTF-8
+    // apart from 4 byte sequences means the same in both encodings
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        if bytes.iter().all(|&b| b < 0xF0) {
+            return Some(Cow::Borrowed(s));
+        }
+    }
+
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i] as u16;
+
+        let (unit, len) = match b {
+            0x00..=0x7F => (b, 1),
+            0xC0..=0xDF => ((b & 0x1F) << 6 | cont(bytes, i + 1)?, 2),
+            0xE0..=0xEF => (
+                (b & 0x0F) << 12 | cont(bytes, i + 1)? << 6 | cont(bytes, i + 2)?,
+                3,
+            ),
+            _ => return None,
+        };
+
+        units.push(unit);
+        i += len;
+    }
+
+    String::from_utf16(&units).ok().map(Cow::Owned)
+}
+
+fn cont(bytes: &[u8], idx: usize) -> Option<u16> {
+    match bytes.get(idx) {
+        Some(&b) if b & 0xC0 == 0x80 => Some((b & 0x3F) as u16),
+        _ => None,
+    }
+}
+
+#[test]
+fn mutf8_round_trip() {
+    assert!(matches!(encode("abc"), Cow::Borrowed(_)));
+    assert!(matches!(decode(b"abc"), Some(Cow::Borrowed("abc"))));
+
+    let s = "a\0b\u{1F600}\u{E9}";
+    let encoded = encode(s);
+    assert_eq!(
+        &[b'a', 0xC0, 0x80, b'b', 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80, 0xC3, 0xA9],
+        &*encoded
+    );
+    assert_eq!(s, decode(&encoded).unwrap());
+
+    // plain 4 byte UTF-8 and lone surrogates are invalid
+    assert!(decode("\u{1F600}".as_bytes()).is_none());
+    assert!(decode(&[0xED, 0xA0, 0xBD]).is_none());
+    assert!(decode(&[0xC3]).is_none());
+}
@@ -0,0 +1,603 @@
+//! The stringified NBT format used in commands and data packs.
+//!
+//! [`Tag`] implements [`Display`] to print SNBT, compactly by default or
+//! indented when using the alternate flag (`{:#}`), and [`FromStr`] to parse
+//! it. Parsing follows the same rules as Minecraft: unquoted values are
+//! numbers if they look like one and strings otherwise, and `true`/`false`
+//! are bytes. Non-finite floating point numbers are written the way Java
+//! prints them (`NaNf`, `Infinityd`, `-Infinityf`), like Minecraft does, and
+//! are read back as numbers here, which Minecraft doesn't do.
+
+use std::fmt;
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+
+use crate::tag::{Compound, Tag, TagType};
+use crate::{Error, Result};
+
+/// The maximum nesting depth of compounds and lists when parsing, which is
+/// the same as Minecraft's.
+const MAX_DEPTH: usize = 512;
+
+const INDENT: &str = "    ";
+
+pub fn parse(s: &str) -> Result<Tag> {
+    let mut parser = Parser {
+        s,
+        pos: 0,
+        depth: 0,
+    };
+    let tag = parser.value()?;
+    parser.skip_whitespace();
+
+    if parser.pos != s.len() {
+        return Err(parser.error("trailing characters after value"));
+    }
+
+    Ok(tag)
+}
+
+impl FromStr for Tag {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse(s)
+    }
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let pretty = f.alternate();
+        write_tag(f, self, pretty, 0)
+    }
+}
+
+fn write_tag(f: &mut Formatter<'_>, tag: &Tag, pretty: bool, indent: usize) -> fmt::Result {
+    match tag {
+        Tag::Byte(v) => write!(f, "{}b", v),
+        Tag::Short(v) => write!(f, "{}s", v),
+        Tag::Int(v) => write!(f, "{}", v),
+        Tag::Long(v) => write!(f, "{}L", v),
+        Tag::Float(v) if !v.is_finite() => write!(f, "{}f", non_finite_name(*v as f64)),
+        Tag::Float(v) => write!(f, "{:?}f", v),
+        Tag::Double(v) if !v.is_finite() => write!(f, "{}d", non_finite_name(*v)),
+        Tag::Double(v) => write!(f, "{:?}d", v),
+        Tag::ByteArray(v) => write_array(f, "B", v.iter().map(|v| format!("{}b", v))),
+        Tag::String(v) => write_quoted(f, v),
+        Tag::List(v) => {
+            if v.is_empty() {
+                return f.write_str("[]");
+            }
+
+            f.write_char('[')?;
+
+            for (idx, el) in v.iter().enumerate() {
+                write_separator(f, idx, pretty, indent + 1)?;
+                write_tag(f, el, pretty, indent + 1)?;
+            }
+
+            write_end(f, ']', pretty, indent)
+        }
+        Tag::Compound(v) => write_compound(f, v, pretty, indent),
+        Tag::IntArray(v) => write_array(f, "I", v.iter().map(|v| v.to_string())),
+        Tag::LongArray(v) => write_array(f, "L", v.iter().map(|v| format!("{}L", v))),
+    }
+}
+
+/// Returns how Java prints a non-finite number.
+fn non_finite_name(v: f64) -> &'static str {
+    if v.is_nan() {
+        "NaN"
+    } else if v > 0.0 {
+        "Infinity"
+    } else {
+        "-Infinity"
+    }
+}
+
+fn write_compound(f: &mut Formatter<'_>, c: &Compound, pretty: bool, indent: usize) -> fmt::Result {
+    if c.is_empty() {
+        return f.write_str("{}");
+    }
+
+    f.write_char('{')?;
+
+    for (idx, (key, el)) in c.iter().enumerate() {
+        write_separator(f, idx, pretty, indent + 1)?;
+
+        if is_unquoted(key) {
+            f.write_str(key)?;
+        } else {
+            write_quoted(f, key)?;
+        }
+
+        f.write_str(if pretty { ": " } else { ":" })?;
+        write_tag(f, el, pretty, indent + 1)?;
+    }
+
+    write_end(f, '}', pretty, indent)
+}
+
+fn write_array<I>(f: &mut Formatter<'_>, prefix: &str, items: I) -> fmt::Result
+where
+    I: Iterator<Item = String>,
+{
+    write!(f, "[{};", prefix)?;
+
+    for (idx, el) in items.enumerate() {
+        if idx > 0 {
+            f.write_char(',')?;
+        }
+
+        f.write_str(&el)?;
+    }
+
+    f.write_char(']')
+}
+
+/// Writes what comes before the element at `idx` of a list or compound.
+fn write_separator(f: &mut Formatter<'_>, idx: usize, pretty: bool, indent: usize) -> fmt::Result {
+    if idx > 0 {
+        f.write_char(',')?;
+    }
+
+    if pretty {
+        f.write_char('\n')?;
+
+        for _ in 0..indent {
+            f.write_str(INDENT)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_end(f: &mut Formatter<'_>, end: char, pretty: bool, indent: usize) -> fmt::Result {
+    if pretty {
+        f.write_char('\n')?;
+
+        for _ in 0..indent {
+            f.write_str(INDENT)?;
+        }
+    }
+
+    f.write_char(end)
+}
+
+fn write_quoted(f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+    let quote = if s.contains('"') && !s.contains('\'') {
+        '\''
+    } else {
+        '"'
+    };
+
+    f.write_char(quote)?;
+
+    for c in s.chars() {
+        if c == quote || c == '\\' {
+            f.write_char('\\')?;
+        }
+
+        f.write_char(c)?;
+    }
+
+    f.write_char(quote)
+}
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+fn is_unquoted(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(is_unquoted_char)
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error<S: Into<String>>(&self, msg: S) -> Error {
+        Error::Syntax {
+            pos: self.pos,
+            msg: msg.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            _ => Err(self.error(format!("expected '{}'", expected))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Tag> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => self.nested(Parser::compound),
+            Some('[') => self.nested(Parser::list_or_array),
+            Some('"') | Some('\'') => Ok(Tag::String(self.quoted()?)),
+            Some(_) => {
+                let s = self.unquoted();
+
+                if s.is_empty() {
+                    return Err(self.error("expected value"));
+                }
+
+                Ok(unquoted_value(s))
+            }
+            None => Err(self.error("expected value")),
+        }
+    }
+
+    fn nested<F>(&mut self, op: F) -> Result<Tag>
+    where
+        F: FnOnce(&mut Self) -> Result<Tag>,
+    {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(format!("nesting depth exceeds limit of {}", MAX_DEPTH)));
+        }
+
+        self.depth += 1;
+        let result = op(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn compound(&mut self) -> Result<Tag> {
+        self.expect('{')?;
+        let mut compound = Compound::new();
+        self.skip_whitespace();
+
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Tag::Compound(compound));
+        }
+
+        loop {
+            let key = self.key()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            compound.insert(key, value);
+
+            if !self.list_separator('}')? {
+                break;
+            }
+        }
+
+        Ok(Tag::Compound(compound))
+    }
+
+    fn key(&mut self) -> Result<String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('"') | Some('\'') => self.quoted(),
+            _ => {
+                let key = self.unquoted();
+
+                if key.is_empty() {
+                    return Err(self.error("expected key"));
+                }
+
+                Ok(key.to_string())
+            }
+        }
+    }
+
+    /// Reads the separator after an element of a list or compound. Returns
+    /// whether another element follows.
+    fn list_separator(&mut self, end: char) -> Result<bool> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(',') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(c) if c == end => {
+                self.pos += 1;
+                Ok(false)
+            }
+            _ => Err(self.error(format!("expected ',' or '{}'", end))),
+        }
+    }
+
+    fn list_or_array(&mut self) -> Result<Tag> {
+        self.expect('[')?;
+
+        let rest = &self.s[self.pos..];
+        let array_type = match rest.get(..2) {
+            Some("B;") => Some(TagType::Byte),
+            Some("I;") => Some(TagType::Int),
+            Some("L;") => Some(TagType::Long),
+            _ => None,
+        };
+
+        let elements = match array_type {
+            Some(_) => {
+                self.pos += 2;
+                self.elements()?
+            }
+            None => self.elements()?,
+        };
+
+        match array_type {
+            None => {
+                if let Some(first) = elements.first() {
+                    let ty = first.tag_type();
+
+                    if elements.iter().any(|el| el.tag_type() != ty) {
+                        return Err(self.error("list contains elements of different types"));
+                    }
+                }
+
+                Ok(Tag::List(elements))
+            }
+            Some(ty) => {
+                let wrong_type = || self.error(format!("expected only {} elements in array", ty));
+
+                match ty {
+                    TagType::Byte => elements
+                        .into_iter()
+                        .map(|el| match el {
+                            Tag::Byte(v) => Ok(v),
+                            _ => Err(wrong_type()),
+                        })
+                        .collect::<Result<_>>()
+                        .map(Tag::ByteArray),
+                    TagType::Int => elements
+                        .into_iter()
+                        .map(|el| match el {
+                            Tag::Int(v) => Ok(v),
+                            _ => Err(wrong_type()),
+                        })
+                        .collect::<Result<_>>()
+                        .map(Tag::IntArray),
+                    _ => elements
+                        .into_iter()
+                        .map(|el| match el {
+                            Tag::Long(v) => Ok(v),
+                            _ => Err(wrong_type()),
+                        })
+                        .collect::<Result<_>>()
+                        .map(Tag::LongArray),
+                }
+            }
+        }
+    }
+
+    /// Reads the elements of a list or array, up to and including the closing
+    /// bracket.
+    fn elements(&mut self) -> Result<Vec<Tag>> {
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(elements);
+        }
+
+        loop {
+            elements.push(self.value()?);
+
+            if !self.list_separator(']')? {
+                break;
+            }
+        }
+
+        Ok(elements)
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let quote = self.next().expect("quoted string without quote");
+        let mut s = String::new();
+
+        loop {
+            match self.next() {
+                None => return Err(self.error("unterminated string")),
+                Some('\\') => match self.next() {
+                    Some(c) if c == '\\' || c == '"' || c == '\'' => s.push(c),
+                    _ => return Err(self.error("invalid escape sequence")),
+                },
+                Some(c) if c == quote => break,
+                Some(c) => s.push(c),
+            }
+        }
+
+        Ok(s)
+    }
+
+    fn unquoted(&mut self) -> &str {
+        let start = self.pos;
+
+        while let Some(c) = self.peek() {
+            if !is_unquoted_char(c) {
+                break;
+            }
+
+            self.pos += 1;
+        }
+
+        &self.s[start..self.pos]
+    }
+}
+
+/// Figures out the type of an unquoted value, which is a string unless it
+/// looks like a number or boolean.
+fn unquoted_value(s: &str) -> Tag {
+    match s {
+        "true" => return Tag::Byte(1),
+        "false" => return Tag::Byte(0),
+        _ => {}
+    }
+
+    let (body, suffix) = s.split_at(s.len() - 1);
+    let parsed = match suffix {
+        "b" | "B" if is_int(body) => body.parse().ok().map(Tag::Byte),
+        "s" | "S" if is_int(body) => body.parse().ok().map(Tag::Short),
+        "l" | "L" if is_int(body) => body.parse().ok().map(Tag::Long),
+        "f" | "F" if is_float(body) => body.parse().ok().map(Tag::Float),
+        "d" | "D" if is_float(body) => body.parse().ok().map(Tag::Double),
+        "f" | "F" => non_finite(body).map(|v| Tag::Float(v as f32)),
+        "d" | "D" => non_finite(body).map(Tag::Double),
+        _ if is_int(s) => s.parse().ok().map(Tag::Int),
+        _ if is_float(s) && s.contains(['.', 'e', 'E']) => s.parse().ok().map(Tag::Double),
+        _ => None,
+    };
+
+    parsed.unwrap_or_else(|| Tag::String(s.to_string()))
+}
+
+/// Parses the names [`non_finite_name`] returns.
+fn non_finite(s: &str) -> Option<f64> {
+    match s {
+        "NaN" => Some(f64::NAN),
+        "Infinity" | "+Infinity" => Some(f64::INFINITY),
+        "-Infinity" => Some(f64::NEG_INFINITY),
+        _ => None,
+    }
+}
+
+fn strip_sign(s: &str) -> &str {
+    s.strip_prefix(|c| c == '+' || c == '-').unwrap_or(s)
+}
+
+fn is_int(s: &str) -> bool {
+    let s = strip_sign(s);
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_float(s: &str) -> bool {
+    let s = strip_sign(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(idx) => (&s[..idx], Some(&s[idx + 1..])),
+        None => (s, None),
+    };
+
+    let mut parts = mantissa.splitn(2, '.');
+    let int = parts.next().unwrap_or("");
+    let frac = parts.next().unwrap_or("");
+    let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+
+    digits(int)
+        && digits(frac)
+        && !(int.is_empty() && frac.is_empty())
+        && exponent.is_none_or(is_int)
+}
+
+#[test]
+fn snbt_round_trip() {
+    let s = r#"{name:"a \"b\"",'x y':[1,2,3],list:[{},{a:1b}],bytes:[B;1b,-2b],ints:[I;],longs:[L;5L],n:{f:1.5f,d:-2.0d,s:3s,l:4L}}"#;
+    let tag: Tag = s.parse().unwrap();
+
+    let c = tag.as_compound().unwrap();
+    assert_eq!(Some("a \"b\""), c["name"].as_str());
+    assert_eq!(
+        Tag::List(vec![Tag::Int(1), Tag::Int(2), Tag::Int(3)]),
+        c["x y"]
+    );
+    assert_eq!(Tag::ByteArray(vec![1, -2]), c["bytes"]);
+    assert_eq!(Tag::IntArray(vec![]), c["ints"]);
+    assert_eq!(Tag::LongArray(vec![5]), c["longs"]);
+    let n = c["n"].as_compound().unwrap();
+    assert_eq!(Tag::Float(1.5), n["f"]);
+    assert_eq!(Tag::Double(-2.0), n["d"]);
+    assert_eq!(Tag::Short(3), n["s"]);
+    assert_eq!(Tag::Long(4), n["l"]);
+
+    assert_eq!(
+        r#"{bytes:[B;1b,-2b],ints:[I;],list:[{},{a:1b}],longs:[L;5L],n:{d:-2.0d,f:1.5f,l:4L,s:3s},name:'a "b"',"x y":[1,2,3]}"#,
+        tag.to_string()
+    );
+    assert_eq!(tag, tag.to_string().parse().unwrap());
+    assert_eq!(tag, format!("{:#}", tag).parse().unwrap());
+    assert_eq!(
+        "{\n    a: [\n        1b\n    ],\n    b: {}\n}",
+        format!("{:#}", parse("{a:[true],b:{}}").unwrap())
+    );
+}
+
+#[test]
+fn snbt_non_finite() {
+    let tag = Tag::List(vec![
+        Tag::Float(f32::INFINITY),
+        Tag::Float(f32::NEG_INFINITY),
+    ]);
+    assert_eq!("[Infinityf,-Infinityf]", tag.to_string());
+    assert_eq!(tag, tag.to_string().parse().unwrap());
+
+    let tag = Tag::Double(f64::NEG_INFINITY);
+    assert_eq!(tag, tag.to_string().parse().unwrap());
+
+    match Tag::Float(f32::NAN).to_string().parse().unwrap() {
+        Tag::Float(v) => assert!(v.is_nan()),
+        tag => panic!("expected a float, got {:?}", tag),
+    }
+
+    match parse("NaNd").unwrap() {
+        Tag::Double(v) => assert!(v.is_nan()),
+        tag => panic!("expected a double, got {:?}", tag),
+    }
+
+    assert_eq!(Tag::String("NaN".to_string()), parse("NaN").unwrap());
+    assert_eq!(Tag::String("leaf".to_string()), parse("leaf").unwrap());
+    assert_eq!("\"NaNf\"", Tag::String("NaNf".to_string()).to_string());
+}
+
+#[test]
+fn snbt_unquoted() {
+    assert_eq!(Tag::Byte(1), parse("true").unwrap());
+    assert_eq!(Tag::Int(-5), parse(" -5 ").unwrap());
+    assert_eq!(Tag::Double(0.5), parse(".5").unwrap());
+    assert_eq!(Tag::Double(1e3), parse("1e3").unwrap());
+    assert_eq!(Tag::Float(2.0), parse("2F").unwrap());
+    assert_eq!(
+        Tag::String("minecraft:stone".to_string()),
+        parse("\"minecraft:stone\"").unwrap()
+    );
+    assert_eq!(Tag::String("1.2.3".to_string()), parse("1.2.3").unwrap());
+    assert_eq!(Tag::String("300b".to_string()), parse("300b").unwrap());
+    assert_eq!(
+        Tag::String("99999999999".to_string()),
+        parse("99999999999").unwrap()
+    );
+
+    assert!(matches!(parse("[1,2b]"), Err(Error::Syntax { pos: 6, .. })));
+    assert!(matches!(parse("[I;1,2b]"), Err(Error::Syntax { .. })));
+    assert!(matches!(parse("{a:1"), Err(Error::Syntax { pos: 4, .. })));
+    assert!(matches!(parse("{a 1}"), Err(Error::Syntax { pos: 3, .. })));
+    assert!(matches!(parse("1 2"), Err(Error::Syntax { pos: 2, .. })));
+    assert!(matches!(
+        parse(&"[".repeat(1000)),
+        Err(Error::Syntax { .. })
+    ));
+}
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// The contents of a compound tag.
+///
+/// This is a [`BTreeMap`], so the entries are kept sorted by their names
+/// instead of in the order they were read or inserted in. Reading and
+/// writing a compound can therefore change the order of its entries, which
+/// Minecraft doesn't depend on.
+pub type Compound = BTreeMap<String, Tag>;
+
+/// A single NBT value, possibly containing other values.
+///
+/// All elements of a [`Tag::List`] have to be of the same type, which is
+/// checked when writing or parsing it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn tag_type(&self) -> TagType {
+        match self {
+            Tag::Byte(_) => TagType::Byte,
+            Tag::Short(_) => TagType::Short,
+            Tag::Int(_) => TagType::Int,
+            Tag::Long(_) => TagType::Long,
+            Tag::Float(_) => TagType::Float,
+            Tag::Double(_) => TagType::Double,
+            Tag::ByteArray(_) => TagType::ByteArray,
+            Tag::String(_) => TagType::String,
+            Tag::List(_) => TagType::List,
+            Tag::Compound(_) => TagType::Compound,
+            Tag::IntArray(_) => TagType::IntArray,
+            Tag::LongArray(_) => TagType::LongArray,
+        }
+    }
+
+    /// Returns the value of any integer tag, widened to an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(v) => Some(*v as i64),
+            Tag::Short(v) => Some(*v as i64),
+            Tag::Int(v) => Some(*v as i64),
+            Tag::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of any numeric tag as an `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Tag::Float(v) => Some(*v as f64),
+            Tag::Double(v) => Some(*v),
+            _ => self.as_i64().map(|v| v as f64),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound_mut(&mut self) -> Option<&mut Compound> {
+        match self {
+            Tag::Compound(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of the elements of a list, or `None` if this isn't a
+    /// list or the list contains elements of different types. Empty lists
+    /// have the element type [`TagType::End`].
+    pub fn list_type(&self) -> Option<TagType> {
+        let list = self.as_list()?;
+
+        match list.first() {
+            None => Some(TagType::End),
+            Some(first) => {
+                let ty = first.tag_type();
+
+                if list.iter().all(|el| el.tag_type() == ty) {
+                    Some(ty)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($type:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$type> for Tag {
+                fn from(v: $type) -> Self {
+                    Tag::$variant(v)
+                }
+            }
+        )*
+    };
+}
+
+impl_from! {
+    i8 => Byte,
+    i16 => Short,
+    i32 => Int,
+    i64 => Long,
+    f32 => Float,
+    f64 => Double,
+    Vec<i8> => ByteArray,
+    String => String,
+    Vec<Tag> => List,
+    Compound => Compound,
+    Vec<i32> => IntArray,
+    Vec<i64> => LongArray,
+}
+
+impl From<&str> for Tag {
+    fn from(v: &str) -> Self {
+        Tag::String(v.to_string())
+    }
+}
+
+impl From<bool> for Tag {
+    fn from(v: bool) -> Self {
+        Tag::Byte(v as i8)
+    }
+}
+
+/// A tag together with its name, as found at the root of an NBT file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedTag {
+    pub name: String,
+    pub tag: Tag,
+}
+
+impl NamedTag {
+    pub fn new<S: Into<String>>(name: S, tag: Tag) -> Self {
+        NamedTag {
+            name: name.into(),
+            tag,
+        }
+    }
+}
+
+/// The type of a tag, as identified by its ID in the binary format.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum TagType {
+    End = 0,
+    Byte = 1,
+    Short = 2,
+    Int = 3,
+    Long = 4,
+    Float = 5,
+    Double = 6,
+    ByteArray = 7,
+    String = 8,
+    List = 9,
+    Compound = 10,
+    IntArray = 11,
+    LongArray = 12,
+}
+
+impl TagType {
+    pub fn from_id(id: u8) -> Option<Self> {
+        let ty = match id {
+            0 => TagType::End,
+            1 => TagType::Byte,
+            2 => TagType::Short,
+            3 => TagType::Int,
+            4 => TagType::Long,
+            5 => TagType::Float,
+            6 => TagType::Double,
+            7 => TagType::ByteArray,
+            8 => TagType::String,
+            9 => TagType::List,
+            10 => TagType::Compound,
+            11 => TagType::IntArray,
+            12 => TagType::LongArray,
+            _ => return None,
+        };
+
+        Some(ty)
+    }
+
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+}
+
+impl Display for TagType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            TagType::End => "end",
+            TagType::Byte => "byte",
+            TagType::Short => "short",
+            TagType::Int => "int",
+            TagType::Long => "long",
+            TagType::Float => "float",
+            TagType::Double => "double",
+            TagType::ByteArray => "byte array",
+            TagType::String => "string",
+            TagType::List => "list",
+            TagType::Compound => "compound",
+            TagType::IntArray => "int array",
+            TagType::LongArray => "long array",
+        };
+
+        f.write_str(s)
+    }
+}